@@ -0,0 +1,63 @@
+//! Subsets and combinations as bit masks, against the iterators of slices
+//! which push and pop indices in vectors.
+//!
+//! Run with `cargo bench --bench bits`.
+
+extern crate combinatorust;
+
+use std::hint::black_box;
+use std::time::Instant;
+use combinatorust::bits::{self, Submasks, MaskCombinations};
+use combinatorust::iter::*;
+
+// Time f, which returns the number of items it went through
+fn time<F: FnMut() -> usize>(name: &str, mut f: F) -> f64 {
+    let start = Instant::now();
+    let items = f();
+    let ns = start.elapsed().as_nanos() as f64 / items as f64;
+    println!("{:<40} {:>10} items {:>8.2} ns/item", name, items, ns);
+    ns
+}
+
+fn main() {
+    let n = 22;
+    let v = (0..n).collect::<Vec<usize>>();
+    let slow = time(&format!("subsequences({})", n), || {
+        let mut s = v.subsequences();
+        let mut count = 0;
+        while let Some(x) = s.next() { black_box(x); count += 1 }
+        count
+    });
+    let fast = time(&format!("Submasks::of_size({})", n), || {
+        let mut count = 0;
+        for m in Submasks::of_size(n) { black_box(m); count += 1 }
+        count
+    });
+    let mut dest = Vec::new();
+    let fast_select = time(&format!("Submasks::of_size({}) + select_into", n), || {
+        let mut count = 0;
+        for m in Submasks::of_size(n) {
+            bits::select_into(m, &v, &mut dest);
+            black_box(&dest);
+            count += 1;
+        }
+        count
+    });
+    println!("speedup: {:.1}x, {:.1}x with select_into\n", slow / fast, slow / fast_select);
+
+    for &(n, k) in &[(30, 5), (40, 4), (64, 4)] {
+        let v = (0..n).collect::<Vec<usize>>();
+        let slow = time(&format!("combinations({}, {})", n, k), || {
+            let mut c = v.combinations(k);
+            let mut count = 0;
+            while let Some(x) = c.next() { black_box(x); count += 1 }
+            count
+        });
+        let fast = time(&format!("MaskCombinations::new({}, {})", n, k), || {
+            let mut count = 0;
+            for m in MaskCombinations::new(n, k) { black_box(m); count += 1 }
+            count
+        });
+        println!("speedup: {:.1}x\n", slow / fast);
+    }
+}
@@ -0,0 +1,92 @@
+//! Combinations in revolving door order, where each step clones at most two
+//! elements, against lexicographic order, where a step clones up to `k`.
+//!
+//! Run with `cargo bench --bench combinations`.
+
+extern crate combinatorust;
+
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use combinatorust::iter::*;
+
+static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+// An element which counts its clones
+#[derive(Debug)]
+struct Counted(String);
+
+impl Clone for Counted {
+    fn clone(&self) -> Counted {
+        CLONES.fetch_add(1, Ordering::Relaxed);
+        Counted(self.0.clone())
+    }
+}
+
+// The average and the maximum number of elements cloned by a step, after
+// the first one
+fn clones<C>(mut c: C) -> (f64, usize) where
+    C: StreamingIterator<Item = [Counted]>
+{
+    c.next();
+    let (mut steps, mut total, mut max) = (0, 0, 0);
+    loop {
+        CLONES.store(0, Ordering::Relaxed);
+        if c.next().is_none() { break }
+        let m = CLONES.load(Ordering::Relaxed);
+        steps += 1;
+        total += m;
+        max = std::cmp::max(max, m);
+    }
+    (total as f64 / steps as f64, max)
+}
+
+// Time f, which returns the number of items it went through
+fn time<F: FnMut() -> usize>(name: &str, mut f: F) -> f64 {
+    let start = Instant::now();
+    let items = f();
+    let ns = start.elapsed().as_nanos() as f64 / items as f64;
+    println!("{:<40} {:>10} items {:>8.2} ns/item", name, items, ns);
+    ns
+}
+
+fn main() {
+    for &(n, k) in &[(30, 6), (35, 5), (40, 5)] {
+        let slow = time(&format!("IndexCombinations::new({}, {})", n, k), || {
+            let mut c = IndexCombinations::new(n, k);
+            let mut count = 0;
+            while let Some(x) = c.next() { black_box(x); count += 1 }
+            count
+        });
+        let fast = time(&format!("GrayCombinations::new({}, {})", n, k), || {
+            let mut c = GrayCombinations::new(n, k);
+            let mut count = 0;
+            while let Some(x) = c.next() { black_box(x); count += 1 }
+            count
+        });
+        println!("speedup: {:.1}x\n", slow / fast);
+
+        // Elements which are expensive to clone
+        let v = (0..n).map(|i| { format!("element {}", i) }).collect::<Vec<String>>();
+        let slow = time(&format!("lex_combinations({}, {})", n, k), || {
+            let mut c = v.lex_combinations(k);
+            let mut count = 0;
+            while let Some(x) = c.next() { black_box(x); count += 1 }
+            count
+        });
+        let fast = time(&format!("combinations({}, {})", n, k), || {
+            let mut c = v.combinations(k);
+            let mut count = 0;
+            while let Some(x) = c.next() { black_box(x); count += 1 }
+            count
+        });
+        println!("speedup: {:.1}x\n", slow / fast);
+
+        let v = (0..n).map(|i| { Counted(format!("element {}", i)) }).collect::<Vec<_>>();
+        let (lex, lex_max) = clones(v.lex_combinations(k));
+        let (gray, gray_max) = clones(v.combinations(k));
+        println!("clones per step: {:.2} (at most {}) in lexicographic order, \
+                  {:.2} (at most {}) in revolving door order\n",
+                 lex, lex_max, gray, gray_max);
+    }
+}
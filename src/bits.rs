@@ -0,0 +1,231 @@
+//! Subsets and combinations of small sets, as bit masks
+//!
+//! For sets with at most 64 elements, subsets can be represented by the bits
+//! of a `u64`, and enumerated with a few arithmetic operations per step.
+//! The elements are only materialized on demand, with `mask_indices` or
+//! `select_into`.
+
+use std::*;
+use error::{Error, Result};
+
+/// The mask of the set `0..n`.
+pub fn full_mask(n: usize) -> u64 {
+    assert!(n <= 64, "Masks can only represent sets with up to 64 elements");
+    if n == 64 { !0 } else { (1 << n) - 1 }
+}
+
+/// An iterator over the submasks of a mask, in increasing order.
+///
+/// There are `2^m` submasks, where `m` is the number of bits set in the mask.
+#[derive(Clone, Debug)]
+pub struct Submasks {
+    mask: u64,
+    cur: u64,
+    done: bool,
+}
+
+impl Submasks {
+    pub fn new(mask: u64) -> Submasks {
+        Submasks { mask: mask, cur: 0, done: false }
+    }
+
+    /// Subsets of `0..n`.
+    pub fn of_size(n: usize) -> Submasks {
+        Submasks::new(full_mask(n))
+    }
+}
+
+impl Iterator for Submasks {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        if self.done { return None }
+        let x = self.cur;
+        if x == self.mask {
+            self.done = true;
+        } else {
+            // Propagate the carry through the bits outside of the mask
+            self.cur = (x | !self.mask).wrapping_add(1) & self.mask;
+        }
+        Some(x)
+    }
+}
+
+/// An iterator over the masks with `k` bits set among the `n` lowest ones,
+/// in increasing order (Gosper's hack).
+#[derive(Clone, Debug)]
+// full is the mask of 0..n.
+pub struct MaskCombinations {
+    full: u64,
+    cur: Option<u64>,
+}
+
+impl MaskCombinations {
+    /// # Panics
+    ///
+    /// If `k > n` or `n > 64`.
+    pub fn new(n: usize, k: usize) -> MaskCombinations {
+        assert!(n <= 64, "Masks can only represent sets with up to 64 elements");
+        assert!(k <= n, "Cannot choose {} elements out of {}", k, n);
+        MaskCombinations { full: full_mask(n), cur: Some(full_mask(k)) }
+    }
+
+    /// Like `new`, or an error if `k > n` or `n > 64`.
+    pub fn try_new(n: usize, k: usize) -> Result<MaskCombinations> {
+        if n > 64 { return Err(Error::TooLarge { n: n, max: 64 }) }
+        if k > n { return Err(Error::InvalidK { n: n, k: k }) }
+        Ok(MaskCombinations::new(n, k))
+    }
+}
+
+impl Iterator for MaskCombinations {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        let x = self.cur?;
+        self.cur = if x == 0 {
+            None
+        } else {
+            let c = x & x.wrapping_neg();
+            match x.checked_add(c) {
+                None => None,
+                Some(r) => {
+                    // Shifting is faster than dividing by c
+                    let y = (((r ^ x) >> 2) >> c.trailing_zeros()) | r;
+                    if y & !self.full != 0 { None } else { Some(y) }
+                }
+            }
+        };
+        Some(x)
+    }
+}
+
+/// An iterator over the subsets of `0..n` as masks, in the order of the
+/// binary reflected Gray code: consecutive masks differ by one bit.
+///
+/// With `transitions`, only the positions of the flipped bits are yielded.
+// i counts the steps: the current mask is i ^ (i >> 1).
+#[derive(Clone, Debug)]
+pub struct GrayMasks {
+    n: usize,
+    i: u64,
+    first: bool,
+    done: bool,
+}
+
+impl GrayMasks {
+    pub fn new(n: usize) -> GrayMasks {
+        assert!(n <= 64, "Masks can only represent sets with up to 64 elements");
+        GrayMasks { n: n, i: 0, first: true, done: false }
+    }
+
+    /// The positions of the bits flipped from one mask to the next, starting
+    /// from the empty mask.
+    pub fn transitions(self) -> GrayFlips {
+        GrayFlips { masks: self }
+    }
+
+    // Move to the next step, returning false if there is none.
+    fn advance(&mut self) -> bool {
+        if self.done { return false }
+        if self.first { self.first = false; return true }
+        if self.i == full_mask(self.n) { self.done = true; return false }
+        self.i += 1;
+        true
+    }
+}
+
+impl Iterator for GrayMasks {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        if self.advance() { Some(self.i ^ self.i >> 1) } else { None }
+    }
+}
+
+/// An iterator over the positions of the bits flipped by the Gray code,
+/// see `GrayMasks::transitions`.
+#[derive(Clone, Debug)]
+pub struct GrayFlips {
+    masks: GrayMasks,
+}
+
+impl Iterator for GrayFlips {
+    type Item = usize;
+    fn next(&mut self) -> Option<usize> {
+        // Skip the initial mask
+        if self.masks.first { self.masks.advance(); }
+        if self.masks.advance() {
+            // The bit flipped at step i is the lowest bit set in i
+            Some(self.masks.i.trailing_zeros() as usize)
+        } else {
+            None
+        }
+    }
+}
+
+/// An iterator over the positions of the bits set in a mask,
+/// in increasing order.
+#[derive(Clone, Debug)]
+pub struct MaskIndices {
+    mask: u64,
+}
+
+impl Iterator for MaskIndices {
+    type Item = usize;
+    fn next(&mut self) -> Option<usize> {
+        if self.mask == 0 { return None }
+        let i = self.mask.trailing_zeros() as usize;
+        self.mask &= self.mask - 1;
+        Some(i)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.mask.count_ones() as usize;
+        (l, Some(l))
+    }
+}
+
+/// The positions of the bits set in `mask`.
+pub fn mask_indices(mask: u64) -> MaskIndices {
+    MaskIndices { mask: mask }
+}
+
+/// Write into `dest` the elements of `src` selected by `mask`,
+/// replacing its contents.
+pub fn select_into<T: Clone>(mask: u64, src: &[T], dest: &mut Vec<T>) {
+    dest.clear();
+    dest.extend(mask_indices(mask).map(|i| { src[i].clone() }));
+}
+
+/// The mask of a set of elements of `0..64`.
+///
+/// # Panics
+///
+/// If an element is not less than 64.
+pub fn indices_mask(indices: &[usize]) -> u64 {
+    indices.iter().fold(0, |m, &i| {
+        assert!(i < 64, "Masks can only represent sets with up to 64 elements");
+        m | 1 << i
+    })
+}
+
+/// Format a subset of `0..n` as a string of `n` bits, the bit of `0` first,
+/// e.g., `0110` for `{1, 2}` with `n = 4`.
+pub fn bitstring(mask: u64, n: usize) -> Bitstring {
+    assert!(n <= 64, "Masks can only represent sets with up to 64 elements");
+    Bitstring { mask: mask, n: n }
+}
+
+/// A subset formatted as a string of bits, see `bitstring`.
+#[derive(Clone, Copy, Debug)]
+pub struct Bitstring {
+    mask: u64,
+    n: usize,
+}
+
+impl fmt::Display for Bitstring {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for i in 0..self.n {
+            write!(f, "{}", self.mask >> i & 1)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+//! Boltzmann samplers, with the `rand` feature
+//!
+//! A Boltzmann sampler of parameter `x` draws an object of size `n` of a
+//! combinatorial class `C` with probability `x^n / C(x)`, so that objects of
+//! the same size are equally likely. Rejecting the objects whose size is
+//! outside of a window gives approximately-uniform random objects of sizes
+//! far beyond the reach of exhaustive enumeration (Duchon, Flajolet, Louchard
+//! and Schaeffer, 2004).
+//!
+//! Classes are specified by a `Grammar` of unlabelled constructions. For
+//! example, binary trees counted by internal nodes are `B = 1 + Z × B × B`,
+//! i.e., the rule `Union(vec![Empty, Product(vec![Atom, Ref(0), Ref(0)])])`,
+//! and compositions are `Sequence(Product(vec![Atom, Sequence(Atom)]))`.
+
+use std::*;
+use rand::Rng;
+use rand::distributions::Distribution;
+
+/// A construction of a combinatorial class. The size of an object is its
+/// number of atoms.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Spec {
+    /// The class of a single object of size 0.
+    Empty,
+    /// The class of a single object of size 1.
+    Atom,
+    /// Disjoint union.
+    Union(Vec<Spec>),
+    /// Cartesian product: tuples of objects, their sizes adding up.
+    Product(Vec<Spec>),
+    /// Finite sequences of objects, which must all have positive size.
+    Sequence(Box<Spec>),
+    /// The class defined by the rule of the given index in the grammar, for
+    /// recursive specifications.
+    Ref(usize),
+}
+
+/// An object drawn by a Boltzmann sampler: the derivation of its `Spec`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Derivation {
+    Empty,
+    Atom,
+    /// The index of the chosen alternative of a `Union`, and its object.
+    Choice(usize, Box<Derivation>),
+    /// The components of a `Product`.
+    Tuple(Vec<Derivation>),
+    /// The components of a `Sequence`.
+    Sequence(Vec<Derivation>),
+}
+
+impl Derivation {
+    /// The number of atoms.
+    pub fn size(&self) -> usize {
+        match *self {
+            Derivation::Empty => 0,
+            Derivation::Atom => 1,
+            Derivation::Choice(_, ref d) => d.size(),
+            Derivation::Tuple(ref ds) | Derivation::Sequence(ref ds) => {
+                ds.iter().map(|d| { d.size() }).sum()
+            }
+        }
+    }
+}
+
+/// A system of mutually recursive specifications, referring to each other
+/// with `Spec::Ref`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grammar {
+    rules: Vec<Spec>,
+}
+
+impl Grammar {
+    /// # Panics
+    ///
+    /// If a `Spec::Ref` is out of range.
+    pub fn new(rules: Vec<Spec>) -> Grammar {
+        fn check(spec: &Spec, n: usize) {
+            match *spec {
+                Spec::Empty | Spec::Atom => (),
+                Spec::Union(ref ss) | Spec::Product(ref ss) => {
+                    for s in ss { check(s, n) }
+                }
+                Spec::Sequence(ref s) => check(s, n),
+                Spec::Ref(i) => assert!(i < n, "Undefined rule {}", i),
+            }
+        }
+        for s in &rules { check(s, rules.len()) }
+        Grammar { rules: rules }
+    }
+
+    pub fn rules(&self) -> &[Spec] {
+        &self.rules
+    }
+
+    /// The values of the generating functions of the rules at `x`, as the
+    /// limit of the iteration of the system from 0, or `None` if it
+    /// diverges, i.e., `x` is beyond the radius of convergence.
+    pub fn values(&self, x: f64) -> Option<Vec<f64>> {
+        let mut values = vec![0.0; self.rules.len()];
+        for _ in 0..ITERATIONS {
+            let next = self.rules.iter()
+                .map(|s| { eval(s, x, &values) })
+                .collect::<Option<Vec<f64>>>()?;
+            if next.iter().any(|&v| { v.is_nan() || v >= DIVERGENCE }) { return None }
+            let converged = next.iter().zip(&values).all(|(&v, &w)| {
+                v - w <= PRECISION * v
+            });
+            values = next;
+            if converged { return Some(values) }
+        }
+        None
+    }
+
+    /// The radius of convergence of the generating function of `rule`, at
+    /// most 1: the largest `x` such that `values(x)` converges, up to
+    /// floating point precision.
+    pub fn singularity(&self, rule: usize) -> f64 {
+        let (mut lo, mut hi) = (0.0, 1.0);
+        for _ in 0..BISECTIONS {
+            let mid = (lo + hi) / 2.0;
+            let finite = self.values(mid).is_some_and(|v| { v[rule].is_finite() });
+            if finite { lo = mid } else { hi = mid }
+        }
+        lo
+    }
+
+    /// The expected size of the objects of `rule` drawn by the Boltzmann
+    /// sampler of parameter `x`: `x C'(x) / C(x)`, by numerical
+    /// differentiation.
+    pub fn expected_size(&self, rule: usize, x: f64) -> Option<f64> {
+        let h = x * 1e-6;
+        let lo = self.values(x - h)?[rule];
+        let hi = self.values(x + h)?[rule];
+        Some(x * (hi.ln() - lo.ln()) / (2.0 * h))
+    }
+}
+
+const ITERATIONS: usize = 10_000;
+const DIVERGENCE: f64 = 1e15;
+const PRECISION: f64 = 1e-12;
+const BISECTIONS: usize = 60;
+
+// The generating function of spec at x, given those of the rules
+fn eval(spec: &Spec, x: f64, values: &[f64]) -> Option<f64> {
+    match *spec {
+        Spec::Empty => Some(1.0),
+        Spec::Atom => Some(x),
+        Spec::Union(ref ss) => ss.iter().map(|s| { eval(s, x, values) }).sum(),
+        Spec::Product(ref ss) => ss.iter().map(|s| { eval(s, x, values) }).product(),
+        Spec::Sequence(ref s) => {
+            let a = eval(s, x, values)?;
+            if a < 1.0 { Some(1.0 / (1.0 - a)) } else { None }
+        }
+        Spec::Ref(i) => Some(values[i]),
+    }
+}
+
+/// A Boltzmann sampler for a rule of a `Grammar`.
+///
+/// Sampling without bounds on the size (as a `Distribution`) may produce
+/// huge objects when `x` is close to the singularity; prefer
+/// `sample_sized`.
+#[derive(Clone, Debug)]
+pub struct Boltzmann {
+    grammar: Grammar,
+    root: usize,
+    x: f64,
+    values: Vec<f64>,
+}
+
+impl Boltzmann {
+    /// The sampler of parameter `x` for the rule `root`, or `None` if `x`
+    /// is beyond the radius of convergence.
+    pub fn new(grammar: Grammar, root: usize, x: f64) -> Option<Boltzmann> {
+        let values = grammar.values(x)?;
+        Some(Boltzmann { grammar: grammar, root: root, x: x, values: values })
+    }
+
+    /// The sampler whose expected size is `n`, or as close as possible,
+    /// with the parameter found by bisection below the singularity.
+    pub fn tuned(grammar: Grammar, root: usize, n: usize) -> Boltzmann {
+        let (mut lo, mut hi) = (0.0, grammar.singularity(root));
+        for _ in 0..BISECTIONS {
+            let mid = (lo + hi) / 2.0;
+            match grammar.expected_size(root, mid) {
+                Some(e) if e < n as f64 => lo = mid,
+                _ => hi = mid,
+            }
+        }
+        Boltzmann::new(grammar, root, lo).unwrap()
+    }
+
+    /// The parameter `x`.
+    pub fn parameter(&self) -> f64 {
+        self.x
+    }
+
+    /// Draw objects until one has a size between `min` and `max` included.
+    /// The objects of the same size remain equally likely. Draws are
+    /// abandoned as soon as they exceed `max`.
+    ///
+    /// # Panics
+    ///
+    /// If `min > max`.
+    pub fn sample_sized<R: Rng + ?Sized>(&self, rng: &mut R, min: usize, max: usize) -> Derivation {
+        assert!(min <= max, "Empty size window");
+        loop {
+            let mut budget = max;
+            let root = Spec::Ref(self.root);
+            if let Some(d) = self.generate(&root, rng, &mut budget) {
+                if max - budget >= min { return d }
+            }
+        }
+    }
+
+    // Draw an object of spec, with at most budget atoms, decreasing it by
+    // the size of the object. None if the budget is exceeded.
+    fn generate<R: Rng + ?Sized>(&self, spec: &Spec, rng: &mut R, budget: &mut usize) -> Option<Derivation> {
+        let values = &self.values;
+        Some(match *spec {
+            Spec::Empty => Derivation::Empty,
+            Spec::Atom => {
+                if *budget == 0 { return None }
+                *budget -= 1;
+                Derivation::Atom
+            }
+            Spec::Union(ref ss) => {
+                let total = eval(spec, self.x, values).unwrap();
+                let mut u = rng.gen::<f64>() * total;
+                // The last alternative absorbs rounding errors
+                let mut i = ss.len() - 1;
+                for (j, s) in ss.iter().enumerate() {
+                    let v = eval(s, self.x, values).unwrap();
+                    if u < v { i = j; break }
+                    u -= v;
+                }
+                Derivation::Choice(i, Box::new(self.generate(&ss[i], rng, budget)?))
+            }
+            Spec::Product(ref ss) => {
+                let mut ds = Vec::with_capacity(ss.len());
+                for s in ss { ds.push(self.generate(s, rng, budget)?) }
+                Derivation::Tuple(ds)
+            }
+            Spec::Sequence(ref s) => {
+                // The length is geometric, of parameter A(x)
+                let a = eval(s, self.x, values).unwrap();
+                let mut ds = Vec::new();
+                while rng.gen::<f64>() < a { ds.push(self.generate(s, rng, budget)?) }
+                Derivation::Sequence(ds)
+            }
+            Spec::Ref(i) => return self.generate(&self.grammar.rules[i], rng, budget),
+        })
+    }
+}
+
+impl Distribution<Derivation> for Boltzmann {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Derivation {
+        let mut budget = usize::MAX;
+        self.generate(&Spec::Ref(self.root), rng, &mut budget).unwrap()
+    }
+}
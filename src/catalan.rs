@@ -0,0 +1,413 @@
+//! Families counted by the Catalan numbers, and the bijections between them
+//!
+//! Every family converts to and from the code of a binary tree yielded by
+//! `Catalan`, so a single enumeration, e.g., `Catalan::new(m + 1)`, can be
+//! viewed in any representation with `CatalanObject::from_code`.
+//!
+//! An object of size `m` has a code of length `m`, and there are
+//! `choose(2 * m, m) / (m + 1)` of them.
+
+use std::*;
+use permutation::{Permutation, contains_pattern};
+use error::Error;
+
+/// A family in bijection with binary trees.
+pub trait CatalanObject: Sized {
+    /// The object of the given code, in the encoding of `Catalan`, or `None`
+    /// if it is not the code of a binary tree: the labels must be
+    /// nondecreasing, with `code[i] <= i`.
+    fn from_code(code: &[usize]) -> Option<Self>;
+
+    /// The code of the object, in the encoding of `Catalan`.
+    fn to_code(&self) -> Vec<usize>;
+
+    /// The object of another family with the same code.
+    fn convert<U: CatalanObject>(&self) -> U {
+        U::from_code(&self.to_code()).unwrap()
+    }
+}
+
+/// Whether `code` is the code of a binary tree, in the encoding of `Catalan`.
+pub fn is_catalan_code(code: &[usize]) -> bool {
+    code.iter().enumerate().all(|(i, &c)| { c <= i && (i == 0 || code[i - 1] <= c) })
+}
+
+/// A Dyck word: a sequence of up (`true`) and down (`false`) steps which
+/// never goes below its starting level and ends there, i.e., a string of
+/// balanced parentheses.
+///
+/// The code of a binary tree gives the word of `catalan_parens`: in preorder,
+/// every internal node gives an up step and every leaf but the last one a
+/// down step.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DyckWord {
+    steps: Vec<bool>,
+}
+
+impl DyckWord {
+    /// The Dyck word with the given steps, if it is one.
+    pub fn from_steps(steps: Vec<bool>) -> Option<DyckWord> {
+        let mut height = 0usize;
+        for &up in &steps {
+            if up {
+                height += 1;
+            } else if height == 0 {
+                return None;
+            } else {
+                height -= 1;
+            }
+        }
+        if height == 0 { Some(DyckWord { steps: steps }) } else { None }
+    }
+
+    /// Parse a string of balanced parentheses, as printed by `Display`.
+    pub fn from_parens(s: &str) -> Option<DyckWord> {
+        let steps = s.chars().map(|c| {
+            match c {
+                '(' => Some(true),
+                ')' => Some(false),
+                _ => None,
+            }
+        }).collect::<Option<Vec<bool>>>()?;
+        DyckWord::from_steps(steps)
+    }
+
+    pub fn steps(&self) -> &[bool] {
+        &self.steps
+    }
+
+    /// The number of up steps.
+    pub fn size(&self) -> usize {
+        self.steps.len() / 2
+    }
+
+    /// The position of the matching down step of every up step, and
+    /// conversely.
+    pub fn matching(&self) -> Vec<usize> {
+        let mut partners = vec![0; self.steps.len()];
+        let mut open = Vec::new();
+        for (i, &up) in self.steps.iter().enumerate() {
+            if up {
+                open.push(i);
+            } else {
+                let j = open.pop().unwrap();
+                partners[i] = j;
+                partners[j] = i;
+            }
+        }
+        partners
+    }
+}
+
+impl CatalanObject for DyckWord {
+    fn from_code(code: &[usize]) -> Option<DyckWord> {
+        if !is_catalan_code(code) { return None }
+        // As in catalan_parens, the nodes labelled by a leaf come before it
+        let mut steps = Vec::with_capacity(2 * code.len());
+        let mut nodes = code.iter().peekable();
+        for leaf in 0..code.len() {
+            while nodes.peek() == Some(&&leaf) {
+                nodes.next();
+                steps.push(true);
+            }
+            steps.push(false);
+        }
+        Some(DyckWord { steps: steps })
+    }
+
+    fn to_code(&self) -> Vec<usize> {
+        let mut code = Vec::with_capacity(self.size());
+        let mut leaf = 0;
+        for &up in &self.steps {
+            if up { code.push(leaf) } else { leaf += 1 }
+        }
+        code
+    }
+}
+
+impl fmt::Display for DyckWord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &up in &self.steps {
+            write!(f, "{}", if up { '(' } else { ')' })?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a string of balanced parentheses, see `DyckWord::from_parens`.
+impl str::FromStr for DyckWord {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<DyckWord, Error> {
+        DyckWord::from_parens(s).ok_or_else(|| {
+            Error::Parse { kind: "Dyck word", input: s.to_string() }
+        })
+    }
+}
+
+/// A full binary tree: every node is a leaf or has two children. Its size is
+/// its number of internal nodes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum BinaryTree {
+    Leaf,
+    Node(Box<BinaryTree>, Box<BinaryTree>),
+}
+
+impl BinaryTree {
+    /// The number of internal nodes.
+    pub fn size(&self) -> usize {
+        match *self {
+            BinaryTree::Leaf => 0,
+            BinaryTree::Node(ref l, ref r) => 1 + l.size() + r.size(),
+        }
+    }
+
+    /// The number of leaves, one more than the size.
+    pub fn leaves(&self) -> usize {
+        self.size() + 1
+    }
+
+    /// The length of the longest path from the root to a leaf.
+    pub fn height(&self) -> usize {
+        match *self {
+            BinaryTree::Leaf => 0,
+            BinaryTree::Node(ref l, ref r) => 1 + cmp::max(l.height(), r.height()),
+        }
+    }
+
+    // Push the labels of the internal nodes in preorder, the leaves being
+    // numbered from first_leaf. Returns the number of leaves.
+    fn push_code(&self, first_leaf: usize, code: &mut Vec<usize>) -> usize {
+        match *self {
+            BinaryTree::Leaf => 1,
+            BinaryTree::Node(ref l, ref r) => {
+                code.push(first_leaf);
+                let left = l.push_code(first_leaf, code);
+                left + r.push_code(first_leaf + left, code)
+            }
+        }
+    }
+}
+
+impl CatalanObject for BinaryTree {
+    fn from_code(code: &[usize]) -> Option<BinaryTree> {
+        // The Dyck word is the preorder traversal, up steps for the internal
+        // nodes and down steps for the leaves, the last leaf omitted
+        fn parse<I: Iterator<Item = bool>>(steps: &mut I) -> BinaryTree {
+            match steps.next() {
+                Some(true) => {
+                    let l = parse(steps);
+                    let r = parse(steps);
+                    BinaryTree::Node(Box::new(l), Box::new(r))
+                }
+                _ => BinaryTree::Leaf,
+            }
+        }
+        let w = DyckWord::from_code(code)?;
+        Some(parse(&mut w.steps.into_iter()))
+    }
+
+    fn to_code(&self) -> Vec<usize> {
+        let mut code = Vec::with_capacity(self.size());
+        self.push_code(0, &mut code);
+        code
+    }
+}
+
+/// A triangulation of a convex polygon with `m + 2` vertices `0..m+2`, as
+/// its `m` triangles, each a sorted triple of vertices, in lexicographic
+/// order.
+///
+/// The root of the binary tree is the triangle on the side `(0, m + 1)`,
+/// and the left and right subtrees triangulate the polygons cut by the two
+/// other sides of that triangle. The label of a node is the smallest vertex
+/// of its triangle.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Triangulation {
+    triangles: Vec<[usize; 3]>,
+}
+
+impl Triangulation {
+    /// The number of vertices of the polygon.
+    pub fn vertices(&self) -> usize {
+        self.triangles.len() + 2
+    }
+
+    pub fn triangles(&self) -> &[[usize; 3]] {
+        &self.triangles
+    }
+
+    /// The diagonals, i.e., the sides of the triangles which are not sides
+    /// of the polygon, as sorted pairs in lexicographic order.
+    pub fn diagonals(&self) -> Vec<(usize, usize)> {
+        let last = self.vertices() - 1;
+        let mut diagonals = Vec::new();
+        for &[a, b, c] in &self.triangles {
+            for &(i, j) in &[(a, b), (b, c), (a, c)] {
+                if j - i > 1 && (i, j) != (0, last) { diagonals.push((i, j)) }
+            }
+        }
+        diagonals.sort();
+        diagonals.dedup();
+        diagonals
+    }
+}
+
+impl CatalanObject for Triangulation {
+    fn from_code(code: &[usize]) -> Option<Triangulation> {
+        fn push(t: &BinaryTree, i: usize, triangles: &mut Vec<[usize; 3]>) -> usize {
+            match *t {
+                BinaryTree::Leaf => i + 1,
+                BinaryTree::Node(ref l, ref r) => {
+                    let k = push(l, i, triangles);
+                    let j = push(r, k, triangles);
+                    triangles.push([i, k, j]);
+                    j
+                }
+            }
+        }
+        let t = BinaryTree::from_code(code)?;
+        let mut triangles = Vec::with_capacity(code.len());
+        push(&t, 0, &mut triangles);
+        triangles.sort();
+        Some(Triangulation { triangles: triangles })
+    }
+
+    fn to_code(&self) -> Vec<usize> {
+        // The apex of the triangle on every side (i, j) with i + 1 < j
+        let apex: collections::HashMap<(usize, usize), usize> = self.triangles.iter()
+            .map(|&[a, b, c]| { ((a, c), b) })
+            .collect();
+        let mut code = Vec::with_capacity(self.triangles.len());
+        let mut sides = vec![(0, self.vertices() - 1)];
+        while let Some((i, j)) = sides.pop() {
+            if j - i < 2 { continue }
+            code.push(i);
+            let k = apex[&(i, j)];
+            sides.push((k, j));
+            sides.push((i, k));
+        }
+        code
+    }
+}
+
+/// A non-crossing perfect matching of `2 * m` points on a line: no two
+/// pairs `(a, b)` and `(c, d)` with `a < c < b < d`.
+///
+/// The pairs are the matching up and down steps of the Dyck word.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NoncrossingMatching {
+    partners: Vec<usize>,
+}
+
+impl NoncrossingMatching {
+    /// The matching with the given partners, if it is a non-crossing perfect
+    /// matching.
+    pub fn from_partners(partners: Vec<usize>) -> Option<NoncrossingMatching> {
+        let n = partners.len();
+        let mut steps = Vec::with_capacity(n);
+        for (i, &j) in partners.iter().enumerate() {
+            if j >= n || j == i || partners[j] != i { return None }
+            steps.push(i < j);
+        }
+        // A matching is non-crossing if and only if it is that of its
+        // Dyck word
+        let w = DyckWord::from_steps(steps)?;
+        if w.matching() == partners {
+            Some(NoncrossingMatching { partners: partners })
+        } else {
+            None
+        }
+    }
+
+    /// The partner of every point.
+    pub fn partners(&self) -> &[usize] {
+        &self.partners
+    }
+
+    /// The pairs `(i, j)` with `i < j`, by increasing `i`.
+    pub fn pairs(&self) -> Vec<(usize, usize)> {
+        self.partners.iter().enumerate()
+            .filter(|&(i, &j)| { i < j })
+            .map(|(i, &j)| { (i, j) })
+            .collect()
+    }
+}
+
+impl CatalanObject for NoncrossingMatching {
+    fn from_code(code: &[usize]) -> Option<NoncrossingMatching> {
+        let w = DyckWord::from_code(code)?;
+        Some(NoncrossingMatching { partners: w.matching() })
+    }
+
+    fn to_code(&self) -> Vec<usize> {
+        let steps = self.partners.iter().enumerate().map(|(i, &j)| { i < j }).collect();
+        DyckWord { steps: steps }.to_code()
+    }
+}
+
+/// A permutation avoiding the pattern `231`, i.e., sortable with a stack.
+///
+/// The Dyck word records the pushes (up steps) and pops (down steps) which
+/// sort the permutation, popping as soon as the top of the stack is the next
+/// output.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Avoiding231 {
+    permutation: Permutation,
+}
+
+impl Avoiding231 {
+    /// The permutation, if it avoids `231`.
+    pub fn new(permutation: Permutation) -> Option<Avoiding231> {
+        if contains_pattern(permutation.as_slice(), &[1, 2, 0]) {
+            None
+        } else {
+            Some(Avoiding231 { permutation: permutation })
+        }
+    }
+
+    pub fn permutation(&self) -> &Permutation {
+        &self.permutation
+    }
+
+    pub fn into_permutation(self) -> Permutation {
+        self.permutation
+    }
+}
+
+impl CatalanObject for Avoiding231 {
+    fn from_code(code: &[usize]) -> Option<Avoiding231> {
+        let w = DyckWord::from_code(code)?;
+        // Every pop outputs the next value, which is then the image of the
+        // position pushed last
+        let mut image = vec![0; w.size()];
+        let mut stack = Vec::new();
+        let (mut pushed, mut popped) = (0, 0);
+        for &up in &w.steps {
+            if up {
+                stack.push(pushed);
+                pushed += 1;
+            } else {
+                image[stack.pop().unwrap()] = popped;
+                popped += 1;
+            }
+        }
+        Some(Avoiding231 { permutation: Permutation::from_vec(image).unwrap() })
+    }
+
+    fn to_code(&self) -> Vec<usize> {
+        let p = self.permutation.as_slice();
+        let mut steps = Vec::with_capacity(2 * p.len());
+        let mut stack = Vec::new();
+        let mut next = 0;
+        for &x in p {
+            stack.push(x);
+            steps.push(true);
+            while stack.last() == Some(&next) {
+                stack.pop();
+                steps.push(false);
+                next += 1;
+            }
+        }
+        DyckWord { steps: steps }.to_code()
+    }
+}
@@ -0,0 +1,131 @@
+//! Random generation and shrinking for quickcheck, with the `quickcheck`
+//! feature
+//!
+//! Shrinking stays within the type: a permutation shrinks toward the
+//! identity and to fewer points, a partition toward fewer and smaller parts,
+//! a binary tree toward its subtrees, and a subset mask toward fewer
+//! elements of a smaller set.
+
+use std::*;
+use quickcheck::{Arbitrary, Gen};
+use bits;
+use catalan::BinaryTree;
+use permutation::Permutation;
+use partition::Partition;
+use view::SubsetMask;
+
+impl Arbitrary for Permutation {
+    fn arbitrary(g: &mut Gen) -> Permutation {
+        let n = usize::arbitrary(g) % (g.size() + 1);
+        let mut p = Permutation::identity(n);
+        // Fisher-Yates shuffle
+        for i in (1..n).rev() {
+            let j = usize::arbitrary(g) % (i + 1);
+            p.swap(i, j);
+        }
+        p
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Permutation>> {
+        let mut smaller = Vec::new();
+        let n = self.len();
+        if n > 0 {
+            // Remove n - 1 from its cycle
+            let mut image = self.as_slice().to_vec();
+            let last = image.pop().unwrap();
+            if let Some(i) = image.iter().position(|&j| { j == n - 1 }) {
+                image[i] = last;
+            }
+            smaller.push(Permutation::from_vec(image).unwrap());
+        }
+        // Make one more fixed point
+        let inv = self.inverse();
+        for i in 0..n {
+            if self[i] != i {
+                let mut p = self.clone();
+                p.swap(i, inv[i]);
+                smaller.push(p);
+            }
+        }
+        Box::new(smaller.into_iter())
+    }
+}
+
+impl Arbitrary for Partition {
+    fn arbitrary(g: &mut Gen) -> Partition {
+        let mut n = usize::arbitrary(g) % (g.size() + 1);
+        let mut parts = Vec::new();
+        while n > 0 {
+            let x = 1 + usize::arbitrary(g) % n;
+            parts.push(x);
+            n -= x;
+        }
+        Partition::from_parts(parts)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Partition>> {
+        let mut smaller = Vec::new();
+        let parts = self.parts();
+        for i in 0..parts.len() {
+            // Equal parts give the same results
+            if i > 0 && parts[i] == parts[i - 1] { continue }
+            let mut removed = parts.to_vec();
+            removed.remove(i);
+            smaller.push(Partition::from_parts(removed));
+            if parts[i] > 1 {
+                let mut decreased = parts.to_vec();
+                decreased[i] -= 1;
+                smaller.push(Partition::from_parts(decreased));
+            }
+        }
+        Box::new(smaller.into_iter())
+    }
+}
+
+impl Arbitrary for BinaryTree {
+    fn arbitrary(g: &mut Gen) -> BinaryTree {
+        // A tree with n internal nodes, split at random between the subtrees
+        fn tree(g: &mut Gen, n: usize) -> BinaryTree {
+            if n == 0 { return BinaryTree::Leaf }
+            let l = usize::arbitrary(g) % n;
+            BinaryTree::Node(Box::new(tree(g, l)), Box::new(tree(g, n - 1 - l)))
+        }
+        let n = usize::arbitrary(g) % (g.size() + 1);
+        tree(g, n)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = BinaryTree>> {
+        let mut smaller = Vec::new();
+        if let BinaryTree::Node(ref l, ref r) = *self {
+            smaller.push((**l).clone());
+            smaller.push((**r).clone());
+            for m in (**l).shrink() {
+                smaller.push(BinaryTree::Node(Box::new(m), r.clone()));
+            }
+            for m in (**r).shrink() {
+                smaller.push(BinaryTree::Node(l.clone(), Box::new(m)));
+            }
+        }
+        Box::new(smaller.into_iter())
+    }
+}
+
+impl Arbitrary for SubsetMask {
+    fn arbitrary(g: &mut Gen) -> SubsetMask {
+        let n = usize::arbitrary(g) % (cmp::min(g.size(), 64) + 1);
+        SubsetMask::new(u64::arbitrary(g) & bits::full_mask(n), n)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = SubsetMask>> {
+        let mut smaller = Vec::new();
+        let (mask, n) = (self.mask(), self.universe());
+        if n > 0 {
+            // Drop n - 1 from the set
+            smaller.push(SubsetMask::new(mask & bits::full_mask(n - 1), n - 1));
+        }
+        for i in self.as_indices() {
+            smaller.push(SubsetMask::new(mask & !(1 << i), n));
+        }
+        Box::new(smaller.into_iter())
+    }
+}
@@ -0,0 +1,42 @@
+//! Errors of the fallible constructors, unranking and parsing functions
+//!
+//! Most functions of this crate panic on invalid arguments, which are
+//! usually programming errors. Their `try_` variants return an `Error`
+//! instead, for arguments which come from outside of the program.
+
+use std::*;
+
+/// The ways in which arguments can be invalid.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Error {
+    /// Cannot choose `k` elements out of `n`.
+    InvalidK { n: usize, k: usize },
+    /// A number does not fit in the integer type of the result.
+    Overflow,
+    /// A rank is not less than the number of objects.
+    RankOutOfRange,
+    /// A set of `n` elements is too large for its representation, which
+    /// has room for `max`, e.g., 64 for bit masks.
+    TooLarge { n: usize, max: usize },
+    /// A string does not represent a `kind`.
+    Parse { kind: &'static str, input: String },
+}
+
+/// A result with this crate's `Error`.
+pub type Result<T> = result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidK { n, k } => write!(f, "Cannot choose {} elements out of {}", k, n),
+            Error::Overflow => write!(f, "Overflow"),
+            Error::RankOutOfRange => write!(f, "Rank out of range"),
+            Error::TooLarge { n, max } => {
+                write!(f, "Set of {} elements, larger than the maximum of {}", n, max)
+            }
+            Error::Parse { kind, ref input } => write!(f, "Not a {}: {:?}", kind, input),
+        }
+    }
+}
+
+impl error::Error for Error {}
@@ -0,0 +1,57 @@
+//! C interface, with the `ffi` feature
+//!
+//! Each function enumerates objects made of indices, in the order of the
+//! corresponding iterator of `iter`, and calls `callback(indices, len, data)`
+//! on each of them. The array is only valid during the call. The enumeration
+//! stops early if the callback returns a nonzero value.
+//!
+//! The functions return the number of calls to the callback.
+//!
+//! The crate is only built as an `rlib` by default. Build a shared library
+//! to link from C with:
+//!
+//! ```sh
+//! cargo rustc --release --features ffi --crate-type cdylib
+//! ```
+
+use std::*;
+use std::os::raw::{c_int, c_void};
+use iter::*;
+
+/// The type of the callbacks.
+pub type Callback = extern "C" fn(*const usize, usize, *mut c_void) -> c_int;
+
+fn run<I>(mut iter: I, callback: Callback, data: *mut c_void) -> usize where
+    I: StreamingIterator<Item = [usize]>
+{
+    let mut count = 0;
+    while let Some(x) = iter.next() {
+        count += 1;
+        if callback(x.as_ptr(), x.len(), data) != 0 { break }
+    }
+    count
+}
+
+/// Combinations of `k` indices in `0..n`, in lexicographic order.
+/// Nothing is enumerated if `k > n`.
+#[no_mangle]
+pub extern "C" fn combinatorust_combinations(n: usize, k: usize,
+                                             callback: Callback,
+                                             data: *mut c_void) -> usize {
+    if k > n { return 0 }
+    run(IndexCombinations::new(n, k), callback, data)
+}
+
+/// Subsequences of `0..n`, in the order of `IndexSubsequences`.
+#[no_mangle]
+pub extern "C" fn combinatorust_subsequences(n: usize, callback: Callback,
+                                             data: *mut c_void) -> usize {
+    run(IndexSubsequences::new(n), callback, data)
+}
+
+/// Permutations of `0..n`, in the order of `IndexPermutations`.
+#[no_mangle]
+pub extern "C" fn combinatorust_permutations(n: usize, callback: Callback,
+                                             data: *mut c_void) -> usize {
+    run(index_permutations(n), callback, data)
+}
@@ -0,0 +1,37 @@
+//! Decoding of raw bytes for fuzzers, with the `arbitrary` feature
+//!
+//! Objects are unranked from digits drawn from the input, so that every
+//! input decodes to a valid object.
+
+use std::*;
+use arbitrary::{Arbitrary, Result, Unstructured};
+use permutation::Permutation;
+use partition::Partition;
+
+/// Unrank a Lehmer code: the `i`-th digit is the position of the image of
+/// `i` among the remaining points.
+impl<'a> Arbitrary<'a> for Permutation {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Permutation> {
+        let n = u.arbitrary_len::<usize>()?;
+        let mut remaining = (0..n).collect::<Vec<usize>>();
+        let mut image = Vec::with_capacity(n);
+        for i in 0..n {
+            image.push(remaining.remove(u.choose_index(n - i)?));
+        }
+        Ok(Permutation::from_vec(image).unwrap())
+    }
+}
+
+/// Unrank a composition, each part being a digit less than what remains.
+impl<'a> Arbitrary<'a> for Partition {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Partition> {
+        let mut n = u.arbitrary_len::<u8>()?;
+        let mut parts = Vec::new();
+        while n > 0 {
+            let x = 1 + u.choose_index(n)?;
+            parts.push(x);
+            n -= x;
+        }
+        Ok(Partition::from_parts(parts))
+    }
+}
@@ -0,0 +1,347 @@
+//! Small simple graphs, and the objects they define
+//!
+//! Graphs have at most 64 vertices, so that sets of vertices can be
+//! represented as bit masks, see the `bits` module.
+
+use std::*;
+use bits::full_mask;
+use iter::{StreamingIterator, Snapshot};
+
+/// A simple undirected graph on the vertices `0..n`, with `n <= 64`.
+// adj[v] is the mask of the neighbors of v.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Graph {
+    adj: Vec<u64>,
+}
+
+impl Graph {
+    /// The graph on `0..n` without edges.
+    ///
+    /// # Panics
+    ///
+    /// If `n > 64`.
+    pub fn new(n: usize) -> Graph {
+        assert!(n <= 64, "Graphs with at most 64 vertices");
+        Graph { adj: vec![0; n] }
+    }
+
+    /// The graph on `0..n` with the given edges.
+    ///
+    /// # Panics
+    ///
+    /// If `n > 64`, or an edge is invalid (see `add_edge`).
+    pub fn from_edges(n: usize, edges: &[(usize, usize)]) -> Graph {
+        let mut g = Graph::new(n);
+        for &(a, b) in edges { g.add_edge(a, b) }
+        g
+    }
+
+    /// The complete graph on `0..n`.
+    pub fn complete(n: usize) -> Graph {
+        Graph::new(n).complement()
+    }
+
+    /// The number of vertices.
+    pub fn order(&self) -> usize {
+        self.adj.len()
+    }
+
+    /// # Panics
+    ///
+    /// If `a == b` or a vertex is out of range.
+    pub fn add_edge(&mut self, a: usize, b: usize) {
+        assert!(a < self.order() && b < self.order(), "Vertex out of range");
+        assert!(a != b, "Loops are not allowed");
+        self.adj[a] |= 1 << b;
+        self.adj[b] |= 1 << a;
+    }
+
+    pub fn has_edge(&self, a: usize, b: usize) -> bool {
+        self.adj[a] & 1 << b != 0
+    }
+
+    /// The mask of the neighbors of `v`.
+    pub fn neighbors(&self, v: usize) -> u64 {
+        self.adj[v]
+    }
+
+    /// The graph with the same vertices, where two distinct vertices are
+    /// adjacent if and only if they are not adjacent in `self`.
+    pub fn complement(&self) -> Graph {
+        let n = self.order();
+        let adj = self.adj.iter().enumerate()
+            .map(|(v, &a)| { !a & full_mask(n) & !(1 << v) }).collect();
+        Graph { adj: adj }
+    }
+}
+
+/// An iterator over the proper colorings of a graph with colors in `0..k`:
+/// the color of every vertex differs from those of its neighbors. A
+/// coloring is given by the colors of the vertices.
+///
+/// The colorings are enumerated in lexicographic order, by backtracking
+/// vertex by vertex.
+// classes[v] is the number of colors used by the vertices before v.
+#[derive(Clone, Debug)]
+pub struct Colorings<'a> {
+    graph: &'a Graph,
+    k: usize,
+    canonical: bool,
+    colors: Vec<usize>,
+    classes: Vec<usize>,
+    first: bool,
+    done: bool,
+}
+
+impl<'a> Colorings<'a> {
+    pub fn new(graph: &'a Graph, k: usize) -> Colorings<'a> {
+        let n = graph.order();
+        Colorings {
+            graph: graph,
+            k: k,
+            canonical: false,
+            colors: vec![0; n],
+            classes: vec![0; n + 1],
+            first: true,
+            done: false,
+        }
+    }
+
+    /// Colorings up to permutations of the colors: every vertex gets a color
+    /// at most the number of colors used before it. There is one coloring
+    /// for every partition of the vertices into at most `k` independent
+    /// sets.
+    pub fn canonical(graph: &'a Graph, k: usize) -> Colorings<'a> {
+        Colorings { canonical: true, .. Colorings::new(graph, k) }
+    }
+
+    /// The current coloring.
+    pub fn current(&self) -> &[usize] {
+        &self.colors
+    }
+
+    /// The number of colors used by the current coloring, if it is
+    /// canonical.
+    pub fn colors_used(&self) -> usize {
+        self.classes[self.colors.len()]
+    }
+
+    // Color the vertices from v on, trying colors from start at v,
+    // backtracking if necessary.
+    fn search(&mut self, mut v: usize, mut start: usize) -> bool {
+        let n = self.colors.len();
+        while v < n {
+            let limit = if self.canonical {
+                cmp::min(self.k, self.classes[v] + 1)
+            } else {
+                self.k
+            };
+            let graph = self.graph;
+            let colors = &self.colors;
+            let c = (start..limit).find(|&c| {
+                (0..v).all(|u| { colors[u] != c || !graph.has_edge(u, v) })
+            });
+            match c {
+                Some(c) => {
+                    self.colors[v] = c;
+                    self.classes[v + 1] = cmp::max(self.classes[v], c + 1);
+                    v += 1;
+                    start = 0;
+                }
+                None => {
+                    if v == 0 { return false }
+                    v -= 1;
+                    start = self.colors[v] + 1;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl<'a> StreamingIterator for Colorings<'a> {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        if self.done { return None }
+        let n = self.colors.len();
+        let found = if self.first {
+            self.first = false;
+            self.search(0, 0)
+        } else if n == 0 {
+            false
+        } else {
+            let c = self.colors[n - 1];
+            self.search(n - 1, c + 1)
+        };
+        self.done = !found;
+        if found { Some(&self.colors) } else { None }
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(&self.colors) }
+    }
+}
+
+impl Graph {
+    /// Iterate through the independent sets (sets of pairwise nonadjacent
+    /// vertices), as masks.
+    pub fn independent_sets(&self) -> IndependentSets {
+        IndependentSets::new(self.adj.clone())
+    }
+
+    /// Iterate through the cliques (sets of pairwise adjacent vertices),
+    /// as masks: these are the independent sets of the complement.
+    pub fn cliques(&self) -> IndependentSets {
+        IndependentSets::new(self.complement().adj)
+    }
+
+    /// Iterate through the maximal cliques, as masks.
+    pub fn maximal_cliques(&self) -> MaximalCliques {
+        MaximalCliques::new(self.adj.clone())
+    }
+
+    /// Iterate through the maximal independent sets, as masks: these are
+    /// the maximal cliques of the complement.
+    pub fn maximal_independent_sets(&self) -> MaximalCliques {
+        MaximalCliques::new(self.complement().adj)
+    }
+}
+
+/// An iterator over the independent sets of a graph, as masks, see
+/// `Graph::independent_sets` and `Graph::cliques`.
+///
+/// The sets are enumerated in lexicographic order of their increasing
+/// sequences of vertices, starting with the empty set, in constant time
+/// per set.
+// stack holds the vertices of the current set, in increasing order, each
+// with the candidates left to replace it. ext is the mask of the vertices
+// which can extend the current set.
+#[derive(Clone, Debug)]
+pub struct IndependentSets {
+    adj: Vec<u64>,
+    stack: Vec<(usize, u64)>,
+    set: u64,
+    ext: u64,
+    first: bool,
+}
+
+impl IndependentSets {
+    fn new(adj: Vec<u64>) -> IndependentSets {
+        let ext = full_mask(adj.len());
+        IndependentSets { adj: adj, stack: Vec::new(), set: 0, ext: ext, first: true }
+    }
+
+    // Add the least vertex of candidates to the set, the others remaining
+    // to replace it
+    fn push(&mut self, candidates: u64) {
+        let v = candidates.trailing_zeros() as usize;
+        let rest = candidates & !(1 << v);
+        self.stack.push((v, rest));
+        self.set |= 1 << v;
+        self.ext = rest & !self.adj[v];
+    }
+}
+
+impl Iterator for IndependentSets {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        if self.first {
+            self.first = false;
+            return Some(0);
+        }
+        if self.ext != 0 {
+            let ext = self.ext;
+            self.push(ext);
+            return Some(self.set);
+        }
+        while let Some((v, rest)) = self.stack.pop() {
+            self.set &= !(1 << v);
+            if rest != 0 {
+                self.push(rest);
+                return Some(self.set);
+            }
+        }
+        None
+    }
+}
+
+/// An iterator over the maximal cliques of a graph, as masks, see
+/// `Graph::maximal_cliques` and `Graph::maximal_independent_sets`.
+///
+/// This is the algorithm of Bron and Kerbosch ("Finding all cliques of an
+/// undirected graph", 1973), with the pivoting rule of Tomita et al.:
+/// the branches are the candidates which are not neighbors of a vertex
+/// with the most neighbors among the candidates.
+// Every frame of the stack is a call (r, p, x) of the recursive algorithm:
+// r is a clique, p the vertices which can extend it, x those which can
+// extend it but were already explored. todo is the part of p left to
+// branch on.
+#[derive(Clone, Debug)]
+pub struct MaximalCliques {
+    adj: Vec<u64>,
+    stack: Vec<Frame>,
+    // Whether the graph has no vertices, and the empty clique was not output
+    empty: bool,
+}
+
+#[derive(Clone, Debug)]
+struct Frame {
+    r: u64,
+    p: u64,
+    x: u64,
+    todo: u64,
+}
+
+impl MaximalCliques {
+    fn new(adj: Vec<u64>) -> MaximalCliques {
+        let p = full_mask(adj.len());
+        let mut c = MaximalCliques { empty: p == 0, adj: adj, stack: Vec::new() };
+        if p != 0 { c.push(0, p, 0) }
+        c
+    }
+
+    fn push(&mut self, r: u64, p: u64, x: u64) {
+        let adj = &self.adj;
+        let pivot = (0..adj.len()).filter(|&u| { (p | x) & 1 << u != 0 })
+            .max_by_key(|&u| { (p & adj[u]).count_ones() }).unwrap();
+        self.stack.push(Frame { r: r, p: p, x: x, todo: p & !adj[pivot] });
+    }
+}
+
+impl Iterator for MaximalCliques {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        if self.empty {
+            self.empty = false;
+            return Some(0);
+        }
+        loop {
+            let branch = match self.stack.last_mut() {
+                None => return None,
+                Some(ref f) if f.todo == 0 => None,
+                Some(f) => {
+                    let v = f.todo.trailing_zeros() as usize;
+                    let branch = (f.r | 1 << v, f.p & self.adj[v], f.x & self.adj[v]);
+                    f.todo &= !(1 << v);
+                    f.p &= !(1 << v);
+                    f.x |= 1 << v;
+                    Some(branch)
+                }
+            };
+            match branch {
+                None => { self.stack.pop(); }
+                Some((r, 0, 0)) => return Some(r),
+                // Not maximal
+                Some((_, 0, _)) => {}
+                Some((r, p, x)) => self.push(r, p, x),
+            }
+        }
+    }
+}
+
+impl<'a> Snapshot for Colorings<'a> {
+    type State = Colorings<'a>;
+    fn snapshot(&self) -> Colorings<'a> { self.clone() }
+    fn restore(&mut self, state: &Colorings<'a>) { self.clone_from(state) }
+}
@@ -0,0 +1,421 @@
+//! Permutation groups given by generators
+//!
+//! A group is represented by a chain of stabilizers (Schreier-Sims), so that
+//! its elements can be enumerated and tested for membership without
+//! enumerating all of `S_n`.
+
+use std::*;
+use permutation::Permutation;
+use partition::Partition;
+use series::Series;
+use iter::{StreamingIterator, Partitions};
+
+/// A group of permutations of `0..n`.
+// Stabilizer chain with base 0, 1, ..., n-1, constructed with Knuth's
+// variant of the Schreier-Sims algorithm ("Efficient representation of
+// perm groups", 1991). transversals[k][j] is an element of the stabilizer
+// of 0..k-1 mapping k to j, if there is one. generators[k] are the
+// generators added at level k: the stabilizer of 0..k-1 is generated by
+// generators[k..].
+#[derive(Clone, Debug)]
+pub struct Group {
+    n: usize,
+    transversals: Vec<Vec<Option<Permutation>>>,
+    generators: Vec<Vec<Permutation>>,
+}
+
+impl Group {
+    /// The trivial group of permutations of `0..n`.
+    pub fn trivial(n: usize) -> Group {
+        let transversals = (0..n).map(|k| {
+            let mut t = vec![None; n];
+            t[k] = Some(Permutation::identity(n));
+            t
+        }).collect();
+        Group {
+            n: n,
+            transversals: transversals,
+            generators: vec![Vec::new(); n],
+        }
+    }
+
+    /// The smallest group of permutations of `0..n` containing the given
+    /// ones.
+    ///
+    /// # Panics
+    ///
+    /// If a generator is not a permutation of `0..n`.
+    pub fn generated_by(n: usize, generators: &[Permutation]) -> Group {
+        let mut g = Group::trivial(n);
+        for p in generators {
+            assert_eq!(p.len(), n, "Generator of the wrong length");
+            g.insert(p.clone(), 0);
+        }
+        g
+    }
+
+    /// The size `n` of the permuted set `0..n`.
+    pub fn degree(&self) -> usize {
+        self.n
+    }
+
+    /// The number of elements.
+    ///
+    /// # Panics
+    ///
+    /// If it does not fit in a `u128`, which can only happen for `n > 34`.
+    pub fn order(&self) -> u128 {
+        self.transversals.iter()
+            .map(|t| { t.iter().filter(|u| { u.is_some() }).count() as u128 })
+            .fold(1, |o, m| { o.checked_mul(m).expect("Overflow") })
+    }
+
+    /// Whether the group contains `p`.
+    pub fn contains(&self, p: &Permutation) -> bool {
+        p.len() == self.n && self.sift(p.clone(), 0).is_none()
+    }
+
+    /// The orbit of `x`: the images of `x` by the elements of the group,
+    /// in increasing order.
+    pub fn orbit(&self, x: usize) -> Vec<usize> {
+        let mut seen = vec![false; self.n];
+        let mut todo = vec![x];
+        seen[x] = true;
+        while let Some(y) = todo.pop() {
+            for p in self.generators.iter().flat_map(|gs| { gs.iter() }) {
+                let z = p[y];
+                if !seen[z] { seen[z] = true; todo.push(z) }
+            }
+        }
+        (0..self.n).filter(|&y| { seen[y] }).collect()
+    }
+
+    /// Iterate through the elements of the group.
+    pub fn elements(&self) -> Elements<'_> {
+        Elements {
+            group: self,
+            // transversals[k][k] is the identity
+            choices: (0..self.n).collect(),
+            done: false,
+        }
+    }
+
+    /// Whether the set `s` (in increasing order) is the smallest of its
+    /// orbit, in lexicographic order, when the group acts on its elements.
+    pub fn is_canonical_set(&self, s: &[usize]) -> bool {
+        let mut image = Vec::with_capacity(s.len());
+        self.elements().all(|p| {
+            image.clear();
+            image.extend(s.iter().map(|&x| { p[x] }));
+            image.sort();
+            &image[..] >= s
+        })
+    }
+
+    /// Whether the word `w` (of length `n`) is the smallest of its orbit,
+    /// in lexicographic order, when the group acts on its positions.
+    pub fn is_canonical_word<T: Ord>(&self, w: &[T]) -> bool {
+        assert_eq!(w.len(), self.n, "Word of the wrong length");
+        self.elements().all(|p| {
+            let image = (0..self.n).map(|i| { &w[p[i]] });
+            image.ge(w.iter())
+        })
+    }
+
+    /// Keep only the canonical representatives of the orbits, under the
+    /// given action, of the items of `iter`.
+    ///
+    /// If `iter` enumerates a set of objects closed under the action, there
+    /// remains exactly one object per orbit.
+    pub fn orbits<I>(&self, action: Action, iter: I) -> Orbits<'_, I> {
+        Orbits { group: self, action: action, iter: iter }
+    }
+
+    // Reduce p, an element of the stabilizer of 0..k-1, by the transversals.
+    // Returns the residue and the level at which it could not be reduced,
+    // or None if p is in the group.
+    fn sift(&self, mut p: Permutation, k: usize) -> Option<(Permutation, usize)> {
+        for l in k..self.n {
+            let j = p[l];
+            match self.transversals[l][j] {
+                None => return Some((p, l)),
+                Some(ref u) => p = u.inverse().compose(&p),
+            }
+        }
+        None
+    }
+
+    // Make sure that p, an element of the stabilizer of 0..k-1, is in the
+    // group, adding its residue as a generator if it is not.
+    fn insert(&mut self, p: Permutation, k: usize) {
+        if let Some((p, l)) = self.sift(p, k) {
+            self.generators[l].push(p.clone());
+            // p is in the stabilizers of all the levels up to l. Starting
+            // from level l makes p itself a member right away.
+            for m in (0..(l + 1)).rev() {
+                let reps = self.transversals[m].iter()
+                    .filter_map(|u| { u.clone() }).collect::<Vec<Permutation>>();
+                for u in reps {
+                    self.close(p.compose(&u), m);
+                }
+            }
+        }
+    }
+
+    // Make sure that p, an element of the stabilizer of 0..k-1, is in the
+    // group, adding it as a coset representative if its coset is new.
+    fn close(&mut self, p: Permutation, k: usize) {
+        let j = p[k];
+        let known = self.transversals[k][j].is_some();
+        if known {
+            let u = self.transversals[k][j].as_ref().unwrap().inverse();
+            let q = u.compose(&p);
+            self.insert(q, k + 1);
+        } else {
+            self.transversals[k][j] = Some(p.clone());
+            let generators = self.generators[k..].iter()
+                .flat_map(|gs| { gs.iter().cloned() }).collect::<Vec<Permutation>>();
+            for g in generators {
+                self.close(g.compose(&p), k);
+            }
+        }
+    }
+}
+
+/// An iterator over the elements of a `Group`.
+///
+/// Each element is uniquely a product of coset representatives, one for
+/// every level of the stabilizer chain. These choices are enumerated like
+/// the digits of a counter.
+#[derive(Clone, Debug)]
+pub struct Elements<'a> {
+    group: &'a Group,
+    // choices[k] is a point j such that transversals[k][j] is defined
+    choices: Vec<usize>,
+    done: bool,
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = Permutation;
+    fn next(&mut self) -> Option<Permutation> {
+        if self.done { return None }
+        let n = self.group.n;
+        let p = self.choices.iter().enumerate()
+            .fold(Permutation::identity(n), |p, (k, &j)| {
+                p.compose(self.group.transversals[k][j].as_ref().unwrap())
+            });
+        // Increment the counter, from the last level
+        self.done = true;
+        for k in (0..n).rev() {
+            let t = &self.group.transversals[k];
+            match (self.choices[k] + 1..n).find(|&j| { t[j].is_some() }) {
+                Some(j) => { self.choices[k] = j; self.done = false; break }
+                None => {
+                    self.choices[k] = t.iter().position(|u| { u.is_some() }).unwrap();
+                }
+            }
+        }
+        Some(p)
+    }
+}
+
+/// How a group of permutations of `0..n` acts on the objects of an
+/// enumeration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// On sets of points, given in increasing order (e.g., `IndexCombinations`
+    /// and `IndexSubsequences`).
+    Sets,
+    /// On words of length `n`, by permuting positions.
+    Words,
+}
+
+/// An iterator over canonical representatives of orbits.
+///
+/// See `Group::orbits`.
+#[derive(Clone, Debug)]
+pub struct Orbits<'a, I> {
+    group: &'a Group,
+    action: Action,
+    iter: I,
+}
+
+impl<'a, I> StreamingIterator for Orbits<'a, I> where
+    I: StreamingIterator<Item = [usize]>
+{
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        loop {
+            let canonical = match self.iter.next() {
+                None => return None,
+                Some(x) => match self.action {
+                    Action::Sets => self.group.is_canonical_set(x),
+                    Action::Words => self.group.is_canonical_word(x),
+                },
+            };
+            if canonical { return self.iter.get() }
+        }
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        self.iter.get()
+    }
+}
+
+/// The cycle index of a group of permutations: the average over the group
+/// of the monomials `p_1^m_1 p_2^m_2 ...`, where `m_i` is the number of
+/// cycles of length `i`.
+///
+/// It is stored as the number of elements of every cycle type, and the
+/// order of the group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CycleIndex {
+    order: u128,
+    terms: collections::BTreeMap<Partition, u128>,
+}
+
+impl CycleIndex {
+    /// The cycle index of `group`, in time proportional to its order.
+    pub fn of_group(group: &Group) -> CycleIndex {
+        let mut terms = collections::BTreeMap::new();
+        for p in group.elements() {
+            *terms.entry(p.cycle_type()).or_insert(0) += 1;
+        }
+        CycleIndex { order: group.order(), terms: terms }
+    }
+
+    /// The cycle index of the symmetric group `S_n`: the partition `λ` of
+    /// `n` is the cycle type of `n!/z_λ` permutations, where `z_λ` is the
+    /// product of `i^m_i m_i!` over the parts `i` of multiplicity `m_i`.
+    ///
+    /// # Panics
+    ///
+    /// On overflow, if `n > 34`.
+    pub fn symmetric(n: usize) -> CycleIndex {
+        let order = (1..(n + 1)).fold(1u128, |f, i| {
+            f.checked_mul(i as u128).expect("Overflow")
+        });
+        let mut terms = collections::BTreeMap::new();
+        let mut it = Partitions::new(n).as_partitions();
+        while let Some(lambda) = it.next() {
+            let mut z = 1;
+            let mut m = 0;
+            for (j, &i) in lambda.parts().iter().enumerate() {
+                m = if j > 0 && lambda.parts()[j - 1] == i { m + 1 } else { 1 };
+                z *= i as u128 * m;
+            }
+            terms.insert(lambda.clone(), order / z);
+        }
+        CycleIndex { order: order, terms: terms }
+    }
+
+    /// The cycle index of the cyclic group of the rotations of `n` points:
+    /// for every divisor `d` of `n`, `φ(d)` rotations have `n/d` cycles of
+    /// length `d`.
+    pub fn cyclic(n: usize) -> CycleIndex {
+        let mut terms = collections::BTreeMap::new();
+        if n == 0 { terms.insert(Partition::from_parts(vec![]), 1); }
+        for d in (1..(n + 1)).filter(|&d| { n.is_multiple_of(d) }) {
+            let phi = (1..(d + 1)).filter(|&i| { gcd(i, d) == 1 }).count();
+            terms.insert(Partition::from_parts(vec![d; n / d]), phi as u128);
+        }
+        CycleIndex { order: cmp::max(n, 1) as u128, terms: terms }
+    }
+
+    /// The cycle index of the dihedral group of the rotations and
+    /// reflections of `n` points, of order `2n`.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is `0`.
+    pub fn dihedral(n: usize) -> CycleIndex {
+        assert!(n > 0, "Dihedral group of degree 0");
+        let mut index = CycleIndex::cyclic(n);
+        // Reflections through a point, or between points if n is even
+        let mut reflections = Vec::new();
+        if n % 2 == 1 {
+            reflections.push((n, vec![2; n / 2].into_iter().chain(vec![1]).collect()));
+        } else {
+            reflections.push((n / 2, vec![2; n / 2]));
+            reflections.push((n / 2, vec![2; n / 2 - 1].into_iter().chain(vec![1, 1]).collect()));
+        }
+        for (count, parts) in reflections {
+            *index.terms.entry(Partition::from_parts(parts)).or_insert(0) += count as u128;
+        }
+        index.order = 2 * n as u128;
+        index
+    }
+
+    /// The order of the group.
+    pub fn order(&self) -> u128 {
+        self.order
+    }
+
+    /// The cycle types, in increasing lexicographic order, with the numbers
+    /// of elements having them.
+    pub fn terms(&self) -> collections::btree_map::Iter<'_, Partition, u128> {
+        self.terms.iter()
+    }
+
+    /// Pólya's substitution: the value of the cycle index with `f(i)` in
+    /// place of `p_i`.
+    ///
+    /// With `f(i) = k`, this is the number of orbits of the colorings of the
+    /// points with `k` colors (see `count_colorings`). Other substitutions
+    /// must also give integers.
+    ///
+    /// # Panics
+    ///
+    /// On overflow.
+    pub fn substitute<F: Fn(usize) -> u128>(&self, f: F) -> u128 {
+        let sum = self.terms.iter().fold(0u128, |s, (lambda, &count)| {
+            let x = lambda.parts().iter().fold(count, |x, &i| {
+                x.checked_mul(f(i)).expect("Overflow")
+            });
+            s.checked_add(x).expect("Overflow")
+        });
+        sum / self.order
+    }
+
+    /// The number of colorings of the points with `k` colors, up to the
+    /// action of the group.
+    ///
+    /// # Panics
+    ///
+    /// On overflow.
+    pub fn count_colorings(&self, k: usize) -> u128 {
+        self.substitute(|_| { k as u128 })
+    }
+
+    /// Pólya's substitution with power series, to count colorings by
+    /// weight: if the colors have weights `w_c`, substituting
+    /// `Σ x^(i w_c)` for `p_i` gives the numbers of colorings of every total
+    /// weight, up to the action of the group.
+    ///
+    /// The result is known up to the smallest degree of the series `f(i)`.
+    ///
+    /// # Panics
+    ///
+    /// On overflow.
+    pub fn substitute_series<F: Fn(usize) -> Series>(&self, f: F) -> Series {
+        let mut sum: Option<Vec<u128>> = None;
+        for (lambda, &count) in &self.terms {
+            let product = lambda.parts().iter()
+                .fold(None, |p: Option<Series>, &i| {
+                    Some(match p { None => f(i), Some(p) => p.times(&f(i)) })
+                });
+            let product = product.map_or(vec![1], |p| { p.coefficients().to_vec() });
+            let sum = sum.get_or_insert_with(|| { vec![0; product.len()] });
+            sum.truncate(product.len());
+            for (s, x) in sum.iter_mut().zip(product) {
+                *s = x.checked_mul(count).and_then(|x| { s.checked_add(x) }).expect("Overflow");
+            }
+        }
+        let sum = sum.unwrap_or_else(|| { vec![0] });
+        Series::from_coefficients(sum.into_iter().map(|s| { s / self.order }).collect())
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
@@ -1,294 +1,3769 @@
 //! Iterators over combinatorial derivatives of slices
 
 use std::*;
-use std::slice::ElementSwaps;
+use rank;
+use permutation::Permutation;
+use partition::Partition;
+use view::Combination;
+use error::{Error, Result};
+
+/// Iterators which lend their items: an item borrows the iterator, so it is
+/// only available until the next step.
+///
+/// This lets the iterators of this module yield slices of their internal
+/// buffers without allocating or cloning anything.
+pub trait StreamingIterator {
+    type Item: ?Sized;
+
+    /// Move to the next item and return it, or `None` if the iteration is
+    /// over.
+    fn next(&mut self) -> Option<&Self::Item>;
+
+    /// The item returned by the last call to `next`, if any.
+    fn get(&self) -> Option<&Self::Item>;
+
+    /// Bounds on the number of remaining items, as `Iterator::size_hint`.
+    ///
+    /// The default knows nothing. The iterators which know their exact
+    /// size return it, unless it does not fit in a `usize`.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, None)
+    }
+
+    /// Consume the iterator, counting the remaining items.
+    ///
+    /// If the `size_hint` is exact, it is the count, without stepping
+    /// through the items.
+    fn count(mut self) -> usize where Self: Sized {
+        if let (lo, Some(hi)) = self.size_hint() {
+            if lo == hi { return lo }
+        }
+        let mut count = 0;
+        while self.next().is_some() { count += 1 }
+        count
+    }
+
+    /// Skip `m` items, or return `Err(k)` if there were only `k < m` left.
+    ///
+    /// The iterators which can be positioned directly, e.g., by unranking,
+    /// skip them without generating them. Otherwise, they are generated
+    /// and dropped.
+    fn advance_by(&mut self, m: usize) -> result::Result<(), usize> {
+        step_forward(self, m)
+    }
+
+    /// Skip `m` items and return the next one, see `advance_by`.
+    fn nth(&mut self, m: usize) -> Option<&Self::Item> {
+        if self.advance_by(m).is_err() { return None }
+        self.next()
+    }
+
+    /// The first item, then every `step`-th one, skipped with `advance_by`,
+    /// e.g., to sample a large enumeration systematically.
+    ///
+    /// # Panics
+    ///
+    /// If `step` is 0.
+    fn step_by(self, step: usize) -> StepBy<Self> where Self: Sized {
+        assert!(step > 0, "Step of 0");
+        StepBy { iter: self, step: step, first: true }
+    }
+
+    /// Consume the iterator, returning an owned copy of the last item.
+    ///
+    /// The iterators which know their last item compute it directly,
+    /// e.g., by unranking the largest rank, instead of stepping through the
+    /// items.
+    fn last(self) -> Option<<Self::Item as ToOwned>::Owned> where
+        Self: Sized,
+        Self::Item: ToOwned
+    {
+        step_to_last(self)
+    }
+
+    /// A standard `Iterator` over owned copies of the items, e.g., `Vec<T>`
+    /// for slices `[T]`.
+    ///
+    /// This allocates for every item, but composes with any iterator
+    /// adaptor, e.g., from `itertools`:
+    /// `v.combinations(k).owned().tuple_windows()`.
+    fn owned(self) -> Owned<Self> where Self: Sized, Self::Item: ToOwned {
+        Owned { iter: self }
+    }
+
+    /// Pair every item with its rank, i.e., its position in the enumeration,
+    /// counted as the items are yielded.
+    fn with_rank(self) -> WithRank<Self> where Self: Sized {
+        WithRank { iter: self, next_rank: 0 }
+    }
+
+    /// Store the items as they are yielded, to index them or iterate
+    /// through them again without regenerating them.
+    fn cached<T: Clone>(self) -> Cached<Self, T> where
+        Self: Sized + StreamingIterator<Item = [T]>
+    {
+        Cached {
+            iter: self,
+            elements: Vec::new(),
+            ends: Vec::new(),
+            limit: usize::MAX,
+            exhausted: false,
+            truncated: false,
+        }
+    }
+
+    /// Report the progress of the enumeration to `callback`, about once per
+    /// second, and once at the end. `total` is the number of items, if
+    /// known, e.g., `rank::choose(n, k)`, to estimate the remaining time.
+    ///
+    /// Without a `total`, the exact `size_hint` of the iterator is used, if
+    /// any.
+    fn progress<F>(self, total: Option<u128>, callback: F) -> Progress<Self, F> where
+        Self: Sized,
+        F: FnMut(&ProgressReport)
+    {
+        let now = time::Instant::now();
+        let total = total.or_else(|| {
+            match self.size_hint() {
+                (lo, Some(hi)) if lo == hi => Some(lo as u128),
+                _ => None,
+            }
+        });
+        Progress {
+            iter: self,
+            callback: callback,
+            total: total,
+            done: 0,
+            start: now,
+            last: now,
+            interval: time::Duration::from_secs(1),
+            finished: false,
+        }
+    }
+}
+
+/// Iterators whose position can be saved and restored, e.g., to fork and
+/// roll back a branch-and-bound search, without cloning the iterator with
+/// its buffers of elements.
+///
+/// The state is made of indices only. It is implemented by the iterators
+/// which own their state: for most of them, the state is a clone of the
+/// iterator itself, which holds no elements. Adaptors such as `Progress` or
+/// `Dedup` do not implement it.
+pub trait Snapshot {
+    type State: Clone;
+
+    /// The current position of the iteration.
+    fn snapshot(&self) -> Self::State;
+
+    /// Return to the position of a snapshot, so that `get` and `next` yield
+    /// what they did at that time.
+    ///
+    /// The snapshot must come from an iterator constructed with the same
+    /// arguments, e.g., the same source slice, or a clone of this one.
+    fn restore(&mut self, state: &Self::State);
+}
+
+// Snapshot of iterators whose only state is indices, as clones of
+// themselves, reusing the buffers on restore.
+macro_rules! impl_snapshot {
+    ($($t:ty),*) => {
+        $(impl Snapshot for $t {
+            type State = $t;
+            fn snapshot(&self) -> $t { self.clone() }
+            fn restore(&mut self, state: &$t) { self.clone_from(state) }
+        })*
+    }
+}
+
+impl_snapshot!(IndexCombinations, GrayCombinations, IndexSubsequences, Multisets,
+               IndexPermutations, AsPermutations, ConstrainedPermutations, Catalan,
+               Compositions, VectorCompositions, Partitions, Dominance, PartitionsOfRank,
+               AsPartitions, WeightedCombinations, SubsetSums, MaximalSubsets,
+               InversionTables);
+
+/// Streaming iterators which can also yield their items from the back.
+///
+/// The items are yielded once, from either end, until the two ends meet.
+pub trait DoubleEndedStreamingIterator: StreamingIterator {
+    /// Move to the previous item from the back and return it, or `None` if
+    /// the iteration is over.
+    fn next_back(&mut self) -> Option<&Self::Item>;
+}
+
+/// A standard `Iterator` over the items of a `StreamingIterator`, converted
+/// with `ToOwned`.
+///
+/// See `StreamingIterator::owned`.
+#[derive(Clone, Debug)]
+pub struct Owned<I> {
+    iter: I,
+}
+
+impl<I> Owned<I> {
+    /// Recover the underlying iterator.
+    pub fn into_inner(self) -> I {
+        self.iter
+    }
+}
+
+impl<I> Iterator for Owned<I> where
+    I: StreamingIterator,
+    I::Item: ToOwned
+{
+    type Item = <I::Item as ToOwned>::Owned;
+    fn next(&mut self) -> Option<<I::Item as ToOwned>::Owned> {
+        self.iter.next().map(|x| { x.to_owned() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+
+    fn count(self) -> usize {
+        self.iter.count()
+    }
+
+    fn last(self) -> Option<<I::Item as ToOwned>::Owned> {
+        self.iter.last()
+    }
+
+    /// Skips with `StreamingIterator::advance_by`, which also makes
+    /// `Iterator::step_by` efficient.
+    fn nth(&mut self, m: usize) -> Option<<I::Item as ToOwned>::Owned> {
+        self.iter.nth(m).map(|x| { x.to_owned() })
+    }
+}
+
+/// Every `step`-th item of a `StreamingIterator`, from the first one.
+///
+/// See `StreamingIterator::step_by`.
+#[derive(Clone, Debug)]
+pub struct StepBy<I> {
+    iter: I,
+    step: usize,
+    first: bool,
+}
+
+impl<I: StreamingIterator> StreamingIterator for StepBy<I> {
+    type Item = I::Item;
+    fn next(&mut self) -> Option<&I::Item> {
+        if self.first {
+            self.first = false;
+            self.iter.next()
+        } else {
+            self.iter.nth(self.step - 1)
+        }
+    }
+
+    fn get(&self) -> Option<&I::Item> {
+        self.iter.get()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lo, hi) = self.iter.size_hint();
+        let steps = |n: usize| {
+            if self.first { n.div_ceil(self.step) } else { n / self.step }
+        };
+        (steps(lo), hi.map(steps))
+    }
+}
+
+/// Double-ended when the underlying iterator is, e.g., for `.rev()`.
+impl<I> DoubleEndedIterator for Owned<I> where
+    I: DoubleEndedStreamingIterator,
+    I::Item: ToOwned
+{
+    fn next_back(&mut self) -> Option<<I::Item as ToOwned>::Owned> {
+        self.iter.next_back().map(|x| { x.to_owned() })
+    }
+}
+
+// The default StreamingIterator::advance_by, stepping through the items
+fn step_forward<I: StreamingIterator + ?Sized>(iter: &mut I, m: usize) -> result::Result<(), usize> {
+    for k in 0..m {
+        if iter.next().is_none() { return Err(k) }
+    }
+    Ok(())
+}
+
+// Iterators which can be moved directly to any position of their
+// enumeration, to skip items without generating them.
+trait Seek: StreamingIterator {
+    // The number of items of the whole enumeration, if it fits.
+    fn total(&self) -> Option<u128>;
+
+    // The number of items left to yield, if it fits.
+    fn remaining(&self) -> Option<u128>;
+
+    // Move to the item at position p < total, to be yielded by the next call
+    // to next if first is set, or as if it had just been yielded otherwise.
+    fn seek(&mut self, p: u128, first: bool);
+}
+
+// StreamingIterator::advance_by for Seek iterators, with the same final
+// state as stepping through the items.
+fn seek_by<I: Seek>(iter: &mut I, m: usize) -> result::Result<(), usize> {
+    let (total, remaining) = match (iter.total(), iter.remaining()) {
+        (Some(t), Some(r)) => (t, r),
+        _ => return step_forward(iter, m),
+    };
+    if (m as u128) < remaining {
+        if m > 0 { iter.seek(total - remaining + m as u128, true) }
+        return Ok(())
+    }
+    if remaining > 0 { iter.seek(total - 1, false) }
+    if m as u128 == remaining { return Ok(()) }
+    // Reach the end, as the default would
+    iter.next();
+    Err(remaining as usize)
+}
+
+// The default StreamingIterator::last, stepping through the items
+fn step_to_last<I>(mut iter: I) -> Option<<I::Item as ToOwned>::Owned> where
+    I: StreamingIterator,
+    I::Item: ToOwned
+{
+    let mut last: Option<<I::Item as ToOwned>::Owned> = None;
+    while let Some(x) = iter.next() {
+        match last {
+            Some(ref mut l) => x.clone_into(l),
+            None => last = Some(x.to_owned()),
+        }
+    }
+    last
+}
+
+// Whether a size_hint says that no item remains
+fn is_over(size_hint: (usize, Option<usize>)) -> bool {
+    size_hint.1 == Some(0)
+}
+
+// The binomial coefficient, if it fits.
+fn checked_choose(n: usize, k: usize) -> Option<u128> {
+    if k > n { return Some(0) }
+    let k = cmp::min(k, n - k);
+    (0..k).try_fold(1u128, |c, i| {
+        Some(c.checked_mul((n - i) as u128)? / (i + 1) as u128)
+    })
+}
+
+// The exact size_hint for a number of remaining items, which may be unknown
+// or too large.
+fn exact_size_hint(remaining: Option<u128>) -> (usize, Option<usize>) {
+    match remaining.and_then(|r| { <usize as convert::TryFrom<u128>>::try_from(r).ok() }) {
+        Some(r) => (r, Some(r)),
+        None => (usize::MAX, None),
+    }
+}
+
+/// An iterator pairing the items of a `StreamingIterator` with their ranks.
+///
+/// See `StreamingIterator::with_rank`.
+#[derive(Clone, Debug)]
+pub struct WithRank<I> {
+    iter: I,
+    next_rank: u128,
+}
+
+impl<I: StreamingIterator> WithRank<I> {
+    /// Count from `rank` instead of `0`, e.g., when the underlying iterator
+    /// resumes an enumeration in the middle.
+    pub fn starting_at(self, rank: u128) -> WithRank<I> {
+        WithRank { next_rank: rank, .. self }
+    }
+
+    /// Move to the next item and return it with its rank, or `None` if the
+    /// iteration is over.
+    // Not Iterator::next: the items borrow from the iterator
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<(u128, &I::Item)> {
+        match self.iter.next() {
+            None => None,
+            Some(x) => {
+                self.next_rank += 1;
+                Some((self.next_rank - 1, x))
+            }
+        }
+    }
+
+    /// The item returned by the last call to `next`, with its rank, if any.
+    ///
+    /// An item which the underlying iterator yielded before being wrapped,
+    /// starting from rank `0`, has no rank, and gives `None`.
+    pub fn get(&self) -> Option<(u128, &I::Item)> {
+        let rank = self.next_rank.checked_sub(1)?;
+        self.iter.get().map(|x| { (rank, x) })
+    }
+
+    /// Recover the underlying iterator.
+    pub fn into_inner(self) -> I {
+        self.iter
+    }
+}
+
+/// A cache of the items of a `StreamingIterator` over slices, filled
+/// lazily, as the items are requested.
+///
+/// See `StreamingIterator::cached`. The items are stored contiguously in a
+/// single buffer.
+// Item i is elements[ends[i-1]..ends[i]], with ends[-1] = 0. exhausted is
+// set when the underlying iterator is over, truncated when an item did not
+// fit under the limit.
+#[derive(Clone, Debug)]
+pub struct Cached<I, T> {
+    iter: I,
+    elements: Vec<T>,
+    ends: Vec<usize>,
+    limit: usize,
+    exhausted: bool,
+    truncated: bool,
+}
+
+impl<I: StreamingIterator<Item = [T]>, T: Clone> Cached<I, T> {
+    /// Store at most `limit` elements in total, over all the items. The
+    /// items after the first one which does not fit are not available.
+    pub fn with_limit(self, limit: usize) -> Cached<I, T> {
+        Cached { limit: limit, .. self }
+    }
+
+    /// The item of index `i`, generating the items up to it if needed, or
+    /// `None` if there are fewer items, or if it is past the limit.
+    pub fn item(&mut self, i: usize) -> Option<&[T]> {
+        while self.ends.len() <= i && self.advance() {}
+        if i < self.ends.len() {
+            let start = if i == 0 { 0 } else { self.ends[i - 1] };
+            Some(&self.elements[start..self.ends[i]])
+        } else {
+            None
+        }
+    }
+
+    /// Generate and store all the items, and return their number.
+    pub fn fill(&mut self) -> usize {
+        while self.advance() {}
+        self.ends.len()
+    }
+
+    /// The number of items stored so far.
+    pub fn cached_len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Whether an item was left out because of the limit.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Iterate through the items from the first one, using the stored items
+    /// and generating the others. This can be repeated.
+    pub fn iter(&mut self) -> Replay<'_, I, T> {
+        Replay { cache: self, next_index: 0 }
+    }
+
+    // Store one more item, returning false if there is none.
+    fn advance(&mut self) -> bool {
+        if self.exhausted || self.truncated { return false }
+        match self.iter.next() {
+            None => { self.exhausted = true; false }
+            Some(x) => {
+                if self.elements.len() + x.len() > self.limit {
+                    self.truncated = true;
+                    return false;
+                }
+                self.elements.extend_from_slice(x);
+                self.ends.push(self.elements.len());
+                true
+            }
+        }
+    }
+}
+
+/// A pass over the items of a `Cached` iterator.
+///
+/// See `Cached::iter`.
+#[derive(Debug)]
+pub struct Replay<'a, I: 'a, T: 'a> {
+    cache: &'a mut Cached<I, T>,
+    next_index: usize,
+}
+
+impl<'a, I: StreamingIterator<Item = [T]>, T: Clone> StreamingIterator for Replay<'a, I, T> {
+    type Item = [T];
+    fn next(&mut self) -> Option<&[T]> {
+        self.next_index += 1;
+        let i = self.next_index - 1;
+        self.cache.item(i)
+    }
+
+    fn get(&self) -> Option<&[T]> {
+        let i = self.next_index.checked_sub(1)?;
+        if i < self.cache.ends.len() {
+            let start = if i == 0 { 0 } else { self.cache.ends[i - 1] };
+            Some(&self.cache.elements[start..self.cache.ends[i]])
+        } else {
+            None
+        }
+    }
+}
+
+/// A `StreamingIterator` reporting its progress.
+///
+/// See `StreamingIterator::progress`. The clock is only read every 1024
+/// items, to keep the overhead negligible.
+// last is the time of the last report, finished whether the final report
+// was made.
+#[derive(Clone)]
+pub struct Progress<I, F> {
+    iter: I,
+    callback: F,
+    total: Option<u128>,
+    done: u128,
+    start: time::Instant,
+    last: time::Instant,
+    interval: time::Duration,
+    finished: bool,
+}
+
+impl<I, F: FnMut(&ProgressReport)> Progress<I, F> {
+    /// Report every `interval` instead of every second.
+    pub fn every(self, interval: time::Duration) -> Progress<I, F> {
+        Progress { interval: interval, .. self }
+    }
+
+    /// Recover the underlying iterator.
+    pub fn into_inner(self) -> I {
+        self.iter
+    }
+
+    fn report(&mut self, now: time::Instant) {
+        self.last = now;
+        let report = ProgressReport {
+            done: self.done,
+            total: self.total,
+            elapsed: now - self.start,
+        };
+        (self.callback)(&report)
+    }
+}
+
+impl<I: StreamingIterator, F: FnMut(&ProgressReport)> StreamingIterator for Progress<I, F> {
+    type Item = I::Item;
+    fn next(&mut self) -> Option<&I::Item> {
+        if self.iter.next().is_some() {
+            self.done += 1;
+            if self.done.is_multiple_of(1024) {
+                let now = time::Instant::now();
+                if now - self.last >= self.interval { self.report(now) }
+            }
+        } else if !self.finished {
+            self.finished = true;
+            self.report(time::Instant::now());
+        }
+        self.iter.get()
+    }
+
+    fn get(&self) -> Option<&I::Item> {
+        self.iter.get()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+
+    /// Steps through the items, to report the progress.
+    fn count(mut self) -> usize {
+        let mut count = 0;
+        while self.next().is_some() { count += 1 }
+        count
+    }
+}
+
+/// The progress of an enumeration, see `StreamingIterator::progress`.
+///
+/// The fields map directly to progress bars, e.g., with `indicatif`,
+/// `bar.set_position(report.done as u64)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProgressReport {
+    /// The number of items yielded so far.
+    pub done: u128,
+    /// The total number of items, if known.
+    pub total: Option<u128>,
+    /// The time since the start of the enumeration.
+    pub elapsed: time::Duration,
+}
+
+impl ProgressReport {
+    /// The number of items per second.
+    pub fn rate(&self) -> f64 {
+        self.done as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// The fraction of the items done, if the total is known.
+    pub fn fraction(&self) -> Option<f64> {
+        self.total.map(|t| { if t == 0 { 1.0 } else { self.done as f64 / t as f64 } })
+    }
+
+    /// The estimated remaining time at the current rate, if the total is
+    /// known.
+    pub fn eta(&self) -> Option<time::Duration> {
+        let rate = self.rate();
+        self.total.and_then(|t| {
+            let remaining = t.saturating_sub(self.done) as f64;
+            if remaining == 0.0 { return Some(time::Duration::from_secs(0)) }
+            if rate > 0.0 && rate.is_finite() {
+                Some(time::Duration::from_secs_f64(remaining / rate))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Orders of enumeration, for the iterators which support several of them.
+///
+/// Objects are compared as sequences: combinations and subsequences by their
+/// indices in increasing order, compositions by their parts. Every
+/// `with_order` constructor lists the orders it supports, and panics on the
+/// others.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Order {
+    /// Lexicographic order.
+    Lex,
+    /// Colexicographic order: lexicographic order of the reversed
+    /// sequences.
+    Colex,
+    /// Reverse lexicographic order, from the largest object to the
+    /// smallest.
+    RevLex,
+    /// An order where consecutive objects differ by a minimal change, e.g.,
+    /// a transposition.
+    GrayMinimalChange,
+}
+
+/// An iterator over combinations of `k` indices in `0..n`.
+///
+/// This is the state machine behind `Combinations`: it only manipulates
+/// indices, so it does not need the elements at all.
+// indices[i] ranges between i and n-k+i, and the sequence is increasing.
+// first distinguishes the first call to .next() from the subsequent ones.
+#[derive(Clone, Debug)]
+pub struct IndexCombinations {
+    n: usize,
+    indices: Vec<usize>,
+    order: Order,
+    first: bool,
+    done: bool,
+}
+
+impl IndexCombinations {
+    pub fn new(n: usize, k: usize) -> IndexCombinations {
+        IndexCombinations::with_buffer(n, k, Vec::with_capacity(k))
+    }
+
+    /// Like `new`, or `Error::InvalidK` if `k > n`.
+    pub fn try_new(n: usize, k: usize) -> Result<IndexCombinations> {
+        if k > n { return Err(Error::InvalidK { n: n, k: k }) }
+        Ok(IndexCombinations::new(n, k))
+    }
+
+    /// Like `new`, but reuse the given buffer instead of allocating a new one.
+    pub fn with_buffer(n: usize, k: usize, mut indices: Vec<usize>)
+        -> IndexCombinations
+    {
+        assert!(k <= n, "Cannot choose {} elements out of {}", k, n);
+        indices.clear();
+        indices.extend(0..k);
+        IndexCombinations {
+            n: n,
+            indices: indices,
+            order: Order::Lex,
+            first: true,
+            done: false,
+        }
+    }
+
+    /// Combinations in the given order, among `Lex` (as `new`), `Colex`
+    /// and `RevLex`. For a minimal change order, see `GrayCombinations`.
+    pub fn with_order(n: usize, k: usize, order: Order) -> IndexCombinations {
+        assert!(k <= n, "Cannot choose {} elements out of {}", k, n);
+        let indices = match order {
+            Order::Lex | Order::Colex => (0..k).collect(),
+            Order::RevLex => ((n - k)..n).collect(),
+            Order::GrayMinimalChange => panic!("Unsupported order {:?}", order),
+        };
+        IndexCombinations {
+            n: n,
+            indices: indices,
+            order: order,
+            first: true,
+            done: false,
+        }
+    }
+
+    /// Combinations in reverse lexicographic order, from `n-k, ..., n-1`
+    /// down to `0, ..., k-1`.
+    pub fn rev_lex(n: usize, k: usize) -> IndexCombinations {
+        IndexCombinations::with_order(n, k, Order::RevLex)
+    }
+
+    /// Start the iteration from the combination with rank `r`
+    /// (see `rank::combination_rank`).
+    pub fn from_rank(n: usize, k: usize, r: usize) -> IndexCombinations {
+        let mut indices = Vec::with_capacity(k);
+        rank::combination_unrank(n, k, r, &mut indices);
+        IndexCombinations {
+            n: n,
+            indices: indices,
+            order: Order::Lex,
+            first: true,
+            done: false,
+        }
+    }
+
+    /// The current combination.
+    pub fn current(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Like `from_rank`, but return an error instead of panicking.
+    pub fn try_from_rank(n: usize, k: usize, r: usize) -> Result<IndexCombinations> {
+        let mut indices = Vec::with_capacity(k);
+        rank::try_combination_unrank(n, k, r, &mut indices)?;
+        Ok(IndexCombinations {
+            n: n,
+            indices: indices,
+            order: Order::Lex,
+            first: true,
+            done: false,
+        })
+    }
+
+    /// The current combination, as a subset of `0..n`.
+    pub fn current_combination(&self) -> Combination<'_, usize> {
+        Combination::new(&self.indices, &self.indices, self.n)
+    }
+
+    /// Give back the internal buffer, to be reused by another iterator.
+    pub fn into_buffer(self) -> Vec<usize> {
+        self.indices
+    }
+
+    // The number of combinations after the current one, if it fits
+    fn remaining_after(&self) -> Option<u128> {
+        let (n, k, c) = (self.n, self.indices.len(), &self.indices);
+        // The combinations greater in lexicographic order: those which agree
+        // up to position i, and have a larger index there
+        let lex = |c: &[usize]| {
+            c.iter().enumerate().try_fold(0u128, |r, (i, &x)| {
+                r.checked_add(checked_choose(n - 1 - x, k - i)?)
+            })
+        };
+        match self.order {
+            Order::Lex => lex(c),
+            Order::RevLex => Some(checked_choose(n, k)? - 1 - lex(c)?),
+            // The combinations greater in colexicographic order: those which
+            // agree from position i + 1 on, and have a larger index at i
+            _ => (0..k).try_fold(0u128, |r, i| {
+                let next = if i + 1 == k { n } else { c[i + 1] };
+                let skipped = checked_choose(next, i + 1)? - checked_choose(c[i] + 1, i + 1)?;
+                r.checked_add(skipped)
+            }),
+        }
+    }
+
+    // The last combination in the order
+    fn last_combination(&self) -> Vec<usize> {
+        let (n, k) = (self.n, self.indices.len());
+        if self.order == Order::RevLex { (0..k).collect() } else { ((n - k)..n).collect() }
+    }
+
+    /// Move to the next combination.
+    ///
+    /// Returns the range of positions that changed, which extends to the end
+    /// except in colexicographic order, where it starts at the beginning, or
+    /// `None` if the iteration is over.
+    pub fn advance(&mut self) -> Option<ops::Range<usize>> {
+        let IndexCombinations { n, ref mut indices, order, ref mut first, ref mut done } = *self;
+        let k = indices.len();
+        if *first { *first = false; return Some(0..k) }
+        if order == Order::Colex {
+            // Increment the first index which is not right before the next
+            // one, and move the previous ones to the beginning
+            let i_opt = (0..k).position(|i| {
+                indices[i] + 1 < if i + 1 == k { n } else { indices[i + 1] }
+            });
+            return match i_opt {
+                None => { *done = true; None },
+                Some(i) => {
+                    indices[i] += 1;
+                    for (l, j) in indices[..i].iter_mut().enumerate() { *j = l }
+                    Some(0..(i + 1))
+                }
+            };
+        }
+        if order == Order::RevLex {
+            // Decrement the last index which is not right after the previous
+            // one, and move the following ones to the end
+            let i_opt = (0..k).rposition(|i| {
+                indices[i] > if i == 0 { 0 } else { indices[i - 1] + 1 }
+            });
+            return match i_opt {
+                None => { *done = true; None },
+                Some(i) => {
+                    indices[i] -= 1;
+                    for (l, j) in indices.iter_mut().enumerate().skip(i + 1) {
+                        *j = n - k + l;
+                    }
+                    Some(i..k)
+                }
+            };
+        }
+        let i_opt = indices.iter().enumerate()
+            .rposition(|(i, &j)| { j != n - k + i });
+        match i_opt {
+            None => { *done = true; None },
+            Some(i) => {
+                let h = indices[i] + 1;
+                for (l, j) in indices[i..].iter_mut().enumerate() {
+                    *j = h + l;
+                }
+                Some(i..k)
+            }
+        }
+    }
+}
+
+/// The state machine of an enumeration of combinations of indices,
+/// as used by `Combinations`.
+pub trait CombinationCore {
+    /// Move to the next combination.
+    ///
+    /// Returns the range of positions that changed, or `None` if the
+    /// iteration is over.
+    fn advance(&mut self) -> Option<ops::Range<usize>>;
+
+    /// The current combination, in increasing order.
+    fn current(&self) -> &[usize];
+
+    /// Give back the internal buffer, to be reused by another iterator.
+    fn into_buffer(self) -> Vec<usize>;
+
+    /// Bounds on the number of remaining combinations, as
+    /// `StreamingIterator::size_hint`. The default knows nothing.
+    fn remaining_hint(&self) -> (usize, Option<usize>) {
+        (0, None)
+    }
+
+    /// Skip `m` combinations, as `StreamingIterator::advance_by`. The
+    /// default steps through them.
+    fn skip_combinations(&mut self, m: usize) -> result::Result<(), usize> {
+        for k in 0..m {
+            if self.advance().is_none() { return Err(k) }
+        }
+        Ok(())
+    }
+
+    /// The last combination, if it can be found without stepping through
+    /// the remaining ones. It is only asked for when some remain. The
+    /// default does not know it.
+    fn last_combination(&self) -> Option<Vec<usize>> {
+        None
+    }
+}
+
+impl CombinationCore for IndexCombinations {
+    fn advance(&mut self) -> Option<ops::Range<usize>> { self.advance() }
+    fn current(&self) -> &[usize] { self.current() }
+    fn into_buffer(self) -> Vec<usize> { self.into_buffer() }
+    fn remaining_hint(&self) -> (usize, Option<usize>) { self.size_hint() }
+    fn last_combination(&self) -> Option<Vec<usize>> { Some(self.last_combination()) }
+    fn skip_combinations(&mut self, m: usize) -> result::Result<(), usize> { self.advance_by(m) }
+}
+
+/// Iterate through combinations of `k` indices in increasing order.
+///
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for IndexCombinations {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        match self.advance() {
+            None => None,
+            Some(_) => Some(&self.indices),
+        }
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(&self.indices) }
+    }
+
+    /// Exact.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        exact_size_hint(self.remaining())
+    }
+
+    fn last(self) -> Option<Vec<usize>> {
+        if is_over(self.size_hint()) { None } else { Some(self.last_combination()) }
+    }
+
+    /// Unranks the combination at the new position.
+    fn advance_by(&mut self, m: usize) -> result::Result<(), usize> {
+        seek_by(self, m)
+    }
+}
+
+impl Seek for IndexCombinations {
+    fn total(&self) -> Option<u128> {
+        checked_choose(self.n, self.indices.len())
+    }
+
+    fn remaining(&self) -> Option<u128> {
+        if self.done { return Some(0) }
+        let first = if self.first { 1 } else { 0 };
+        self.remaining_after()?.checked_add(first)
+    }
+
+    fn seek(&mut self, p: u128, first: bool) {
+        let (n, k) = (self.n, self.indices.len());
+        match self.order {
+            Order::Lex => rank::combination_unrank_as(n, k, &p, &mut self.indices),
+            Order::RevLex => {
+                let r = self.total().unwrap() - 1 - p;
+                rank::combination_unrank_as(n, k, &r, &mut self.indices)
+            }
+            // The colexicographic rank is the sum of choose(c[i], i + 1)
+            _ => {
+                let mut r = p;
+                let mut x = n;
+                for i in (0..k).rev() {
+                    x -= 1;
+                    while checked_choose(x, i + 1).unwrap() > r { x -= 1 }
+                    r -= checked_choose(x, i + 1).unwrap();
+                    self.indices[i] = x;
+                }
+            }
+        }
+        self.first = first;
+        self.done = false;
+    }
+}
+
+/// An iterator over combinations of `k` indices in `0..n`, in a minimal
+/// change order: from one combination to the next, one index is removed and
+/// another is added ("revolving door", Algorithm R from Knuth, TAOCP
+/// 7.2.1.3).
+///
+/// Each step takes constant time, and changes at most two consecutive
+/// positions of the current combination, which stays in increasing order.
+// c[k] = n is a sentinel. level is the level (c_level in Knuth's notation
+// is c[level - 1]) which moves in the next step, 0 if there is none.
+#[derive(Clone, Debug)]
+pub struct GrayCombinations {
+    c: Vec<usize>,
+    level: usize,
+    first: bool,
+    done: bool,
+}
+
+impl GrayCombinations {
+    pub fn new(n: usize, k: usize) -> GrayCombinations {
+        GrayCombinations::with_buffer(n, k, Vec::with_capacity(k + 1))
+    }
+
+    /// Like `new`, or `Error::InvalidK` if `k > n`.
+    pub fn try_new(n: usize, k: usize) -> Result<GrayCombinations> {
+        if k > n { return Err(Error::InvalidK { n: n, k: k }) }
+        Ok(GrayCombinations::new(n, k))
+    }
+
+    /// Like `new`, but reuse the given buffer instead of allocating a new one.
+    pub fn with_buffer(n: usize, k: usize, mut c: Vec<usize>)
+        -> GrayCombinations
+    {
+        assert!(k <= n, "Cannot choose {} elements out of {}", k, n);
+        c.clear();
+        c.extend(0..k);
+        c.push(n);
+        let mut g = GrayCombinations {
+            c: c,
+            level: 0,
+            first: true,
+            done: false,
+        };
+        g.level = g.find_level(1, k);
+        g
+    }
+
+    /// The current combination.
+    pub fn current(&self) -> &[usize] {
+        &self.c[..self.c.len() - 1]
+    }
+
+    /// Give back the internal buffer, to be reused by another iterator.
+    pub fn into_buffer(self) -> Vec<usize> {
+        self.c
+    }
+
+    /// Move to the next combination.
+    ///
+    /// Returns the range of (at most two) positions that changed, or `None`
+    /// if the iteration is over.
+    ///
+    /// Algorithm R moves the lowest level which can: c_j goes up if
+    /// `k - j` is even, and down otherwise. A move of c_j only changes
+    /// c_{j - 1} and c_j, so the levels below j - 2 still cannot move, and
+    /// one of j + 1 and j + 2 can, if there is any. The next level is then
+    /// found among at most five, instead of searching from the bottom.
+    pub fn advance(&mut self) -> Option<ops::Range<usize>> {
+        let t = self.c.len() - 1;
+        if self.first { self.first = false; return Some(0..t) }
+        if self.done { return None }
+        let j = self.level;
+        if j == 0 { self.done = true; return None }
+        let changed = {
+            let c = &mut self.c;
+            if j == 1 {
+                if t & 1 == 1 { c[0] += 1 } else { c[0] -= 1 }
+                0..1
+            } else if (t ^ j) & 1 == 0 {
+                c[j - 2] = c[j - 1];
+                c[j - 1] += 1;
+                (j - 2)..j
+            } else {
+                c[j - 1] = c[j - 2];
+                c[j - 2] = j - 2;
+                (j - 2)..j
+            }
+        };
+        self.level = self.find_level(cmp::max(j, 3) - 2, cmp::min(t, j + 2));
+        Some(changed)
+    }
+
+    // The lowest level in lo..=hi which can move in its direction, 0 if
+    // there is none.
+    fn find_level(&self, lo: usize, hi: usize) -> usize {
+        let (c, t) = (&self.c, self.c.len() - 1);
+        (lo..(hi + 1)).find(|&j| {
+            if (t ^ j) & 1 == 0 { c[j - 1] + 1 < c[j] } else { c[j - 1] >= j }
+        }).unwrap_or(0)
+    }
+}
+
+impl GrayCombinations {
+    // The number of combinations after the current one, if it fits. The
+    // rank of c_1 < ... < c_k in revolving door order is the alternating
+    // sum of choose(c_j + 1, j) - 1, the last term being positive.
+    fn remaining_after(&self) -> Option<u128> {
+        let c = self.current();
+        let (n, k) = (self.c[c.len()], c.len());
+        let (mut plus, mut minus) = (0u128, 0u128);
+        for (i, &x) in c.iter().enumerate() {
+            let t = checked_choose(x + 1, i + 1)? - 1;
+            if (k - i - 1) % 2 == 0 { plus = plus.checked_add(t)? } else { minus = minus.checked_add(t)? }
+        }
+        Some(checked_choose(n, k)? - 1 - (plus - minus))
+    }
+
+    // The last combination: 0, ..., k - 2, n - 1
+    fn last_combination(&self) -> Vec<usize> {
+        let k = self.c.len() - 1;
+        let mut last: Vec<usize> = (0..k).collect();
+        if let Some(x) = last.last_mut() { *x = self.c[k] - 1 }
+        last
+    }
+}
+
+impl GrayCombinations {
+    /// The changes from one combination to the next, as pairs of an index
+    /// removed and an index added, starting from the current combination
+    /// (or the first one, if the iteration has not started).
+    pub fn transitions(mut self) -> CombinationTransitions {
+        if self.first { self.advance(); }
+        let prev = self.current().to_vec();
+        CombinationTransitions { iter: self, prev: prev }
+    }
+}
+
+/// An iterator over the changes between consecutive combinations in
+/// revolving door order, see `GrayCombinations::transitions`.
+// prev is the previous combination.
+#[derive(Clone, Debug)]
+pub struct CombinationTransitions {
+    iter: GrayCombinations,
+    prev: Vec<usize>,
+}
+
+impl Iterator for CombinationTransitions {
+    /// `(removed, added)`
+    type Item = (usize, usize);
+    fn next(&mut self) -> Option<(usize, usize)> {
+        let r = self.iter.advance()?;
+        let c = self.iter.current();
+        let (old, new) = (&self.prev[r.clone()], &c[r.clone()]);
+        let removed = *old.iter().find(|x| { !new.contains(x) }).unwrap();
+        let added = *new.iter().find(|x| { !old.contains(x) }).unwrap();
+        for p in r { self.prev[p] = c[p] }
+        Some((removed, added))
+    }
+}
+
+impl CombinationCore for GrayCombinations {
+    fn advance(&mut self) -> Option<ops::Range<usize>> { self.advance() }
+    fn current(&self) -> &[usize] { self.current() }
+    fn into_buffer(self) -> Vec<usize> { self.into_buffer() }
+    fn remaining_hint(&self) -> (usize, Option<usize>) { self.size_hint() }
+    fn last_combination(&self) -> Option<Vec<usize>> { Some(self.last_combination()) }
+    fn skip_combinations(&mut self, m: usize) -> result::Result<(), usize> { self.advance_by(m) }
+}
+
+/// Iterate through combinations of `k` indices in revolving door order.
+///
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for GrayCombinations {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        match self.advance() {
+            None => None,
+            Some(_) => Some(self.current()),
+        }
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(self.current()) }
+    }
+
+    /// Exact.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        exact_size_hint(self.remaining())
+    }
+
+    fn last(self) -> Option<Vec<usize>> {
+        if is_over(self.size_hint()) { None } else { Some(self.last_combination()) }
+    }
+
+    /// Unranks the combination at the new position.
+    fn advance_by(&mut self, m: usize) -> result::Result<(), usize> {
+        seek_by(self, m)
+    }
+}
+
+impl Seek for GrayCombinations {
+    fn total(&self) -> Option<u128> {
+        let k = self.c.len() - 1;
+        checked_choose(self.c[k], k)
+    }
+
+    fn remaining(&self) -> Option<u128> {
+        if self.done { return Some(0) }
+        let first = if self.first { 1 } else { 0 };
+        self.remaining_after()?.checked_add(first)
+    }
+
+    // The rank r of c_1 < ... < c_j is choose(c_j + 1, j) - 1 - r', where r'
+    // is the rank of c_1 < ... < c_{j - 1}, so choose(c_j, j) <= r.
+    fn seek(&mut self, p: u128, first: bool) {
+        let k = self.c.len() - 1;
+        let mut r = p;
+        let mut x = self.c[k];
+        for j in (1..(k + 1)).rev() {
+            x -= 1;
+            while checked_choose(x, j).unwrap() > r { x -= 1 }
+            self.c[j - 1] = x;
+            r = checked_choose(x + 1, j).unwrap() - 1 - r;
+        }
+        self.level = self.find_level(1, k);
+        self.first = first;
+        self.done = false;
+    }
+}
 
 /// An iterator over combinations of `k` elements in a list of `n`.
-// The i-th cell of dest can contain an element from src with index
-// j between i and n-k+i. indices[i] records the difference n-k+i-j.
-// The bool distinguishes the first call to .next() from the subsequent ones.
-pub struct Combinations<'a, T> where T: 'a {
+///
+/// The order is given by the underlying `CombinationCore`: minimal change
+/// by default (see `GrayCombinations`), so that each step clones at most two
+/// elements, or lexicographic with `IndexCombinations`.
+// The elements are only materialized in dest by the StreamingIterator
+// instance, which needs T: Clone. dest is either empty or up to date, except
+// for the positions in dirty. valid is set when dest is up to date.
+#[derive(Clone, Debug)]
+pub struct Combinations<'a, T, C = GrayCombinations> where T: 'a {
+    src: &'a [T],
+    dest: Vec<T>,
+    dirty: ops::Range<usize>,
+    valid: bool,
+    core: C,
+}
+
+/// The state is that of the underlying `CombinationCore`, the elements being
+/// cloned again from the source on restore.
+impl<'a, T, C> Snapshot for Combinations<'a, T, C> where
+    T: 'a + Clone,
+    C: CombinationCore + Snapshot + StreamingIterator<Item = [usize]>
+{
+    type State = C::State;
+    fn snapshot(&self) -> C::State {
+        self.core.snapshot()
+    }
+
+    fn restore(&mut self, state: &C::State) {
+        let Combinations { src, ref mut dest, ref mut dirty, ref mut valid, ref mut core } = *self;
+        core.restore(state);
+        dest.clear();
+        *dirty = 0..0;
+        *valid = core.get().is_some();
+        if *valid { dest.extend(core.current().iter().map(|&j| { src[j].clone() })) }
+    }
+}
+
+pub trait CombinationsIterator<T> {
+    /// Combinations of `k` elements in revolving door order: consecutive
+    /// combinations differ by one element (see `GrayCombinations`).
+    fn combinations<'a>(&'a self, k: usize) -> Combinations<'a, T>;
+
+    /// Like `combinations`, but reuse the given buffers for the internal
+    /// state instead of allocating new ones.
+    ///
+    /// The buffers can be recovered with `Combinations::into_buffers`.
+    fn combinations_with_buffers<'a>(&'a self, k: usize,
+                                     dest: Vec<T>, indices: Vec<usize>)
+        -> Combinations<'a, T>;
+
+    /// The same as `combinations`.
+    fn gray_combinations<'a>(&'a self, k: usize)
+        -> Combinations<'a, T, GrayCombinations>;
+
+    /// Like `combinations`, but in lexicographic order of the positions
+    /// (see `IndexCombinations`).
+    fn lex_combinations<'a>(&'a self, k: usize)
+        -> Combinations<'a, T, IndexCombinations>;
+
+    /// Like `combinations`, but in reverse lexicographic order of the
+    /// positions (see `IndexCombinations::rev_lex`).
+    fn rev_lex_combinations<'a>(&'a self, k: usize)
+        -> Combinations<'a, T, IndexCombinations>;
+}
+
+impl<T> CombinationsIterator<T> for [T] {
+    fn combinations<'a>(&'a self, k: usize) -> Combinations<'a, T> {
+        self.combinations_with_buffers(
+            k, Vec::with_capacity(k), Vec::with_capacity(k))
+    }
+
+    fn combinations_with_buffers<'a>(&'a self, k: usize,
+                                     mut dest: Vec<T>, indices: Vec<usize>)
+        -> Combinations<'a, T>
+    {
+        dest.clear();
+        Combinations {
+            src: self,
+            dest: dest,
+            dirty: 0..0,
+            valid: false,
+            core: GrayCombinations::with_buffer(self.len(), k, indices),
+        }
+    }
+
+    fn gray_combinations<'a>(&'a self, k: usize)
+        -> Combinations<'a, T, GrayCombinations>
+    {
+        self.combinations(k)
+    }
+
+    fn lex_combinations<'a>(&'a self, k: usize)
+        -> Combinations<'a, T, IndexCombinations>
+    {
+        Combinations {
+            src: self,
+            dest: Vec::with_capacity(k),
+            dirty: 0..0,
+            valid: false,
+            core: IndexCombinations::new(self.len(), k),
+        }
+    }
+
+    fn rev_lex_combinations<'a>(&'a self, k: usize)
+        -> Combinations<'a, T, IndexCombinations>
+    {
+        Combinations {
+            src: self,
+            dest: Vec::with_capacity(k),
+            dirty: 0..0,
+            valid: false,
+            core: IndexCombinations::rev_lex(self.len(), k),
+        }
+    }
+}
+
+impl<'a, T, C: CombinationCore> Combinations<'a, T, C> {
+    /// Move to the next combination without materializing it, so that
+    /// `get` returns `None` until the next call to `next`.
+    ///
+    /// Returns `false` when the iteration is over.
+    pub fn advance(&mut self) -> bool {
+        self.valid = false;
+        match self.core.advance() {
+            None => false,
+            Some(r) => {
+                self.dirty = if self.dirty.start == self.dirty.end { r } else {
+                    cmp::min(self.dirty.start, r.start)..cmp::max(self.dirty.end, r.end)
+                };
+                true
+            }
+        }
+    }
+
+    /// Positions in the source slice of the elements of the current
+    /// combination, in increasing order.
+    pub fn current_indices(&self) -> &[usize] {
+        self.core.current()
+    }
+
+    /// References to the elements of the current combination.
+    pub fn current_refs<'b>(&'b self) -> impl Iterator<Item = &'a T> + 'b {
+        let src = self.src;
+        self.core.current().iter().map(move |&j| { &src[j] })
+    }
+
+    /// The current combination, with the positions of its elements, or
+    /// `None` when `get` would return `None`.
+    pub fn current_combination(&self) -> Option<Combination<'_, T>> {
+        if !self.valid { return None }
+        Some(Combination::new(&self.dest, self.core.current(), self.src.len()))
+    }
+
+    /// Give back the internal buffers, to be reused by another iterator.
+    pub fn into_buffers(self) -> (Vec<T>, Vec<usize>) {
+        (self.dest, self.core.into_buffer())
+    }
+}
+
+/// Iterate through combinations of `k` elements.
+///
+/// Each iteration yields a slice of length `k` with distinct elements from the
+/// stored sequence in the same order. If `n` is the length of the source slice,
+/// there are (`n` choose `k`) combinations.
+///
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl<'a, T, C> StreamingIterator for Combinations<'a, T, C> where
+    T: 'a + Clone,
+    C: CombinationCore
+{
+    type Item = [T];
+    fn next(&mut self) -> Option<&[T]> {
+        if !self.advance() { return None }
+        let Combinations { src, ref mut dest, ref mut dirty, ref mut valid, ref core } = *self;
+        let indices = core.current();
+        if dest.len() != indices.len() {
+            dest.clear();
+            dest.extend(indices.iter().map(|&j| { src[j].clone() }));
+        } else {
+            for p in dirty.clone() { dest[p].clone_from(&src[indices[p]]) }
+        }
+        *dirty = 0..0;
+        *valid = true;
+        Some(dest)
+    }
+
+    fn get(&self) -> Option<&[T]> {
+        if self.valid { Some(&self.dest) } else { None }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.core.remaining_hint()
+    }
+
+    fn advance_by(&mut self, m: usize) -> result::Result<(), usize> {
+        self.valid = false;
+        // The elements are cloned again at the next step
+        self.dirty = 0..self.core.current().len();
+        self.core.skip_combinations(m)
+    }
+
+    fn last(self) -> Option<<[T] as ToOwned>::Owned> where [T]: ToOwned {
+        if is_over(self.core.remaining_hint()) { return None }
+        match self.core.last_combination() {
+            Some(last) => {
+                let last: Vec<T> = last.iter().map(|&j| { self.src[j].clone() }).collect();
+                Some(last[..].to_owned())
+            }
+            None => step_to_last(self),
+        }
+    }
+}
+
+/// An iterator over subsequences of `0..n`, i.e., increasing sequences of
+/// indices.
+///
+/// This is the state machine behind `Subsequences`.
+#[derive(Clone, Debug)]
+pub struct IndexSubsequences {
+    n: usize,
+    indices: Vec<usize>,
+    order: Order,
+    first: bool,
+}
+
+impl IndexSubsequences {
+    pub fn new(n: usize) -> IndexSubsequences {
+        IndexSubsequences::with_buffer(n, Vec::new())
+    }
+
+    /// Like `new`, but reuse the given buffer instead of allocating a new one.
+    pub fn with_buffer(n: usize, mut indices: Vec<usize>) -> IndexSubsequences {
+        indices.clear();
+        IndexSubsequences {
+            n: n,
+            indices: indices,
+            order: Order::Lex,
+            first: true,
+        }
+    }
+
+    /// Subsequences in the given order, among `Lex` (as `new`) and
+    /// `RevLex`.
+    pub fn with_order(n: usize, order: Order) -> IndexSubsequences {
+        let indices = match order {
+            Order::Lex => Vec::new(),
+            Order::RevLex => if n == 0 { Vec::new() } else { vec![n - 1] },
+            _ => panic!("Unsupported order {:?}", order),
+        };
+        IndexSubsequences {
+            n: n,
+            indices: indices,
+            order: order,
+            first: true,
+        }
+    }
+
+    /// Subsequences in reverse lexicographic order, from `n-1` down to the
+    /// empty subsequence.
+    pub fn rev_lex(n: usize) -> IndexSubsequences {
+        IndexSubsequences::with_order(n, Order::RevLex)
+    }
+
+    /// Start the iteration from the subsequence with rank `r`
+    /// (see `rank::subsequence_rank`).
+    pub fn from_rank(n: usize, r: usize) -> IndexSubsequences {
+        let mut indices = Vec::new();
+        rank::subsequence_unrank(n, r, &mut indices);
+        IndexSubsequences {
+            n: n,
+            indices: indices,
+            order: Order::Lex,
+            first: true,
+        }
+    }
+
+    /// Like `from_rank`, but return an error instead of panicking.
+    pub fn try_from_rank(n: usize, r: usize) -> Result<IndexSubsequences> {
+        let mut indices = Vec::new();
+        rank::try_subsequence_unrank(n, r, &mut indices)?;
+        Ok(IndexSubsequences {
+            n: n,
+            indices: indices,
+            order: Order::Lex,
+            first: true,
+        })
+    }
+
+    /// The current subsequence.
+    pub fn current(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Give back the internal buffer, to be reused by another iterator.
+    pub fn into_buffer(self) -> Vec<usize> {
+        self.indices
+    }
+
+    /// Move to the next subsequence.
+    ///
+    /// Returns the first position that changed, or `None` if the iteration
+    /// is over (in which case it starts again from the first subsequence).
+    pub fn advance(&mut self) -> Option<usize> {
+        let IndexSubsequences { n, ref mut indices, order, ref mut first } = *self;
+        // The first call returns the initial subsequence
+        if *first { *first = false; return Some(0) }
+        if order == Order::RevLex {
+            let l = indices.len();
+            let last = match indices.last() {
+                None => {
+                    // Wrap around
+                    if n > 0 { indices.push(n - 1) }
+                    *first = true;
+                    return None;
+                }
+                Some(&last) => last,
+            };
+            // Decrement the last index and append n-1, or remove it if it is
+            // right after the previous one
+            let least = if l == 1 { 0 } else { indices[l - 2] + 1 };
+            if last > least {
+                indices[l - 1] = last - 1;
+                indices.push(n - 1);
+            } else {
+                indices.pop();
+            }
+            return Some(l - 1);
+        }
+        let i = indices.last().map_or(0, |&i| { i+1 });
+        // Push an element while we can
+        if i < n {
+            indices.push(i);
+            return Some(indices.len() - 1)
+        }
+        // The end of the input is reached,
+        // pop and increment the previous index
+        indices.pop();
+        match indices.last_mut() {
+            None => { *first = true; None }, // Wrap around
+            Some(i) => { *i += 1; Some(indices.len() - 1) },
+        }
+    }
+}
+
+/// Iterate through the subsequences of `0..n`.
+///
+/// Resets after returning `None`.
+impl StreamingIterator for IndexSubsequences {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        match self.advance() {
+            None => None,
+            Some(_) => Some(&self.indices),
+        }
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first { None } else { Some(&self.indices) }
+    }
+
+    /// Exact, the iteration being over when it starts again.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        exact_size_hint(self.remaining())
+    }
+
+    /// The last subsequence is `[n - 1]`, or empty in reverse order.
+    fn last(self) -> Option<Vec<usize>> {
+        if is_over(self.size_hint()) { return None }
+        if self.order == Order::RevLex || self.n == 0 { Some(Vec::new()) } else { Some(vec![self.n - 1]) }
+    }
+
+    /// Unranks the subsequence at the new position.
+    fn advance_by(&mut self, m: usize) -> result::Result<(), usize> {
+        seek_by(self, m)
+    }
+}
+
+// The reverse lexicographic order is the reverse of the lexicographic order
+impl Seek for IndexSubsequences {
+    fn total(&self) -> Option<u128> {
+        if self.n < 128 { Some(1 << self.n) } else { None }
+    }
+
+    fn remaining(&self) -> Option<u128> {
+        let total = self.total()?;
+        let r: u128 = rank::subsequence_rank_as(self.n, &self.indices);
+        Some(match (self.order, self.first) {
+            (Order::RevLex, true) => r + 1,
+            (Order::RevLex, false) => r,
+            (_, true) => total - r,
+            (_, false) => total - r - 1,
+        })
+    }
+
+    fn seek(&mut self, p: u128, first: bool) {
+        let r = if self.order == Order::RevLex { self.total().unwrap() - 1 - p } else { p };
+        rank::subsequence_unrank_as(self.n, &r, &mut self.indices);
+        self.first = first;
+    }
+}
+
+/// An iterator over subsets/subsequences.
+///
+/// Gives directly the subsequences as slices of an internal vector.
+// The elements are only materialized in dest by the StreamingIterator
+// instance, which needs T: Clone. dest is a prefix of the current
+// subsequence, and all of it when valid is set.
+#[derive(Clone, Debug)]
+pub struct Subsequences<'a, T> where T: 'a {
     src: &'a [T],
     dest: Vec<T>,
+    valid: bool,
+    core: IndexSubsequences,
+}
+
+/// The state is that of the underlying `IndexSubsequences`, the elements
+/// being cloned again from the source on restore.
+impl<'a, T> Snapshot for Subsequences<'a, T> where T: 'a + Clone {
+    type State = IndexSubsequences;
+    fn snapshot(&self) -> IndexSubsequences {
+        self.core.snapshot()
+    }
+
+    fn restore(&mut self, state: &IndexSubsequences) {
+        let Subsequences { src, ref mut dest, ref mut valid, ref mut core } = *self;
+        core.restore(state);
+        dest.clear();
+        *valid = core.get().is_some();
+        if *valid { dest.extend(core.current().iter().map(|&j| { src[j].clone() })) }
+    }
+}
+
+pub trait SubsequencesIterator<T> {
+    fn subsequences<'a>(&'a self) -> Subsequences<'a, T>;
+
+    /// Like `subsequences`, but reuse the given buffers for the internal
+    /// state instead of allocating new ones.
+    ///
+    /// The buffers can be recovered with `Subsequences::into_buffers`.
+    fn subsequences_with_buffers<'a>(&'a self,
+                                     dest: Vec<T>, indices: Vec<usize>)
+        -> Subsequences<'a, T>;
+
+    /// Like `subsequences`, but in reverse lexicographic order of the
+    /// positions (see `IndexSubsequences::rev_lex`).
+    fn rev_lex_subsequences<'a>(&'a self) -> Subsequences<'a, T>;
+}
+
+impl<T> SubsequencesIterator<T> for [T] {
+    fn subsequences<'a>(&'a self) -> Subsequences<'a, T> {
+        self.subsequences_with_buffers(Vec::new(), Vec::new())
+    }
+
+    fn subsequences_with_buffers<'a>(&'a self,
+                                     mut dest: Vec<T>, indices: Vec<usize>)
+        -> Subsequences<'a, T>
+    {
+        dest.clear();
+        Subsequences {
+            src: self,
+            dest: dest,
+            valid: false,
+            core: IndexSubsequences::with_buffer(self.len(), indices),
+        }
+    }
+
+    fn rev_lex_subsequences<'a>(&'a self) -> Subsequences<'a, T> {
+        Subsequences {
+            src: self,
+            dest: Vec::new(),
+            valid: false,
+            core: IndexSubsequences::rev_lex(self.len()),
+        }
+    }
+}
+
+impl<'a, T> Subsequences<'a, T> {
+    /// Move to the next subsequence without materializing it, so that
+    /// `get` returns `None` until the next call to `next`.
+    ///
+    /// Returns `false` when the iteration is over.
+    pub fn advance(&mut self) -> bool {
+        self.valid = false;
+        match self.core.advance() {
+            None => { self.dest.clear(); false },
+            Some(i) => { self.dest.truncate(i); true }
+        }
+    }
+
+    /// Positions in the source slice of the elements of the current
+    /// subsequence, in increasing order.
+    pub fn current_indices(&self) -> &[usize] {
+        self.core.current()
+    }
+
+    /// References to the elements of the current subsequence.
+    pub fn current_refs<'b>(&'b self) -> impl Iterator<Item = &'a T> + 'b {
+        let src = self.src;
+        self.core.current().iter().map(move |&j| { &src[j] })
+    }
+
+    /// Give back the internal buffers, to be reused by another iterator.
+    pub fn into_buffers(self) -> (Vec<T>, Vec<usize>) {
+        (self.dest, self.core.into_buffer())
+    }
+}
+
+/// Iterate through the subsequences of a stored sequence.
+///
+/// If `n` is the length of the source slice, there are `2^n` subsequences.
+///
+/// Resets after returning `None`.
+impl<'a, T> StreamingIterator for Subsequences<'a, T> where T: 'a + Clone {
+    type Item = [T];
+    fn next(&mut self) -> Option<&[T]> {
+        if !self.advance() { return None }
+        let Subsequences { src, ref mut dest, ref mut valid, ref core } = *self;
+        let l = dest.len();
+        dest.extend(core.current()[l..].iter().map(|&j| { src[j].clone() }));
+        *valid = true;
+        Some(dest)
+    }
+
+    fn get(&self) -> Option<&[T]> {
+        if self.valid { Some(&self.dest) } else { None }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.core.size_hint()
+    }
+
+    fn advance_by(&mut self, m: usize) -> result::Result<(), usize> {
+        // The elements are cloned again at the next step
+        self.valid = false;
+        self.dest.clear();
+        self.core.advance_by(m)
+    }
+
+    fn last(self) -> Option<<[T] as ToOwned>::Owned> where [T]: ToOwned {
+        let src = self.src;
+        let last: Vec<T> = self.core.last()?.iter().map(|&j| { src[j].clone() }).collect();
+        Some(last[..].to_owned())
+    }
+}
+
+/// An iterator over multisets of `k` elements of `0..n`, as multiplicity
+/// vectors: the `i`-th entry is the number of copies of `i`.
+///
+/// The order is that of combinations with replacement in lexicographic
+/// order, from `[k, 0, ..., 0]` to `[0, ..., 0, k]`. There are
+/// `choose(n + k - 1, k)` multisets.
+#[derive(Clone, Debug)]
+pub struct Multisets {
+    mult: Vec<usize>,
+    first: bool,
+    done: bool,
+}
+
+impl Multisets {
+    pub fn new(n: usize, k: usize) -> Multisets {
+        let mut mult = vec![0; n];
+        if n > 0 { mult[0] = k }
+        // There is nothing to choose from
+        let done = n == 0 && k > 0;
+        Multisets { mult: mult, first: true, done: done }
+    }
+
+    /// The current multiplicities.
+    pub fn current(&self) -> &[usize] {
+        &self.mult
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for Multisets {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        if self.done { return None }
+        if self.first { self.first = false; return Some(&self.mult) }
+        // Move one copy of the last element before the end to the next
+        // position, along with the copies of the last element
+        let n = self.mult.len();
+        let i_opt = if n == 0 { None } else {
+            self.mult[..n - 1].iter().rposition(|&m| { m > 0 })
+        };
+        match i_opt {
+            None => { self.done = true; None }
+            Some(i) => {
+                let t = self.mult[n - 1];
+                self.mult[n - 1] = 0;
+                self.mult[i] -= 1;
+                self.mult[i + 1] = t + 1;
+                Some(&self.mult)
+            }
+        }
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(&self.mult) }
+    }
+}
+
+/// An operator over permutations.
+///
+/// The permutations are not copied: a single vector is rearranged by one
+/// swap of adjacent elements per step, in the same order as
+/// `IndexPermutations`, and an immutable slice into it is returned.
+///
+/// This minimal change order is not double-ended. For permutations which
+/// can be yielded from both ends, see `PermutationsIterator::lex_permutations`.
+#[derive(Clone, Debug)]
+pub struct Permutations<T> {
+    dest: Vec<T>,
+    swaps: IndexPermutations,
+}
+
+/// The state is that of the underlying `IndexPermutations`. The elements are
+/// rearranged in place on restore, without cloning them.
+impl<T> Snapshot for Permutations<T> {
+    type State = IndexPermutations;
+    fn snapshot(&self) -> IndexPermutations {
+        self.swaps.snapshot()
+    }
+
+    fn restore(&mut self, state: &IndexPermutations) {
+        // dest is the source rearranged by the current permutation: undo it
+        self.swaps.current_permutation().apply_inverse_to(&mut self.dest);
+        state.current_permutation().apply_to(&mut self.dest);
+        self.swaps.restore(state);
+    }
+}
+
+pub trait PermutationsIterator<T> {
+    fn permutations_iter(&self) -> Permutations<T>;
+
+    /// The distinct permutations in lexicographic order, from both ends
+    /// (see `LexPermutations`).
+    fn lex_permutations(&self) -> LexPermutations<T> where T: Ord;
+}
+
+impl<T> PermutationsIterator<T> for [T] where T: Clone {
+    fn permutations_iter(&self) -> Permutations<T> {
+        Permutations {
+            dest: self.to_vec(),
+            swaps: index_permutations(self.len()),
+        }
+    }
+
+    fn lex_permutations(&self) -> LexPermutations<T> where T: Ord {
+        LexPermutations::new(self)
+    }
+}
+
+impl<T> Permutations<T> {
+    /// The current permutation.
+    pub fn current(&self) -> &[T] {
+        &self.dest
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl<T> StreamingIterator for Permutations<T> {
+    type Item = [T];
+    fn next(&mut self) -> Option<&[T]> {
+        let Permutations {
+            ref mut dest,
+            ref mut swaps,
+        } = *self;
+        match swaps.advance() {
+            None => None,
+            Some((0, 0)) => Some(dest),
+            Some((a, b)) => { dest.swap(a, b); Some(dest) },
+        }
+    }
+
+    fn get(&self) -> Option<&[T]> {
+        self.swaps.get().map(|_| { &self.dest[..] })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.swaps.size_hint()
+    }
+
+    /// Rearranges the elements in place, without cloning them.
+    fn advance_by(&mut self, m: usize) -> result::Result<(), usize> {
+        let perm = self.swaps.current_permutation().clone();
+        let result = self.swaps.advance_by(m);
+        perm.apply_inverse_to(&mut self.dest);
+        self.swaps.current_permutation().apply_to(&mut self.dest);
+        result
+    }
+
+    fn last(mut self) -> Option<<[T] as ToOwned>::Owned> where [T]: ToOwned {
+        let last = Permutation::from_vec(self.swaps.clone().last()?).unwrap();
+        self.swaps.current_permutation().apply_inverse_to(&mut self.dest);
+        last.apply_to(&mut self.dest);
+        Some(self.dest[..].to_owned())
+    }
+}
+
+/// An iterator over permutations of `0..n`, by adjacent transpositions
+/// ("plain changes", or Steinhaus-Johnson-Trotter order).
+///
+/// Unlike `Permutations`, it does not need to clone any element: most uses
+/// can permute indices and index their own data. The transposition applied
+/// at each step is available with `last_swap`, to update such data in place.
+// Algorithm P from Knuth, TAOCP 7.2.1.2. c[j] and o[j] are the
+// inversion counter and the direction of the element j + 1.
+#[derive(Clone, Debug)]
+pub struct IndexPermutations {
+    perm: Permutation,
+    c: Vec<usize>,
+    o: Vec<bool>,
+    swap: Option<(usize, usize)>,
+    first: bool,
+    done: bool,
+}
+
+/// Iterate through permutations of `0..n`, starting from the identity.
+///
+/// There are `n!` permutations.
+pub fn index_permutations(n: usize) -> IndexPermutations {
+    IndexPermutations {
+        perm: Permutation::identity(n),
+        c: vec![0; n],
+        o: vec![true; n],
+        swap: None,
+        first: true,
+        done: false,
+    }
+}
+
+impl IndexPermutations {
+    /// Permutations in the given order, which must be `GrayMinimalChange`
+    /// (as `index_permutations`). For lexicographic order, see
+    /// `next_permutation`.
+    pub fn with_order(n: usize, order: Order) -> IndexPermutations {
+        assert!(order == Order::GrayMinimalChange, "Unsupported order {:?}", order);
+        index_permutations(n)
+    }
+
+    /// The current permutation.
+    pub fn current(&self) -> &[usize] {
+        self.perm.as_slice()
+    }
+
+    /// The current permutation, as a value.
+    pub fn current_permutation(&self) -> &Permutation {
+        &self.perm
+    }
+
+    /// Yield `Permutation` values instead of slices.
+    pub fn as_permutations(self) -> AsPermutations {
+        AsPermutations { iter: self }
+    }
+
+    /// The positions `(i, i + 1)` swapped from one permutation to the next,
+    /// starting from the current permutation (or the identity, if the
+    /// iteration has not started).
+    pub fn transitions(mut self) -> PermutationTransitions {
+        if self.first { self.advance(); }
+        PermutationTransitions { iter: self }
+    }
+
+    /// The positions `(i, i + 1)` swapped by the last step, or `None` before
+    /// the second permutation.
+    pub fn last_swap(&self) -> Option<(usize, usize)> {
+        self.swap
+    }
+
+    /// Move to the next permutation.
+    ///
+    /// Returns the positions which were swapped, `(0, 0)` the first time,
+    /// or `None` if the iteration is over.
+    pub fn advance(&mut self) -> Option<(usize, usize)> {
+        if self.first { self.first = false; return Some((0, 0)) }
+        if self.done { return None }
+        let mut j = self.perm.len();
+        let mut s = 0;
+        while j > 1 {
+            let (c, o) = (self.c[j - 1], self.o[j - 1]);
+            if o && c + 1 < j || !o && c > 0 {
+                let q = if o { c + 1 } else { c - 1 };
+                let (a, b) = (j - c + s - 1, j - q + s - 1);
+                let swap = (cmp::min(a, b), cmp::max(a, b));
+                self.perm.swap(a, b);
+                self.c[j - 1] = q;
+                self.swap = Some(swap);
+                return Some(swap)
+            }
+            if o { s += 1 }
+            self.o[j - 1] = !o;
+            j -= 1;
+        }
+        self.done = true;
+        None
+    }
+}
+
+/// Iterate through the permutations of `0..n`.
+///
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for IndexPermutations {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        match self.advance() {
+            None => None,
+            Some(_) => Some(self.perm.as_slice()),
+        }
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(self.perm.as_slice()) }
+    }
+
+    /// Exact.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        exact_size_hint(self.remaining())
+    }
+
+    /// Unranks the permutation at the new position.
+    fn advance_by(&mut self, m: usize) -> result::Result<(), usize> {
+        seek_by(self, m)
+    }
+
+    /// The last permutation swaps `0` and `1`.
+    fn last(self) -> Option<Vec<usize>> {
+        if is_over(self.size_hint()) { return None }
+        let n = self.perm.len();
+        let mut last: Vec<usize> = (0..n).collect();
+        if n >= 2 { last.swap(0, 1) }
+        Some(last)
+    }
+}
+
+// The counters c[j] are the digits of the rank in a reflected mixed radix,
+// where the j-th digit is at most j, and counts down when o[j] is false,
+// i.e., when the number formed by the previous digits is odd. c[j] is also
+// the number of elements less than j after j in the permutation.
+impl Seek for IndexPermutations {
+    fn total(&self) -> Option<u128> {
+        (2..(self.perm.len() + 1)).try_fold(1u128, |f, j| { f.checked_mul(j as u128) })
+    }
+
+    fn remaining(&self) -> Option<u128> {
+        let total = self.total()?;
+        if self.done { return Some(0) }
+        let rank = (1..self.perm.len()).fold(0u128, |r, j| {
+            let d = if self.o[j] { self.c[j] } else { j - self.c[j] };
+            r * (j + 1) as u128 + d as u128
+        });
+        let consumed = if self.first { 0 } else { 1 };
+        Some(total - rank - consumed)
+    }
+
+    fn seek(&mut self, p: u128, first: bool) {
+        let n = self.perm.len();
+        let mut r = p;
+        for j in (1..n).rev() {
+            let d = (r % (j + 1) as u128) as usize;
+            r /= (j + 1) as u128;
+            self.o[j] = r.is_multiple_of(2);
+            self.c[j] = if self.o[j] { d } else { j - d };
+        }
+        // Insert the elements in increasing order, before c[j] smaller ones
+        let mut image = Vec::with_capacity(n);
+        for j in 0..n { image.insert(j - self.c[j], j) }
+        self.perm = Permutation::from_vec(image).unwrap();
+        self.swap = None;
+        self.first = first;
+        self.done = false;
+    }
+}
+
+/// An iterator over the adjacent transpositions between consecutive
+/// permutations in plain changes order, see `IndexPermutations::transitions`.
+#[derive(Clone, Debug)]
+pub struct PermutationTransitions {
+    iter: IndexPermutations,
+}
+
+impl Iterator for PermutationTransitions {
+    type Item = (usize, usize);
+    fn next(&mut self) -> Option<(usize, usize)> {
+        self.iter.advance()
+    }
+}
+
+/// An iterator over permutations of `0..n` as `Permutation` values.
+#[derive(Clone, Debug)]
+pub struct AsPermutations {
+    iter: IndexPermutations,
+}
+
+impl StreamingIterator for AsPermutations {
+    type Item = Permutation;
+    fn next(&mut self) -> Option<&Permutation> {
+        match self.iter.advance() {
+            None => None,
+            Some(_) => Some(&self.iter.perm),
+        }
+    }
+
+    fn get(&self) -> Option<&Permutation> {
+        self.iter.get().map(|_| { &self.iter.perm })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+
+    fn advance_by(&mut self, m: usize) -> result::Result<(), usize> {
+        self.iter.advance_by(m)
+    }
+
+    fn last(self) -> Option<Permutation> {
+        self.iter.last().map(|last| { Permutation::from_vec(last).unwrap() })
+    }
+}
+
+/// A constraint on the image of a position, for `ConstrainedPermutations`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointConstraint {
+    /// No constraint.
+    Any,
+    /// The position is a fixed point.
+    Fixed,
+    /// The position is not a fixed point.
+    Moved,
+}
+
+/// An iterator over permutations of `0..n` with constraints on their fixed
+/// points, in lexicographic order.
+///
+/// For example, derangements are the permutations where every point is
+/// `Moved`. The permutations are generated directly by backtracking, which
+/// only reaches dead ends when the last few positions are constrained.
+#[derive(Clone, Debug)]
+pub struct ConstrainedPermutations {
+    constraints: Vec<PointConstraint>,
+    perm: Vec<usize>,
+    used: Vec<bool>,
+    first: bool,
+    done: bool,
+}
+
+impl ConstrainedPermutations {
+    /// Permutations with the given constraint for every position.
+    pub fn new(constraints: Vec<PointConstraint>) -> ConstrainedPermutations {
+        let n = constraints.len();
+        ConstrainedPermutations {
+            constraints: constraints,
+            perm: vec![0; n],
+            used: vec![false; n],
+            first: true,
+            done: false,
+        }
+    }
+
+    /// Permutations of `0..n` which fix the points of `fixed` and move the
+    /// points of `moved`.
+    ///
+    /// # Panics
+    ///
+    /// If a point is not in `0..n`, or is both fixed and moved.
+    pub fn with_points(n: usize, fixed: &[usize], moved: &[usize])
+        -> ConstrainedPermutations
+    {
+        let mut constraints = vec![PointConstraint::Any; n];
+        for &i in fixed.iter().chain(moved) {
+            assert!(i < n, "{} is not a point of 0..{}", i, n);
+        }
+        for &i in fixed { constraints[i] = PointConstraint::Fixed }
+        for &i in moved {
+            assert!(constraints[i] != PointConstraint::Fixed,
+                    "{} cannot be both fixed and moved", i);
+            constraints[i] = PointConstraint::Moved;
+        }
+        ConstrainedPermutations::new(constraints)
+    }
+
+    /// Derangements of `0..n`: permutations without fixed points.
+    pub fn derangements(n: usize) -> ConstrainedPermutations {
+        ConstrainedPermutations::new(vec![PointConstraint::Moved; n])
+    }
+
+    /// The current permutation.
+    pub fn current(&self) -> &[usize] {
+        &self.perm
+    }
+
+    /// Move to the next permutation.
+    ///
+    /// Returns `false` when the iteration is over.
+    pub fn advance(&mut self) -> bool {
+        if self.done { return false }
+        let n = self.perm.len();
+        let found = if self.first {
+            self.first = false;
+            self.search(0, 0)
+        } else if n == 0 {
+            false
+        } else {
+            let v = self.perm[n - 1];
+            self.used[v] = false;
+            self.search(n - 1, v + 1)
+        };
+        self.done = !found;
+        found
+    }
+
+    // Fill the positions from i on, starting with the value start at i,
+    // backtracking if necessary.
+    fn search(&mut self, mut i: usize, mut start: usize) -> bool {
+        let n = self.perm.len();
+        while i < n {
+            match (start..n).find(|&v| { self.allowed(i, v) }) {
+                Some(v) => {
+                    self.perm[i] = v;
+                    self.used[v] = true;
+                    i += 1;
+                    start = 0;
+                }
+                None => {
+                    if i == 0 { return false }
+                    i -= 1;
+                    let v = self.perm[i];
+                    self.used[v] = false;
+                    start = v + 1;
+                }
+            }
+        }
+        true
+    }
+
+    fn allowed(&self, i: usize, v: usize) -> bool {
+        // The image of a fixed point is reserved
+        let reserved = self.constraints[v] == PointConstraint::Fixed;
+        !self.used[v] && match self.constraints[i] {
+            PointConstraint::Any => !reserved,
+            PointConstraint::Fixed => v == i,
+            PointConstraint::Moved => v != i && !reserved,
+        }
+    }
+}
+
+/// Iterate through the constrained permutations.
+///
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for ConstrainedPermutations {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        if self.advance() { Some(&self.perm) } else { None }
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(&self.perm) }
+    }
+}
+
+/// Rearrange `v` into the next permutation in lexicographic order.
+///
+/// Returns `false`, leaving `v` sorted in increasing order, if `v` was the
+/// last permutation.
+pub fn next_permutation<T: Ord>(v: &mut [T]) -> bool {
+    let i = match v.windows(2).rposition(|w| { w[0] < w[1] }) {
+        None => { v.reverse(); return false }
+        Some(i) => i,
+    };
+    let j = v.iter().rposition(|x| { v[i] < *x }).unwrap();
+    v.swap(i, j);
+    v[i + 1..].reverse();
+    true
+}
+
+/// Rearrange `v` into the previous permutation in lexicographic order.
+///
+/// Returns `false`, leaving `v` sorted in decreasing order, if `v` was the
+/// first permutation.
+pub fn prev_permutation<T: Ord>(v: &mut [T]) -> bool {
+    let i = match v.windows(2).rposition(|w| { w[0] > w[1] }) {
+        None => { v.reverse(); return false }
+        Some(i) => i,
+    };
+    let j = v.iter().rposition(|x| { v[i] > *x }).unwrap();
+    v.swap(i, j);
+    v[i + 1..].reverse();
+    true
+}
+
+/// An iterator over the distinct permutations of a slice in lexicographic
+/// order, from the sorted one to the reverse sorted one, with
+/// `next_permutation`, and from the back with `prev_permutation`.
+///
+/// Equal elements are not distinguished, so every arrangement is yielded
+/// once.
+// lo is the next item from the front, or the last one yielded from the front
+// if front is set, and likewise for hi from the back. The items left are
+// those strictly between the yielded ones. back_last is set if the last item
+// was yielded from the back.
+#[derive(Clone, Debug)]
+pub struct LexPermutations<T> {
+    lo: Vec<T>,
+    hi: Vec<T>,
+    front: bool,
+    back: bool,
+    back_last: bool,
+    done: bool,
+}
+
+impl<T: Ord + Clone> LexPermutations<T> {
+    pub fn new(src: &[T]) -> LexPermutations<T> {
+        let mut lo = src.to_vec();
+        lo.sort();
+        let mut hi = lo.clone();
+        hi.reverse();
+        LexPermutations {
+            lo: lo,
+            hi: hi,
+            front: false,
+            back: false,
+            back_last: false,
+            done: false,
+        }
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl<T: Ord> StreamingIterator for LexPermutations<T> {
+    type Item = [T];
+    fn next(&mut self) -> Option<&[T]> {
+        if self.done { return None }
+        if self.front && !next_permutation(&mut self.lo) || self.back && self.lo >= self.hi {
+            self.done = true;
+            return None;
+        }
+        self.front = true;
+        self.back_last = false;
+        Some(&self.lo)
+    }
+
+    fn get(&self) -> Option<&[T]> {
+        if self.done || !self.front && !self.back {
+            None
+        } else if self.back_last {
+            Some(&self.hi)
+        } else {
+            Some(&self.lo)
+        }
+    }
+}
+
+impl<T: Ord> DoubleEndedStreamingIterator for LexPermutations<T> {
+    fn next_back(&mut self) -> Option<&[T]> {
+        if self.done { return None }
+        if self.back && !prev_permutation(&mut self.hi) || self.front && self.hi <= self.lo {
+            self.done = true;
+            return None;
+        }
+        self.back = true;
+        self.back_last = true;
+        Some(&self.hi)
+    }
+}
+
+/// An iterator over pairs of elements.
+///
+/// A `Product` is a variant of `std::iter::FlatMap` with a constant iterator.
+///
+/// Not very useful, as it can simply be replaced with a nested loop...
+pub struct Product<I: Iterator, J> {
+    iter_b_const: J,
+    cur_a: Option<I::Item>,
+    iter_a: I,
+    iter_b: J,
+}
+
+// Deriving would not require `I::Item: Clone`
+impl<I: Clone + Iterator, J: Clone> Clone for Product<I, J> where
+    I::Item: Clone
+{
+    fn clone(&self) -> Self {
+        Product {
+            iter_b_const: self.iter_b_const.clone(),
+            cur_a: self.cur_a.clone(),
+            iter_a: self.iter_a.clone(),
+            iter_b: self.iter_b.clone(),
+        }
+    }
+}
+
+impl<I: Iterator + fmt::Debug, J: fmt::Debug> fmt::Debug for Product<I, J> where
+    I::Item: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Product")
+            .field("iter_b_const", &self.iter_b_const)
+            .field("cur_a", &self.cur_a)
+            .field("iter_a", &self.iter_a)
+            .field("iter_b", &self.iter_b)
+            .finish()
+    }
+}
+
+impl<I: Sized + Iterator, J: Clone + Iterator> Product<I, J> where
+    I::Item: Clone
+{
+    pub fn new(mut i: I, j: J) -> Self
+    {
+        Product {
+            iter_b_const: j.clone(),
+            cur_a: i.next(),
+            iter_a: i,
+            iter_b: j }
+    }
+}
+
+impl<I: Iterator, J: Clone + Iterator> Iterator for Product<I, J> where
+    I::Item: Clone
+{
+    type Item = (I::Item, J::Item);
+    fn next(&mut self) -> Option<<Self as Iterator>::Item> {
+        // This loops infinitely if self.iter_b is empty
+        // and self.iter_a is infinite.
+        loop {
+            match (self.cur_a.as_ref(), self.iter_b.next()) {
+                (Some(x), Some(y)) => return Some((x.clone(), y)),
+                (None, _) => return None,
+                _ => {}
+            }
+            self.cur_a = self.iter_a.next();
+            self.iter_b = self.iter_b_const.clone();
+        }
+    }
+}
+
+/// An iterator over binary trees.
+///
+/// There are `choose(2 * n, n) / (n + 1)` trees with `n + 1` leaves.
+///
+/// Successive trees differ at a single position, see `changed`, and every
+/// step takes constant time.
+// start[i] is the value of indices[i] at the start of its current sweep (see
+// next()), it is only brought up to date when the position moves again.
+// focus holds the focus pointers: when focus[i] != i, the positions
+// focus[i] + 1 ..= i have finished their sweeps, and focus[i] is the next one
+// to their left which has not, 0 if there is none. changed is the position
+// updated by the last step.
+#[derive(Clone, Debug)]
+pub struct Catalan {
     indices: Vec<usize>,
+    start: Vec<usize>,
+    focus: Vec<usize>,
+    changed: Option<usize>,
+    first: bool,
+    done: bool,
+}
+
+impl Catalan {
+    pub fn new(n: usize) -> Catalan {
+        Catalan::with_buffer(n, Vec::with_capacity(n - 1))
+    }
+
+    /// Like `new`, but reuse the given buffer instead of allocating a new one.
+    pub fn with_buffer(n: usize, mut indices: Vec<usize>) -> Catalan {
+        indices.clear();
+        indices.extend(0..n-1);
+        Catalan {
+            start: indices.clone(),
+            focus: indices.clone(),
+            indices: indices,
+            changed: None,
+            first: true,
+            done: false,
+        }
+    }
+
+    /// The position which changed in the last step, or `None` before the
+    /// second tree.
+    pub fn changed(&self) -> Option<usize> {
+        self.changed
+    }
+
+    /// Give back the internal buffer, to be reused by another iterator.
+    pub fn into_buffer(self) -> Vec<usize> {
+        self.indices
+    }
+
+    /// The current tree, in the encoding described below.
+    pub fn current_indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    // The number of trees after the current one: every position which has
+    // not finished its sweep is followed by the rest of it, and each value
+    // there by all the ways to complete the prefix.
+    fn remaining(&self) -> Option<u128> {
+        let m = self.indices.len();
+        let mut remaining = 0u128;
+        let mut i = m.saturating_sub(1);
+        while i > 0 {
+            if self.focus[i] != i { i = self.focus[i]; continue }
+            let (lo, x) = (self.indices[i - 1], self.indices[i]);
+            let s = if x == sweep_end(i, self.start[i]) { x } else { self.start[i] };
+            let rest = if x == s {
+                dyck_paths(m, i, lo)? - dyck_paths(m, i + 1, s)?
+            } else if s > x {
+                dyck_paths(m, i, x + 1)? - dyck_paths(m, i + 1, s)?
+            } else {
+                dyck_paths(m, i, x + 1)?
+            };
+            remaining = remaining.checked_add(rest)?;
+            i -= 1;
+        }
+        Some(remaining)
+    }
+}
+
+/// Format a binary tree yielded by `Catalan` as a string of balanced
+/// parentheses: in preorder, every internal node gives `(` and every leaf
+/// but the last one gives `)`.
+///
+/// For example, `0 1 2 3 3 4` (see below) gives `()()()(()())`.
+pub fn catalan_parens(tree: &[usize]) -> CatalanParens<'_> {
+    CatalanParens { tree: tree }
+}
+
+/// The parenthesis string of a binary tree, see `catalan_parens`.
+#[derive(Clone, Copy, Debug)]
+pub struct CatalanParens<'a> {
+    tree: &'a [usize],
+}
+
+impl<'a> fmt::Display for CatalanParens<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The labels are the leaves before which the nodes are inserted,
+        // in nondecreasing order
+        let mut nodes = self.tree.iter().peekable();
+        for leaf in 0..self.tree.len() {
+            while nodes.peek() == Some(&&leaf) {
+                nodes.next();
+                write!(f, "(")?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+/// Iterate through binary trees with n leaves.
+///
+/// The returned slices represent sequences of nodes from the traversals of
+/// binary trees, where a label is the index of the leftmost leaf in the
+/// subtree rooted at the corresponding node.
+///
+/// Alternatively, starting with a sequence of leaves:
+///
+/// `0 1 2 3 4 5 6`,
+/// 
+/// the slice
+///
+/// `0 1 2 3 3 4`
+/// 
+/// can be associated with a prefix expression, obtained by inserting an
+/// operator at the corresponding positions between the leaves:
+///
+/// `+ 0 + 1 + 2 + + 3 + 4 5 6`
+///
+/// Brackets reveal the tree structure (with a LISP flavour):
+///
+/// `(+ 0 (+ 1 (+ 2 (+ (+ 3 (+ 4 5)) 6))))`
+///
+/// That is equivalent to the infix expression:
+///
+/// `0 + (1 + (2 + ((3 + (4 + 5)) + 6)))`
+///
+/// # Algorithm for `next()`
+///
+/// The slices are the sequences with `indices[0] = 0` and
+/// `indices[i - 1] <= indices[i] <= i`. They are listed as a mixed-radix
+/// Gray code, where `indices[i]` moves faster than `indices[i - 1]`: for
+/// every prefix, `indices[i]` sweeps through its range, from the value it
+/// starts with, then up from `indices[i - 1]`, skipping the start.
+///
+/// A sweep which starts at `i` ends at `i - 1`, and any other one ends at
+/// `i`. Both are at least `indices[i - 1]` for every prefix, so the prefix
+/// can change while `indices[i]` stays put, and every step changes a single
+/// position.
+///
+/// The position to change is found with focus pointers, as in Knuth's
+/// loopless reflected Gray code (TAOCP 7.2.1.1, Algorithm H), so no loop is
+/// needed.
+impl StreamingIterator for Catalan {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        let Catalan {
+            ref mut indices,
+            ref mut start,
+            ref mut focus,
+            ref mut changed,
+            ref mut first,
+            ref mut done,
+        } = *self;
+        if *first { *first = false; return Some(indices) }
+        if *done { return None }
+        let m = indices.len();
+        let i = if m == 0 { 0 } else { focus[m - 1] };
+        if i == 0 { *done = true; return None }
+        focus[m - 1] = m - 1;
+        let (lo, x) = (indices[i - 1], indices[i]);
+        // The previous sweep is over, a new one starts here
+        if x == sweep_end(i, start[i]) { start[i] = x }
+        let s = start[i];
+        let y = if x == s {
+            if lo == s { lo + 1 } else { lo }
+        } else if x + 1 == s {
+            x + 2
+        } else {
+            x + 1
+        };
+        indices[i] = y;
+        *changed = Some(i);
+        if y == sweep_end(i, s) {
+            focus[i] = focus[i - 1];
+            focus[i - 1] = i - 1;
+        }
+        Some(indices)
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(&self.indices) }
+    }
+
+    /// Exact.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done { return (0, Some(0)) }
+        let first = if self.first { 1 } else { 0 };
+        exact_size_hint(self.remaining().and_then(|r| { r.checked_add(first) }))
+    }
+
+    /// Every position ends at the end of its sweep. `indices[i]` sweeps once
+    /// for every prefix, and there is an odd number of them, a Catalan number,
+    /// exactly when `i + 1` is a power of two: then the last tree has
+    /// `indices[i] = i - 1`, and `indices[i] = i` elsewhere.
+    fn last(self) -> Option<Vec<usize>> {
+        if is_over(self.size_hint()) { return None }
+        Some((0..self.indices.len()).map(|i| {
+            if i > 0 && i & (i + 1) == 0 { i - 1 } else { i }
+        }).collect())
+    }
+}
+
+// Where the sweep of indices[i] starting at s ends.
+fn sweep_end(i: usize, s: usize) -> usize {
+    if s == i { i - 1 } else { i }
+}
+
+// The number of ways to end a Dyck path of semilength m from a point after
+// a up steps and b <= a down steps, by the reflection principle: the paths
+// going below the axis are those from (b - 1, a + 1), with r = 2m - a - b
+// steps.
+fn dyck_paths(m: usize, a: usize, b: usize) -> Option<u128> {
+    let r = 2 * m - a - b;
+    Some(checked_choose(r, m - a)? - checked_choose(r, m - b + 1)?)
+}
+
+/// An iterator over compositions of `n`: sequences of positive parts
+/// summing to `n`, in lexicographic order, or in reverse with `with_order`.
+///
+/// With `with_parts`, the parts are restricted to a given set, e.g.,
+/// compositions with parts in `{1, 2}` are counted by Fibonacci numbers.
+/// Only the reachable sums are explored, so the restricted compositions are
+/// generated directly, without dead ends.
+// reachable[r] is whether r is a sum of allowed parts.
+#[derive(Clone, Debug)]
+pub struct Compositions {
+    parts: Vec<usize>,
+    reachable: Vec<bool>,
+    comp: Vec<usize>,
+    order: Order,
+    first: bool,
+    done: bool,
+}
+
+impl Compositions {
+    /// All the compositions of `n`.
+    pub fn new(n: usize) -> Compositions {
+        Compositions::with_parts(n, &(1..(n + 1)).collect::<Vec<usize>>())
+    }
+
+    /// Compositions of `n` whose parts belong to `parts`.
+    pub fn with_parts(n: usize, parts: &[usize]) -> Compositions {
+        Compositions::with_order(n, parts, Order::Lex)
+    }
+
+    /// Compositions of `n` whose parts belong to `parts`, in the given
+    /// order, among `Lex` (as `with_parts`) and `RevLex`.
+    pub fn with_order(n: usize, parts: &[usize], order: Order) -> Compositions {
+        assert!(order == Order::Lex || order == Order::RevLex,
+                "Unsupported order {:?}", order);
+        let mut parts = parts.iter().cloned().filter(|&p| { p > 0 && p <= n })
+            .collect::<Vec<usize>>();
+        parts.sort();
+        parts.dedup();
+        let mut reachable = vec![false; n + 1];
+        reachable[0] = true;
+        for r in 1..(n + 1) {
+            reachable[r] = parts.iter().any(|&p| { p <= r && reachable[r - p] });
+        }
+        Compositions {
+            done: !reachable[n],
+            parts: parts,
+            reachable: reachable,
+            comp: Vec::new(),
+            order: order,
+            first: true,
+        }
+    }
+
+    /// The current composition.
+    pub fn current(&self) -> &[usize] {
+        &self.comp
+    }
+
+    // Complete the composition with the first parts summing to rem in the
+    // order: the smallest ones, or the largest ones in reverse.
+    fn fill(&mut self, mut rem: usize) {
+        while rem > 0 {
+            let reachable = &self.reachable;
+            let fits = |&&p: &&usize| { p <= rem && reachable[rem - p] };
+            let p = if self.order == Order::RevLex {
+                *self.parts.iter().rev().find(fits).unwrap()
+            } else {
+                *self.parts.iter().find(fits).unwrap()
+            };
+            self.comp.push(p);
+            rem -= p;
+        }
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for Compositions {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        if self.done { return None }
+        if self.first {
+            self.first = false;
+            let n = self.reachable.len() - 1;
+            self.fill(n);
+            return Some(&self.comp)
+        }
+        // Increase the last part which can be, and complete with the
+        // smallest parts (or decrease it and complete with the largest parts)
+        let mut rem = 0;
+        while let Some(p) = self.comp.pop() {
+            rem += p;
+            let reachable = &self.reachable;
+            let fits = |&&q: &&usize| { q <= rem && reachable[rem - q] };
+            let next = if self.order == Order::RevLex {
+                self.parts.iter().rev().filter(|&&q| { q < p }).find(fits)
+            } else {
+                self.parts.iter().filter(|&&q| { q > p }).find(fits)
+            };
+            if let Some(&q) = next {
+                self.comp.push(q);
+                self.fill(rem - q);
+                return Some(&self.comp)
+            }
+        }
+        self.done = true;
+        None
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(&self.comp) }
+    }
+}
+
+/// An iterator over the vector compositions of `target`: the sequences of
+/// nonzero vectors of nonnegative integers summing to `target`,
+/// componentwise, in lexicographic order. With `with_parts`, the vectors are
+/// restricted to a given set.
+///
+/// For a target of dimension 1, these are the compositions, and for the
+/// target `(1, 1, ..., 1)`, they are the ordered set partitions. As for
+/// `Compositions`, only the reachable remainders are explored.
+// The remainders, vectors below target, are indexed in the mixed radix of
+// target + 1, with the given strides. comp holds the indices of the parts of
+// the current composition in parts, and vectors the parts themselves.
+#[derive(Clone, Debug)]
+pub struct VectorCompositions {
+    strides: Vec<usize>,
+    parts: Vec<Vec<usize>>,
+    reachable: Vec<bool>,
+    comp: Vec<usize>,
+    vectors: Vec<Vec<usize>>,
+    rem: Vec<usize>,
+    first: bool,
+    done: bool,
+}
+
+impl VectorCompositions {
+    /// All the vector compositions of `target`.
+    pub fn new(target: &[usize]) -> VectorCompositions {
+        // The nonzero vectors below target, in lexicographic order
+        let mut parts = Vec::new();
+        let mut v = vec![0; target.len()];
+        loop {
+            match (0..v.len()).rev().find(|&i| { v[i] < target[i] }) {
+                None => break,
+                Some(i) => {
+                    v[i] += 1;
+                    for x in &mut v[(i + 1)..] { *x = 0 }
+                }
+            }
+            parts.push(v.clone());
+        }
+        VectorCompositions::with_parts(target, &parts)
+    }
+
+    /// Vector compositions of `target` whose parts belong to `parts`.
+    ///
+    /// # Panics
+    ///
+    /// If a part is zero, or of a different dimension than `target`.
+    pub fn with_parts(target: &[usize], parts: &[Vec<usize>]) -> VectorCompositions {
+        for p in parts {
+            assert!(p.len() == target.len(), "Part of the wrong dimension");
+            assert!(p.iter().any(|&x| { x > 0 }), "Zero part");
+        }
+        let mut parts: Vec<Vec<usize>> = parts.iter()
+            .filter(|p| { p.iter().zip(target).all(|(x, t)| { x <= t }) })
+            .cloned()
+            .collect();
+        parts.sort();
+        parts.dedup();
+        let mut strides = vec![1; target.len()];
+        for i in (1..target.len()).rev() {
+            strides[i - 1] = strides[i] * (target[i] + 1);
+        }
+        let size = target.first().map_or(1, |&t| { strides[0] * (t + 1) });
+        let index = |v: &[usize]| -> usize {
+            v.iter().zip(&strides).map(|(x, s)| { x * s }).sum()
+        };
+        // A remainder minus a part has a smaller index
+        let mut reachable = vec![false; size];
+        reachable[0] = true;
+        let mut r = vec![0; target.len()];
+        for l in 1..size {
+            let mut i = target.len() - 1;
+            while r[i] == target[i] { r[i] = 0; i -= 1 }
+            r[i] += 1;
+            reachable[l] = parts.iter().any(|p| {
+                p.iter().zip(&r).all(|(x, y)| { x <= y }) && reachable[l - index(p)]
+            });
+        }
+        VectorCompositions {
+            parts: parts,
+            done: !reachable[size - 1],
+            reachable: reachable,
+            strides: strides,
+            comp: Vec::new(),
+            vectors: Vec::new(),
+            rem: target.to_vec(),
+            first: true,
+        }
+    }
+
+    // Whether the part i fits in the remainder, leaving a reachable one
+    fn fits(&self, i: usize) -> bool {
+        let p = &self.parts[i];
+        p.iter().zip(&self.rem).all(|(x, r)| { x <= r }) && {
+            let l: usize = self.rem.iter().zip(p).zip(&self.strides)
+                .map(|((r, x), s)| { (r - x) * s })
+                .sum();
+            self.reachable[l]
+        }
+    }
+
+    fn push(&mut self, i: usize) {
+        for (r, x) in self.rem.iter_mut().zip(&self.parts[i]) { *r -= x }
+        self.comp.push(i);
+        self.vectors.push(self.parts[i].clone());
+    }
+
+    fn pop(&mut self) -> Option<usize> {
+        let i = self.comp.pop()?;
+        self.vectors.pop();
+        for (r, x) in self.rem.iter_mut().zip(&self.parts[i]) { *r += x }
+        Some(i)
+    }
+
+    // Complete the composition with the smallest parts
+    fn fill(&mut self) {
+        while self.rem.iter().any(|&r| { r > 0 }) {
+            let i = (0..self.parts.len()).find(|&i| { self.fits(i) }).unwrap();
+            self.push(i);
+        }
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for VectorCompositions {
+    type Item = [Vec<usize>];
+    fn next(&mut self) -> Option<&[Vec<usize>]> {
+        if self.done { return None }
+        if self.first {
+            self.first = false;
+            self.fill();
+            return Some(&self.vectors)
+        }
+        // Replace the last part which can be by a larger one, and complete
+        // with the smallest parts
+        while let Some(i) = self.pop() {
+            if let Some(j) = ((i + 1)..self.parts.len()).find(|&j| { self.fits(j) }) {
+                self.push(j);
+                self.fill();
+                return Some(&self.vectors)
+            }
+        }
+        self.done = true;
+        None
+    }
+
+    fn get(&self) -> Option<&[Vec<usize>]> {
+        if self.first || self.done { None } else { Some(&self.vectors) }
+    }
+}
+
+/// Iterators which can write their items into a caller-owned buffer.
+///
+/// This gives owned results without allocating for every item.
+pub trait NextInto {
+    /// The elements of the yielded slices.
+    type Elem;
+
+    /// Write the next item into `buf`, replacing its contents.
+    ///
+    /// Returns `false`, leaving `buf` untouched, when the iteration is over.
+    fn next_into(&mut self, buf: &mut Vec<Self::Elem>) -> bool;
+
+    /// Write up to `size` consecutive items into `buf`, replacing its
+    /// contents and reusing the allocations of its elements.
+    ///
+    /// Returns the number of items written, which is less than `size` only
+    /// when the iteration is over.
+    fn next_chunk(&mut self, buf: &mut Vec<Vec<Self::Elem>>, size: usize)
+        -> usize
+    {
+        let mut l = 0;
+        while l < size {
+            if l == buf.len() { buf.push(Vec::new()) }
+            if !self.next_into(&mut buf[l]) { break }
+            l += 1;
+        }
+        buf.truncate(l);
+        l
+    }
+
+    /// Iterate through chunks of `size` consecutive items.
+    fn chunks(self, size: usize) -> Chunks<Self> where Self: Sized {
+        assert!(size > 0, "Chunks must not be empty");
+        Chunks { iter: self, buf: Vec::new(), size: size }
+    }
+}
+
+/// An iterator over chunks of consecutive items of another iterator.
+///
+/// The last chunk may be shorter.
+#[derive(Clone, Debug)]
+pub struct Chunks<I: NextInto> {
+    iter: I,
+    buf: Vec<Vec<I::Elem>>,
+    size: usize,
+}
+
+impl<I: NextInto> StreamingIterator for Chunks<I> {
+    type Item = [Vec<I::Elem>];
+    fn next(&mut self) -> Option<&[Vec<I::Elem>]> {
+        match self.iter.next_chunk(&mut self.buf, self.size) {
+            0 => None,
+            _ => Some(&self.buf),
+        }
+    }
+
+    fn get(&self) -> Option<&[Vec<I::Elem>]> {
+        if self.buf.is_empty() { None } else { Some(&self.buf) }
+    }
+}
+
+// Implement NextInto in terms of a StreamingIterator over slices
+macro_rules! next_into_impl {
+    ($([$($p: tt)*] $ty: ty, $elem: ty;)*) => {
+        $(impl<$($p)*> NextInto for $ty {
+            type Elem = $elem;
+            fn next_into(&mut self, buf: &mut Vec<$elem>) -> bool {
+                match self.next() {
+                    None => false,
+                    Some(x) => {
+                        buf.clear();
+                        buf.extend(x.iter().cloned());
+                        true
+                    }
+                }
+            }
+        })*
+    }
+}
+
+next_into_impl! {
+    [] IndexCombinations, usize;
+    [] GrayCombinations, usize;
+    ['a, T: Clone, C: CombinationCore] Combinations<'a, T, C>, T;
+    [] IndexSubsequences, usize;
+    ['a, T: Clone] Subsequences<'a, T>, T;
+    [T: Clone] Permutations<T>, T;
+    [] IndexPermutations, usize;
+    [] ConstrainedPermutations, usize;
+    [] Multisets, usize;
+    [] Compositions, usize;
+    [] Partitions, usize;
+    [] WeightedCombinations, usize;
+    [] SubsetSums, usize;
+    [] MaximalSubsets, usize;
+    [] InversionTables, usize;
+    [] Catalan, usize;
+}
+
+/// An iterator over partitions of `n`: nonincreasing sequences of positive
+/// parts summing to `n`, in reverse lexicographic order, from `[n]` to
+/// `[1, ..., 1]`.
+#[derive(Clone, Debug)]
+pub struct Partitions {
+    partition: Partition,
     first: bool,
+    done: bool,
+}
+
+impl Partitions {
+    pub fn new(n: usize) -> Partitions {
+        Partitions::with_max_part(n, n)
+    }
+
+    /// Partitions of `n` whose parts are at most `m`.
+    ///
+    /// By conjugation, there are as many partitions of `n` with at most `m`
+    /// parts.
+    pub fn with_max_part(n: usize, m: usize) -> Partitions {
+        let mut parts = Vec::new();
+        let done = m == 0 && n > 0;
+        if !done { push_parts(&mut parts, m, n) }
+        Partitions {
+            partition: Partition::from_parts(parts),
+            first: true,
+            done: done,
+        }
+    }
+
+    /// The current partition.
+    pub fn current(&self) -> &[usize] {
+        self.partition.parts()
+    }
+
+    /// The current partition, as a value.
+    pub fn current_partition(&self) -> &Partition {
+        &self.partition
+    }
+
+    /// Yield `Partition` values instead of slices.
+    pub fn as_partitions(self) -> AsPartitions {
+        AsPartitions { iter: self }
+    }
+
+    // Move to the next partition, returning false if there is none.
+    fn advance(&mut self) -> bool {
+        if self.done { return false }
+        if self.first { self.first = false; return true }
+        let parts = self.partition.parts_mut();
+        // Decrease the last part greater than 1, and split what follows it
+        // into parts as large as possible
+        match parts.iter().rposition(|&x| { x > 1 }) {
+            None => { self.done = true; false }
+            Some(i) => {
+                // The parts after i are ones
+                let x = parts[i] - 1;
+                let rem = x + parts.len() - i;
+                parts.truncate(i);
+                push_parts(parts, x, rem);
+                true
+            }
+        }
+    }
+}
+
+// Push parts x, then the remainder, summing to rem
+fn push_parts(parts: &mut Vec<usize>, x: usize, mut rem: usize) {
+    while rem >= x && x > 0 {
+        parts.push(x);
+        rem -= x;
+    }
+    if rem > 0 { parts.push(rem) }
 }
 
-pub trait CombinationsIterator<T> {
-    fn combinations<'a>(&'a self, k: usize) -> Combinations<'a, T>;
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for Partitions {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        if self.advance() { Some(self.partition.parts()) } else { None }
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(self.partition.parts()) }
+    }
 }
 
-impl<T: Clone> CombinationsIterator<T> for [T] {
-    fn combinations<'a>(&'a self, k: usize) -> Combinations<'a, T> {
-        let is = iter::repeat(self.len()-k).take(k).collect::<Vec<usize>>();
-        Combinations {
-            src: self,
-            dest: self[0..k].to_vec(),
-            indices: is,
-            first: true,
+/// An iterator over the partitions dominated by, or dominating, a given
+/// one (see `Partition::dominates`), in reverse lexicographic order.
+///
+/// The reverse lexicographic order extends the dominance order, so the
+/// partitions dominated by `λ` all come after `λ`, and those dominating it
+/// before: the enumeration of `Partitions` is cut at `λ`, and filtered.
+// done is set once the bound is output, when enumerating the partitions
+// dominating it, and finished once None is output, since iter is then left
+// on the bound.
+#[derive(Clone, Debug)]
+pub struct Dominance {
+    iter: Partitions,
+    bound: Partition,
+    dominated: bool,
+    done: bool,
+    finished: bool,
+}
+
+impl Dominance {
+    /// The partitions dominated by `bound`, including itself, from `bound`
+    /// to `[1, ..., 1]`.
+    pub fn dominated_by(bound: &Partition) -> Dominance {
+        Dominance {
+            iter: Partitions { partition: bound.clone(), first: true, done: false },
+            bound: bound.clone(),
+            dominated: true,
+            done: false,
+            finished: false,
+        }
+    }
+
+    /// The partitions dominating `bound`, including itself, from `[n]` to
+    /// `bound`.
+    pub fn dominating(bound: &Partition) -> Dominance {
+        Dominance {
+            iter: Partitions::new(bound.size()),
+            bound: bound.clone(),
+            dominated: false,
+            done: false,
+            finished: false,
         }
     }
+
+    /// The current partition, as a value.
+    pub fn current_partition(&self) -> &Partition {
+        self.iter.current_partition()
+    }
 }
 
-/// Iterate through combinations of `k` elements.
-///
-/// Each iteration yields a slice of length `k` with distinct elements from the
-/// stored sequence in the same order. If `n` is the length of the source slice,
-/// there are (`n` choose `k`) combinations.
-///
-/// Calls to `.next()` after `None` has been output give undefined results.
-/// (In practice, currently, it keeps returning `None`)
-impl<'a, 'b, T> Iterator for Combinations<'a, T> where T: 'a + Clone {
-    type Item = &'b [T];
-    fn next(&mut self) -> Option<<Self as Iterator>::Item> {
-        let Combinations {
-            src,
-            ref mut dest,
-            ref mut indices,
-            ref mut first,
-        } = *self;
-        let n = src.len();
-        let k = dest.len();
-        if *first { *first = false; return Some(dest) }
-        let i_opt = indices.iter().rposition(|&j| { j != 0 });
-        match i_opt {
-            None => None,
-            Some(i) => {
-                let h = indices[i];
-                let m = n - h + 1;
-                let r = (m - k + i)..m;
-                for j in indices[i..].iter_mut() { *j = h - 1; }
-                dest[i..].clone_from_slice(&src[r]);
-                Some(dest)
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for Dominance {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        if self.done { self.finished = true; return None }
+        while self.iter.advance() {
+            let p = &self.iter.partition;
+            let keep = if self.dominated {
+                self.bound.dominates(p)
+            } else {
+                // Nothing after bound dominates it
+                self.done = *p == self.bound;
+                p.dominates(&self.bound)
+            };
+            if keep { return Some(self.iter.partition.parts()) }
+        }
+        self.finished = true;
+        None
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.finished { None } else { self.iter.get() }
+    }
+}
+
+/// An iterator over the partitions of `n` whose Dyson rank (see
+/// `Partition::rank`) is `r`, in reverse lexicographic order.
+#[derive(Clone, Debug)]
+pub struct PartitionsOfRank {
+    iter: Partitions,
+    r: isize,
+}
+
+impl PartitionsOfRank {
+    pub fn new(n: usize, r: isize) -> PartitionsOfRank {
+        PartitionsOfRank { iter: Partitions::new(n), r: r }
+    }
+
+    /// The current partition, as a value.
+    pub fn current_partition(&self) -> &Partition {
+        self.iter.current_partition()
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for PartitionsOfRank {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        while self.iter.advance() {
+            if self.iter.partition.rank() == self.r {
+                return Some(self.iter.partition.parts());
             }
         }
+        None
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        self.iter.get()
     }
 }
 
-/// An iterator over subsets/subsequences.
+/// An iterator over partitions as `Partition` values.
+#[derive(Clone, Debug)]
+pub struct AsPartitions {
+    iter: Partitions,
+}
+
+impl StreamingIterator for AsPartitions {
+    type Item = Partition;
+    fn next(&mut self) -> Option<&Partition> {
+        if self.iter.advance() { Some(&self.iter.partition) } else { None }
+    }
+
+    fn get(&self) -> Option<&Partition> {
+        self.iter.get().map(|_| { &self.iter.partition })
+    }
+}
+
+/// An iterator over `k`-combinations of indices into a slice of weights,
+/// whose total weight is between `min` and `max` (inclusive).
 ///
-/// Gives directly the subsequences as slices of an internal vector.
-// Invariant: dest and indices have the same length
-pub struct Subsequences<'a, T> where T: 'a {
-    src: &'a [T],
-    dest: Vec<T>,
+/// The weights are sorted, and a branch is cut as soon as the lightest
+/// or the heaviest ways to complete it fall out of the bounds. Each
+/// combination is output as increasing indices into the original slice,
+/// but the combinations come in lexicographic order of the positions of
+/// their elements in the sorted weights.
+// pos holds increasing positions in the sorted weights, sum their weights.
+// prefix[i] is the total of the i lightest weights, in a u128 so that it
+// cannot overflow. sum is at most max.
+#[derive(Clone, Debug)]
+pub struct WeightedCombinations {
+    weights: Vec<u64>,
+    order: Vec<usize>,
+    prefix: Vec<u128>,
+    k: usize,
+    min: u64,
+    max: u64,
+    pos: Vec<usize>,
+    sum: u64,
     indices: Vec<usize>,
     first: bool,
+    done: bool,
 }
 
-pub trait SubsequencesIterator<T> {
-    fn subsequences<'a>(&'a self) -> Subsequences<'a, T>;
-}
-
-impl<T> SubsequencesIterator<T> for [T] {
-    fn subsequences<'a>(&'a self) -> Subsequences<'a, T> {
-        Subsequences {
-            src: self,
-            dest: Vec::new(),
-            indices: Vec::new(),
+impl WeightedCombinations {
+    pub fn new(weights: &[u64], k: usize, min: u64, max: u64) -> WeightedCombinations {
+        let mut order = (0..weights.len()).collect::<Vec<usize>>();
+        order.sort_by_key(|&i| { weights[i] });
+        let weights = order.iter().map(|&i| { weights[i] }).collect::<Vec<u64>>();
+        let mut prefix = vec![0u128];
+        for &w in &weights {
+            let last = prefix[prefix.len() - 1];
+            prefix.push(last + w as u128);
+        }
+        WeightedCombinations {
+            weights: weights,
+            order: order,
+            prefix: prefix,
+            k: k,
+            min: min,
+            max: max,
+            pos: Vec::with_capacity(k),
+            sum: 0,
+            indices: Vec::with_capacity(k),
             first: true,
+            // The empty combination has weight 0
+            done: k == 0 && min > 0,
+        }
+    }
+
+    /// The total weight of the current combination.
+    pub fn weight(&self) -> u64 {
+        self.sum
+    }
+
+    // Complete pos from its current length, trying positions from start
+    // for the next element, and backtracking as needed. Returns false if
+    // there is no more combination.
+    fn search(&mut self, mut start: usize) -> bool {
+        let n = self.weights.len();
+        loop {
+            let l = self.pos.len();
+            if l == self.k { return true }
+            // Elements left to choose after this one
+            let r = self.k - l - 1;
+            let mut found = None;
+            let mut p = start;
+            while p + r < n {
+                let s = self.sum as u128 + self.weights[p] as u128;
+                // Lightest completion, which only gets heavier with p
+                if s + self.prefix[p + 1 + r] - self.prefix[p + 1] > self.max as u128 { break }
+                // Heaviest completion
+                if s + self.prefix[n] - self.prefix[n - r] >= self.min as u128 {
+                    found = Some(p);
+                    break;
+                }
+                p += 1;
+            }
+            match found {
+                Some(p) => {
+                    self.pos.push(p);
+                    self.sum += self.weights[p];
+                    start = p + 1;
+                }
+                None => match self.pos.pop() {
+                    None => return false,
+                    Some(q) => {
+                        self.sum -= self.weights[q];
+                        start = q + 1;
+                    }
+                },
+            }
         }
     }
 }
 
-/// Iterate through the subsequences of a stored sequence.
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for WeightedCombinations {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        if self.done { return None }
+        let found = if self.first {
+            self.first = false;
+            self.search(0)
+        } else {
+            match self.pos.pop() {
+                None => false,
+                Some(q) => {
+                    self.sum -= self.weights[q];
+                    self.search(q + 1)
+                }
+            }
+        };
+        if !found { self.done = true; return None }
+        self.indices.clear();
+        let order = &self.order;
+        self.indices.extend(self.pos.iter().map(|&p| { order[p] }));
+        self.indices.sort();
+        Some(&self.indices)
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(&self.indices) }
+    }
+}
+
+/// An iterator over the subsets of indices into a slice of weights whose
+/// total weight is exactly `target`.
 ///
-/// If `n` is the length of the source slice, there are `2^n` subsequences.
+/// A table of the sums reachable by every suffix of the weights guides the
+/// search, so that there are no dead ends: the time per subset is linear in
+/// the number of weights, after a precomputation in time and space
+/// `O(n * target)`.
 ///
-/// Resets after returning `None`.
-impl<'a, 'b, T> Iterator for Subsequences<'a, T> where T: 'a + Clone {
-    type Item = &'b [T];
-    fn next<'c>(&'c mut self) -> Option<&'c [T]> {
-        let Subsequences {
-            src,
-            ref mut dest,
-            ref mut indices,
-            ref mut first,
-        } = *self;
-        // The first call returns an empty slice
-        if *first { *first = false; return Some(dest) }
-        let n = src.len();
-        let i = indices.last().map_or(0, |&i| { i+1 });
-        // Push an element while we can
-        if i < n {
-            indices.push(i);
-            dest.push(src[i].clone());
-            return Some(dest)
+/// The subsets are output as increasing indices, in lexicographic order of
+/// their indicator vectors (so the subsets without the first index come
+/// first).
+// reachable[i * (target + 1) + s] is whether s is the sum of a subset of
+// weights[i..]. chosen[i] is whether index i is in the current subset, and
+// rem[i] the weight left to reach before deciding on i.
+#[derive(Clone, Debug)]
+pub struct SubsetSums {
+    weights: Vec<usize>,
+    target: usize,
+    reachable: Vec<bool>,
+    chosen: Vec<bool>,
+    rem: Vec<usize>,
+    indices: Vec<usize>,
+    first: bool,
+    done: bool,
+}
+
+impl SubsetSums {
+    pub fn new(weights: &[usize], target: usize) -> SubsetSums {
+        let n = weights.len();
+        let t = target + 1;
+        let mut reachable = vec![false; (n + 1) * t];
+        reachable[n * t] = true;
+        for i in (0..n).rev() {
+            for s in 0..t {
+                reachable[i * t + s] = reachable[(i + 1) * t + s] ||
+                    (weights[i] <= s && reachable[(i + 1) * t + s - weights[i]]);
+            }
         }
-        // The end of the input is reached,
-        // pop and increment the previous index
-        indices.pop();
-        dest.pop();
-        match (indices.last_mut(), dest.last_mut()) {
-            (None, None) => { *first = true; return None }, // Wrap around
-            (Some(i), Some(x)) => {
-                *i += 1;
-                *x = src[*i].clone();
-            },
-            _ => assert![false, "Should not happen!"]
+        SubsetSums {
+            weights: weights.to_vec(),
+            target: target,
+            done: !reachable[target],
+            reachable: reachable,
+            chosen: vec![false; n],
+            rem: vec![0; n],
+            indices: Vec::new(),
+            first: true,
+        }
+    }
+
+    // Whether s is a sum of a subset of weights[i..]
+    fn is_reachable(&self, i: usize, s: usize) -> bool {
+        self.reachable[i * (self.target + 1) + s]
+    }
+
+    // Decide on the indices from i, leaving them out whenever possible.
+    // The caller ensures that s is reachable from i.
+    fn fill(&mut self, i: usize, mut s: usize) {
+        for j in i..self.weights.len() {
+            self.rem[j] = s;
+            self.chosen[j] = !self.is_reachable(j + 1, s);
+            if self.chosen[j] { s -= self.weights[j] }
         }
-        Some(dest)
     }
 }
 
-/// An operator over permutations.
-///
-/// The advantage of this implementation over the standard one from `std::slice`
-/// is that the permutations are not copied.
-/// Instead, an immutable slice into the vector is returned.
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for SubsetSums {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        if self.done { return None }
+        if self.first {
+            self.first = false;
+            let target = self.target;
+            self.fill(0, target);
+        } else {
+            // Include the last index that was left out and could be taken
+            let i = (0..self.weights.len()).rev().find(|&i| {
+                !self.chosen[i] && self.weights[i] <= self.rem[i] &&
+                    self.is_reachable(i + 1, self.rem[i] - self.weights[i])
+            });
+            match i {
+                None => { self.done = true; return None }
+                Some(i) => {
+                    self.chosen[i] = true;
+                    let s = self.rem[i] - self.weights[i];
+                    self.fill(i + 1, s);
+                }
+            }
+        }
+        self.indices.clear();
+        let chosen = &self.chosen;
+        self.indices.extend((0..chosen.len()).filter(|&i| { chosen[i] }));
+        Some(&self.indices)
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(&self.indices) }
+    }
+}
+
+/// An iterator over the maximal subsets of indices into a slice of weights
+/// whose total weight is at most `capacity`: no other index can be added
+/// without exceeding it.
 ///
-/// The order in which the permutations are returned is not guaranteed
-/// to be the same as the one from the standard `Permutations` iterator.
+/// Leaving out an index requires the rest of the subset to fill the
+/// capacity beyond the point where that index would fit. Branches where
+/// even taking all the remaining indices would not achieve this are cut,
+/// since all their subsets are contained in larger feasible ones.
 ///
-/// Resets after returning `None`.
-// Simply reuse the standard implementation elements
-pub struct Permutations<T> {
-    dest: Vec<T>,
-    swaps: ElementSwaps,
+/// The subsets are output as increasing indices, in reverse lexicographic
+/// order of their indicator vectors, starting with the greedy one.
+// chosen[i] is whether index i is in the current subset. Before deciding on
+// i, rem[i] is the capacity left and excluded[i] the least weight left out.
+// suffix[i] is the total of weights[i..].
+#[derive(Clone, Debug)]
+pub struct MaximalSubsets {
+    weights: Vec<usize>,
+    suffix: Vec<usize>,
+    chosen: Vec<bool>,
+    rem: Vec<usize>,
+    excluded: Vec<usize>,
+    indices: Vec<usize>,
+    first: bool,
+    done: bool,
 }
 
-pub trait PermutationsIterator<T> {
-    fn permutations_iter(&self) -> Permutations<T>;
-}
+impl MaximalSubsets {
+    pub fn new(weights: &[usize], capacity: usize) -> MaximalSubsets {
+        let n = weights.len();
+        let mut suffix = vec![0usize; n + 1];
+        for i in (0..n).rev() {
+            suffix[i] = suffix[i + 1].saturating_add(weights[i]);
+        }
+        let mut rem = vec![0; n + 1];
+        let mut excluded = vec![0; n + 1];
+        rem[0] = capacity;
+        excluded[0] = usize::MAX;
+        MaximalSubsets {
+            weights: weights.to_vec(),
+            suffix: suffix,
+            chosen: vec![false; n],
+            rem: rem,
+            excluded: excluded,
+            indices: Vec::new(),
+            first: true,
+            done: false,
+        }
+    }
 
-impl<T> PermutationsIterator<T> for [T] where T: Clone {
-    fn permutations_iter(&self) -> Permutations<T> {
-        Permutations {
-            dest: self.to_vec(),
-            swaps: ElementSwaps::new(self.len()),
+    /// The total weight of the current subset.
+    pub fn weight(&self) -> usize {
+        let n = self.weights.len();
+        self.rem[0] - self.rem[n]
+    }
+
+    // Whether, from index i with capacity r left and least excluded weight
+    // m, taking all the remaining indices leaves less than m. At the end,
+    // this is exactly maximality.
+    fn feasible(&self, i: usize, r: usize, m: usize) -> bool {
+        r < m.saturating_add(self.suffix[i])
+    }
+
+    // Decide on the indices from i, trying to include i first if include
+    // is true, and backtracking as needed. Returns false if there is no
+    // more subset.
+    fn search(&mut self, mut i: usize, mut include: bool) -> bool {
+        let n = self.weights.len();
+        loop {
+            if i == n { return true }
+            let (r, m, w) = (self.rem[i], self.excluded[i], self.weights[i]);
+            if include && w <= r && self.feasible(i + 1, r - w, m) {
+                self.chosen[i] = true;
+                self.rem[i + 1] = r - w;
+                self.excluded[i + 1] = m;
+                i += 1;
+                continue;
+            }
+            let m = cmp::min(m, w);
+            if self.feasible(i + 1, r, m) {
+                self.chosen[i] = false;
+                self.rem[i + 1] = r;
+                self.excluded[i + 1] = m;
+                i += 1;
+                include = true;
+                continue;
+            }
+            // Leave out the last index included so far
+            match (0..i).rev().find(|&j| { self.chosen[j] }) {
+                None => return false,
+                Some(j) => { i = j; include = false }
+            }
         }
     }
 }
 
-impl<'b, T> Iterator for Permutations<T> where T: Clone {
-    type Item = &'b [T];
-    fn next(&mut self) -> Option<<Self as Iterator>::Item> {
-        let Permutations {
-            ref mut dest,
-            ref mut swaps,
-        } = *self;
-        match swaps.next() {
-            None => None,
-            Some((0, 0)) => Some(dest),
-            Some((a, b)) => { dest.swap(a, b); Some(dest) },
-        }
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for MaximalSubsets {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        if self.done { return None }
+        let found = if self.first {
+            self.first = false;
+            self.search(0, true)
+        } else {
+            let n = self.weights.len();
+            match (0..n).rev().find(|&j| { self.chosen[j] }) {
+                None => false,
+                Some(j) => self.search(j, false),
+            }
+        };
+        if !found { self.done = true; return None }
+        self.indices.clear();
+        let chosen = &self.chosen;
+        self.indices.extend((0..chosen.len()).filter(|&i| { chosen[i] }));
+        Some(&self.indices)
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(&self.indices) }
     }
 }
 
-/// An iterator over pairs of elements.
+/// An iterator over the items of an iterator of indices into a slice,
+/// replaced by the elements they point to, skipping the duplicates which
+/// arise from equal elements, e.g., the permutations of a list with
+/// repetitions.
 ///
-/// A `Product` is a variant of `std::iter::FlatMap` with a constant iterator.
+/// Two items are duplicates if they are equal as sequences of elements,
+/// e.g., the combinations at positions `[0, 2]` and `[1, 2]` of `[a, a, b]`,
+/// both `[a, b]`. Only one of them is output, the first one in
+/// lexicographic order of the positions:
 ///
-/// Not very useful, as it can simply be replaced with a nested loop...
-pub struct Product<I: Iterator, J> {
-    iter_b_const: J,
-    cur_a: Option<I::Item>,
-    iter_a: I,
-    iter_b: J,
+/// - an increasing item, e.g., a combination or a subsequence, is output if
+///   every position is the first occurrence of its element after the
+///   previous position;
+/// - any other item, e.g., a permutation, is output if equal elements
+///   appear in increasing order of positions.
+///
+/// Nothing is remembered, so the memory does not grow with the output, but
+/// the underlying iterator must yield all the increasing items of the
+/// lengths it yields, as the iterators of combinations and subsequences do,
+/// or all the arrangements of `0..n`, as the iterators of permutations do.
+// first[i] is the least index of an element equal to src[i], next[i] the
+// least one after i, or src.len(), and prev[i] the greatest one before i.
+// cursor is scratch space: cursor[first[i]] is the index of the next
+// occurrence of src[i] expected in a canonical permutation, and is reset to
+// first[i] after every item.
+#[derive(Clone, Debug)]
+pub struct Dedup<'a, T: 'a, I> {
+    iter: I,
+    src: &'a [T],
+    first: Vec<usize>,
+    next: Vec<usize>,
+    prev: Vec<Option<usize>>,
+    cursor: Vec<usize>,
+    dest: Vec<T>,
+    valid: bool,
 }
 
-impl<I: Sized + Iterator, J: Clone + Iterator> Product<I, J> where
-    I::Item: Clone
-{
-    pub fn new(mut i: I, j: J) -> Self
-    {
-        Product {
-            iter_b_const: j.clone(),
-            cur_a: i.next(),
-            iter_a: i,
-            iter_b: j }
+impl<'a, T: PartialEq, I> Dedup<'a, T, I> {
+    pub fn new(iter: I, src: &'a [T]) -> Dedup<'a, T, I> {
+        let n = src.len();
+        let first = (0..n).map(|i| {
+            src.iter().position(|x| { *x == src[i] }).unwrap()
+        }).collect::<Vec<usize>>();
+        let next = (0..n).map(|i| {
+            ((i + 1)..n).find(|&j| { first[j] == first[i] }).unwrap_or(n)
+        }).collect();
+        let prev = (0..n).map(|i| {
+            (0..i).rev().find(|&j| { first[j] == first[i] })
+        }).collect();
+        Dedup {
+            iter: iter,
+            src: src,
+            cursor: first.clone(),
+            first: first,
+            next: next,
+            prev: prev,
+            dest: Vec::new(),
+            valid: false,
+        }
     }
 }
 
-impl<I: Iterator, J: Clone + Iterator> Iterator for Product<I, J> where
-    I::Item: Clone
+/// Calls to `.next()` after `None` has been output keep returning `None` if
+/// the underlying iterator does.
+impl<'a, T, I> StreamingIterator for Dedup<'a, T, I> where
+    T: 'a + Clone,
+    I: StreamingIterator<Item = [usize]>
 {
-    type Item = (I::Item, J::Item);
-    fn next(&mut self) -> Option<<Self as Iterator>::Item> {
-        // This loops infinitely if self.iter_b is empty
-        // and self.iter_a is infinite.
+    type Item = [T];
+    fn next(&mut self) -> Option<&[T]> {
+        self.valid = false;
         loop {
-            match (self.cur_a.as_ref(), self.iter_b.next()) {
-                (Some(x), Some(y)) => return Some((x.clone(), y)),
-                (None, _) => return None,
-                _ => {}
+            let indices = self.iter.next()?;
+            let canonical = if indices.windows(2).all(|w| { w[0] < w[1] }) {
+                // No occurrence of an element between the previous position
+                // and its own
+                let prev = &self.prev;
+                let mut before = None;
+                indices.iter().all(|&i| {
+                    let leftmost = match (prev[i], before) {
+                        (None, _) => true,
+                        (Some(j), Some(b)) => j <= b,
+                        (Some(_), None) => false,
+                    };
+                    before = Some(i);
+                    leftmost
+                })
+            } else {
+                let (first, cursor) = (&self.first, &mut self.cursor);
+                let mut in_order = true;
+                for &i in indices {
+                    if cursor[first[i]] != i { in_order = false; break }
+                    cursor[first[i]] = self.next[i];
+                }
+                for &i in indices { cursor[first[i]] = first[i] }
+                in_order
+            };
+            if canonical {
+                let src = self.src;
+                self.dest.clear();
+                self.dest.extend(indices.iter().map(|&i| { src[i].clone() }));
+                self.valid = true;
+                return Some(&self.dest);
             }
-            self.cur_a = self.iter_a.next();
-            self.iter_b = self.iter_b_const.clone();
         }
     }
+
+    fn get(&self) -> Option<&[T]> {
+        if self.valid { Some(&self.dest) } else { None }
+    }
 }
 
-/// An iterator over binary trees.
+/// An iterator over permutations of `0..n`, in lexicographic order of their
+/// inversion tables (see `Permutation::inversion_table`).
 ///
-/// There are `choose(2 * n, n) / (n + 1)` trees with `n + 1` leaves.
-pub struct Catalan {
-    indices: Vec<usize>,
+/// The tables are counted in a mixed radix, where the `j`-th digit is less
+/// than `n - j`, so the rank of a permutation is simply the value of its
+/// table. Every step rebuilds the permutation, in quadratic time.
+#[derive(Clone, Debug)]
+pub struct InversionTables {
+    table: Vec<usize>,
+    perm: Permutation,
     first: bool,
+    done: bool,
 }
 
-impl Catalan {
-    pub fn new(n: usize) -> Catalan {
-        Catalan {
-            indices: (0..n-1).collect(),
-            first: true,
+impl InversionTables {
+    pub fn new(n: usize) -> InversionTables {
+        InversionTables::from_rank(n, 0)
+    }
+
+    /// Start the iteration from the permutation with rank `r`, i.e., whose
+    /// inversion table is `r` in the mixed radix.
+    ///
+    /// # Panics
+    ///
+    /// If `r` is not less than `n!`.
+    pub fn from_rank(n: usize, r: usize) -> InversionTables {
+        InversionTables::try_from_rank(n, r).expect("Rank out of range")
+    }
+
+    /// Like `from_rank`, but return an error instead of panicking.
+    pub fn try_from_rank(n: usize, mut r: usize) -> Result<InversionTables> {
+        let mut table = vec![0; n];
+        for j in (0..n).rev() {
+            table[j] = r % (n - j);
+            r /= n - j;
         }
+        if r != 0 { return Err(Error::RankOutOfRange) }
+        Ok(InversionTables {
+            perm: Permutation::from_inversion_table(&table).unwrap(),
+            table: table,
+            first: true,
+            done: false,
+        })
+    }
+
+    /// The inversion table of the current permutation.
+    pub fn table(&self) -> &[usize] {
+        &self.table
+    }
+
+    /// The rank of the current permutation.
+    pub fn rank(&self) -> usize {
+        let n = self.table.len();
+        self.table.iter().enumerate().fold(0, |r, (j, &b)| { r * (n - j) + b })
     }
 }
 
-/// Iterate through binary trees with n leaves.
-///
-/// The returned slices represent sequences of nodes from the traversals of
-/// binary trees, where a label is the index of the leftmost leaf in the
-/// subtree rooted at the corresponding node.
-///
-/// Alternatively, starting with a sequence of leaves:
-///
-/// `0 1 2 3 4 5 6`,
-/// 
-/// the slice
-///
-/// `0 1 2 3 3 4`
-/// 
-/// can be associated with a prefix expression, obtained by inserting an
-/// operator at the corresponding positions between the leaves:
-///
-/// `+ 0 + 1 + 2 + + 3 + 4 5 6`
-///
-/// Brackets reveal the tree structure (with a LISP flavour):
-///
-/// `(+ 0 (+ 1 (+ 2 (+ (+ 3 (+ 4 5)) 6))))`
-///
-/// That is equivalent to the infix expression:
-///
-/// `0 + (1 + (2 + ((3 + (4 + 5)) + 6)))`
-///
-/// # Algorithm for `next()`
-///
-/// From this state (`j != 0`, `*`: cells left intact):
-///
-/// `0 0 0 ... 0 0 0 ... 0 j * ...`
-///
-/// the next one is
-///
-/// `0 1 2 ... j-2 j-1 j-1 ... j-1 j-1 * ...`
-///
-impl<'a> Iterator for Catalan {
-    type Item = &'a [usize];
-    fn next(&mut self) -> Option<<Self as Iterator>::Item> {
-        let Catalan { ref mut indices, ref mut first } = *self;
-        if *first { *first = false; return Some(indices) }
-        match indices.iter().position(|&j| { j != 0 }) {
-            None => None,
-            Some(i) => {
-                let j = indices[i];
-                indices.move_from((0..j).collect(), 0, j);
-                for k in indices[j..(i + 1)].iter_mut() { *k = j-1 }
-                Some(indices)
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for InversionTables {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        if self.done { return None }
+        if self.first {
+            self.first = false;
+            return Some(self.perm.as_slice());
+        }
+        // Increment the counter
+        let n = self.table.len();
+        match (0..n).rposition(|j| { self.table[j] + 1 < n - j }) {
+            None => { self.done = true; None }
+            Some(j) => {
+                self.table[j] += 1;
+                for b in &mut self.table[(j + 1)..] { *b = 0 }
+                self.perm = Permutation::from_inversion_table(&self.table).unwrap();
+                Some(self.perm.as_slice())
             }
         }
     }
-}
-
-// - Partitions
 
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(self.perm.as_slice()) }
+    }
+}
@@ -0,0 +1,274 @@
+//! Integer points in boxes, with linear constraints
+//!
+//! The points are vectors of `i64`, the `i`-th coordinate ranging in a
+//! given `ops::Range`. Without constraints, this is counting in a mixed
+//! radix. The points with a fixed sum of coordinates have their own
+//! iterator, `FixedSumTuples`.
+
+use std::*;
+use iter::{StreamingIterator, Snapshot};
+
+/// A linear inequality `Σ coefficients[j] * x[j] <= bound`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LinearConstraint {
+    coefficients: Vec<i64>,
+    bound: i64,
+}
+
+impl LinearConstraint {
+    /// `Σ coefficients[j] * x[j] <= bound`.
+    pub fn at_most(coefficients: Vec<i64>, bound: i64) -> LinearConstraint {
+        LinearConstraint { coefficients: coefficients, bound: bound }
+    }
+
+    /// `Σ coefficients[j] * x[j] >= bound`.
+    pub fn at_least(coefficients: Vec<i64>, bound: i64) -> LinearConstraint {
+        LinearConstraint::at_most(coefficients.iter().map(|&a| { -a }).collect(), -bound)
+    }
+
+    pub fn coefficients(&self) -> &[i64] {
+        &self.coefficients
+    }
+
+    pub fn bound(&self) -> i64 {
+        self.bound
+    }
+
+    /// Whether `x` satisfies the constraint.
+    pub fn holds(&self, x: &[i64]) -> bool {
+        let s: i128 = self.coefficients.iter().zip(x)
+            .map(|(&a, &x)| { a as i128 * x as i128 })
+            .sum();
+        s <= self.bound as i128
+    }
+}
+
+/// An iterator over the integer points of a box, the product of the given
+/// ranges, which satisfy the given constraints, in lexicographic order.
+///
+/// The coordinates are chosen one at a time, by backtracking. Every
+/// constraint bounds the current coordinate, given the previous ones and the
+/// smallest possible contribution of the following ones, so the search only
+/// backtracks when the constraints conflict with each other.
+///
+/// # Panics
+///
+/// If a constraint does not have one coefficient per coordinate.
+// min_rest[c][i] is the smallest value of the terms of the constraint c
+// from the coordinate i on, over the box, and sums[c] the value of its terms
+// of the coordinates chosen so far in point. hi[i] is the largest value
+// allowed for point[i].
+#[derive(Clone, Debug)]
+pub struct LatticePoints {
+    ranges: Vec<ops::Range<i64>>,
+    constraints: Vec<LinearConstraint>,
+    min_rest: Vec<Vec<i128>>,
+    sums: Vec<i128>,
+    point: Vec<i64>,
+    hi: Vec<i64>,
+    first: bool,
+    done: bool,
+}
+
+impl LatticePoints {
+    pub fn new(ranges: &[ops::Range<i64>], constraints: Vec<LinearConstraint>) -> LatticePoints {
+        let k = ranges.len();
+        for c in &constraints {
+            assert!(c.coefficients.len() == k, "Constraint of the wrong length");
+        }
+        let empty = ranges.iter().any(|r| { r.start >= r.end });
+        let min_rest: Vec<Vec<i128>> = constraints.iter().map(|c| {
+            let mut m = vec![0i128; k + 1];
+            if !empty {
+                for i in (0..k).rev() {
+                    let a = c.coefficients[i] as i128;
+                    let (lo, hi) = (ranges[i].start as i128, ranges[i].end as i128 - 1);
+                    m[i] = m[i + 1] + cmp::min(a * lo, a * hi);
+                }
+            }
+            m
+        }).collect();
+        // Infeasible even with the smallest contributions
+        let infeasible = constraints.iter().zip(&min_rest).any(|(c, m)| {
+            m[0] > c.bound as i128
+        });
+        LatticePoints {
+            ranges: ranges.to_vec(),
+            sums: vec![0; constraints.len()],
+            constraints: constraints,
+            min_rest: min_rest,
+            point: Vec::with_capacity(k),
+            hi: vec![0; k],
+            first: true,
+            done: empty || infeasible,
+        }
+    }
+
+    // The bounds of the next coordinate, given the chosen ones, included
+    fn bounds(&self) -> (i64, i64) {
+        let i = self.point.len();
+        let mut lo = self.ranges[i].start as i128;
+        let mut hi = self.ranges[i].end as i128 - 1;
+        for (c, constraint) in self.constraints.iter().enumerate() {
+            let a = constraint.coefficients[i] as i128;
+            let slack = constraint.bound as i128 - self.sums[c] - self.min_rest[c][i + 1];
+            // a * x <= slack
+            if a > 0 {
+                hi = cmp::min(hi, slack.div_euclid(a));
+            } else if a < 0 {
+                lo = cmp::max(lo, -slack.div_euclid(-a));
+            } else if slack < 0 {
+                return (1, 0);
+            }
+        }
+        if lo > hi { (1, 0) } else { (lo as i64, hi as i64) }
+    }
+
+    fn push(&mut self, x: i64) {
+        let i = self.point.len();
+        for (s, c) in self.sums.iter_mut().zip(&self.constraints) {
+            *s += c.coefficients[i] as i128 * x as i128;
+        }
+        self.point.push(x);
+    }
+
+    fn pop(&mut self) -> Option<i64> {
+        let x = self.point.pop()?;
+        let i = self.point.len();
+        for (s, c) in self.sums.iter_mut().zip(&self.constraints) {
+            *s -= c.coefficients[i] as i128 * x as i128;
+        }
+        Some(x)
+    }
+
+    // Increment the last coordinate which can be, dropping the following
+    // ones, returning false if there is none.
+    fn backtrack(&mut self) -> bool {
+        while let Some(x) = self.pop() {
+            if x < self.hi[self.point.len()] {
+                self.push(x + 1);
+                return true;
+            }
+        }
+        false
+    }
+
+    // Complete the point with the smallest allowed coordinates, backtracking
+    // if necessary, returning false if there is no point left.
+    fn search(&mut self) -> bool {
+        while self.point.len() < self.ranges.len() {
+            let (lo, hi) = self.bounds();
+            if lo <= hi {
+                self.hi[self.point.len()] = hi;
+                self.push(lo);
+            } else if !self.backtrack() {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for LatticePoints {
+    type Item = [i64];
+    fn next(&mut self) -> Option<&[i64]> {
+        if self.done { return None }
+        let found = if self.first {
+            self.first = false;
+            self.search()
+        } else {
+            self.backtrack() && self.search()
+        };
+        self.done = !found;
+        if found { Some(&self.point) } else { None }
+    }
+
+    fn get(&self) -> Option<&[i64]> {
+        if self.first || self.done { None } else { Some(&self.point) }
+    }
+}
+
+/// An iterator over the tuples of integers in the product of the given
+/// ranges whose sum is `s`, in lexicographic order.
+///
+/// Every coordinate is bounded by the sums reachable by the following ones,
+/// so the tuples are generated directly, without dead ends.
+// min_rest[i] and max_rest[i] are the smallest and largest sums of the
+// coordinates from i on.
+#[derive(Clone, Debug)]
+pub struct FixedSumTuples {
+    ranges: Vec<ops::Range<i64>>,
+    min_rest: Vec<i128>,
+    max_rest: Vec<i128>,
+    tuple: Vec<i64>,
+    first: bool,
+    done: bool,
+}
+
+impl FixedSumTuples {
+    pub fn new(ranges: &[ops::Range<i64>], s: i64) -> FixedSumTuples {
+        let k = ranges.len();
+        let empty = ranges.iter().any(|r| { r.start >= r.end });
+        let mut min_rest = vec![0i128; k + 1];
+        let mut max_rest = vec![0i128; k + 1];
+        for i in (0..k).rev() {
+            min_rest[i] = min_rest[i + 1] + ranges[i].start as i128;
+            max_rest[i] = max_rest[i + 1] + ranges[i].end as i128 - 1;
+        }
+        let s = s as i128;
+        let mut t = FixedSumTuples {
+            ranges: ranges.to_vec(),
+            done: empty || s < min_rest[0] || s > max_rest[0],
+            min_rest: min_rest,
+            max_rest: max_rest,
+            tuple: vec![0; k],
+            first: true,
+        };
+        if !t.done { t.fill(0, s) }
+        t
+    }
+
+    // Set the coordinates from i on to the smallest ones summing to rem
+    fn fill(&mut self, i: usize, mut rem: i128) {
+        for j in i..self.tuple.len() {
+            let x = cmp::max(self.ranges[j].start as i128, rem - self.max_rest[j + 1]);
+            self.tuple[j] = x as i64;
+            rem -= x;
+        }
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for FixedSumTuples {
+    type Item = [i64];
+    fn next(&mut self) -> Option<&[i64]> {
+        if self.done { return None }
+        if self.first {
+            self.first = false;
+            return Some(&self.tuple)
+        }
+        // Increase the last coordinate which can be, the last one being
+        // determined by the others, and minimize the following ones
+        let k = self.tuple.len();
+        let mut rem = self.tuple.last().map_or(0, |&x| { x as i128 });
+        for i in (0..k.saturating_sub(1)).rev() {
+            let x = self.tuple[i] as i128;
+            rem += x;
+            let hi = cmp::min(self.ranges[i].end as i128 - 1, rem - self.min_rest[i + 1]);
+            if x < hi {
+                self.tuple[i] += 1;
+                self.fill(i + 1, rem - x - 1);
+                return Some(&self.tuple)
+            }
+        }
+        self.done = true;
+        None
+    }
+
+    fn get(&self) -> Option<&[i64]> {
+        if self.first || self.done { None } else { Some(&self.tuple) }
+    }
+}
+
+impl_snapshot!(LatticePoints, FixedSumTuples);
@@ -1,9 +1,74 @@
-#![feature(collections)]
+// Explicit field initialization is the style of this crate
+#![allow(clippy::redundant_field_names)]
+
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "rand")]
+extern crate rand;
+#[cfg(feature = "quickcheck")]
+extern crate quickcheck;
+#[cfg(feature = "proptest")]
+extern crate proptest;
+#[cfg(feature = "arbitrary")]
+extern crate arbitrary;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "memmap2")]
+extern crate memmap2;
+#[cfg(feature = "num-bigint")]
+extern crate num_bigint;
+#[cfg(feature = "python")]
+extern crate pyo3;
+
+pub mod error;
+#[macro_use]
 pub mod iter;
+pub mod rank;
+pub mod bits;
+pub mod permutation;
+pub mod partition;
+pub mod group;
+pub mod tableau;
+pub mod plane_partition;
+pub mod matrix;
+pub mod search;
+pub mod poset;
+pub mod graph;
+pub mod necklace;
+pub mod catalan;
+pub mod view;
+pub mod polytope;
+pub mod lattice;
+pub mod series;
+#[macro_use]
+pub mod testing;
+pub mod output;
+#[cfg(feature = "rayon")]
+pub mod par;
+#[cfg(feature = "rand")]
+pub mod random;
+#[cfg(feature = "rand")]
+pub mod boltzmann;
+#[cfg(feature = "quickcheck")]
+mod check;
+#[cfg(feature = "proptest")]
+pub mod strategy;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "python")]
+mod python;
+
+pub use error::Error;
 
 #[cfg(test)]
 mod tests {
     use iter::*;
+    use rank;
+    use bits;
 
     // Macro for testing iterators.
     macro_rules! iter_test {
@@ -13,13 +78,17 @@ mod tests {
          iter: $it: expr, // Iterator to test
          count: $x_count:expr) => { // Number of expected iterations
             #[test]
+            #[allow(redundant_semicolons)]
             fn $test_fn() {
                 $(use $($i)::*;)*
                 $($s;)*
                 let mut count = 0;
-                for x in $it {
-                    println!("{:?}", x);
-                    count += 1;
+                // Keep the temporaries of $it alive during the loop
+                match $it {
+                    mut it => while let Some(x) = it.next() {
+                        println!("{:?}", x);
+                        count += 1;
+                    }
                 }
                 let x_count = $x_count;
                 assert_eq![count, x_count]
@@ -40,10 +109,9 @@ mod tests {
 
     iter_test!(
         subsequences_count,
-        [ use std::num::Int; ],
         { let n = 6usize; },
         iter: (0..n).collect::<Vec<usize>>().subsequences(),
-        count: 2usize.pow(n)
+        count: 1usize << n
     );
 
     iter_test!(
@@ -66,5 +134,2471 @@ mod tests {
         iter: Catalan::new(n + 1),
         count: choose(2 * n, n) / (n + 1)
     );
-}
 
+    iter_test!(
+        combinations_clone_count,
+        { let n = 6usize; let k = 3usize;
+          let v = (0..n).collect::<Vec<usize>>();
+          let mut c = v.combinations(k);
+          let _ = c.next(); let _ = c.next(); },
+        iter: c.clone(),
+        count: choose(n, k) - 2
+    );
+
+    iter_test!(
+        combinations_with_buffers_count,
+        { let n = 6usize; let k = 3usize;
+          let v = (0..n).collect::<Vec<usize>>();
+          let (dest, indices) = v.combinations(k + 1).into_buffers(); },
+        iter: v.combinations_with_buffers(k, dest, indices),
+        count: choose(n, k)
+    );
+
+    #[test]
+    fn combinations_current_indices() {
+        let v = ['a', 'b', 'c', 'd'];
+        let mut c = v.lex_combinations(2);
+        c.next();
+        assert_eq!(c.current_indices(), &[0, 1][..]);
+        c.next();
+        assert_eq!(c.current_indices(), &[0, 2][..]);
+        let mut c = v.combinations(2);
+        c.next();
+        assert_eq!(c.current_indices(), &[0, 1][..]);
+        c.next();
+        assert_eq!(c.current_indices(), &[1, 2][..]);
+    }
+
+    #[test]
+    fn subsequences_next_into() {
+        let v = [1, 2];
+        let mut s = v.subsequences();
+        let mut buf = Vec::new();
+        let mut all = Vec::new();
+        while s.next_into(&mut buf) { all.push(buf.clone()); }
+        assert_eq!(all, vec![vec![], vec![1], vec![1, 2], vec![2]]);
+    }
+
+    iter_test!(
+        index_combinations_count,
+        { let n = 6usize; let k = 3usize; },
+        iter: IndexCombinations::new(n, k),
+        count: choose(n, k)
+    );
+
+    #[test]
+    fn subsequences_no_clone() {
+        struct NoClone(usize);
+        let v = [NoClone(1), NoClone(2), NoClone(3)];
+        let mut s = v.subsequences();
+        let mut sum = 0;
+        while s.advance() {
+            sum += s.current_refs().map(|x| { x.0 }).sum::<usize>();
+        }
+        assert_eq!(sum, 4 * (1 + 2 + 3));
+    }
+
+    #[test]
+    fn combination_rank_roundtrip() {
+        use rank::*;
+        let (n, k) = (7, 3);
+        let mut c = Vec::new();
+        let mut iter = IndexCombinations::new(n, k);
+        let mut r = 0;
+        while let Some(x) = iter.next() {
+            assert_eq!(combination_rank(n, x), r);
+            combination_unrank(n, k, r, &mut c);
+            assert_eq!(&c[..], x);
+            r += 1;
+        }
+    }
+
+    #[test]
+    fn subsequence_rank_roundtrip() {
+        use rank::*;
+        let n = 5;
+        let mut s = Vec::new();
+        let mut iter = IndexSubsequences::new(n);
+        let mut r = 0;
+        while let Some(x) = iter.next() {
+            assert_eq!(subsequence_rank(n, x), r);
+            subsequence_unrank(n, r, &mut s);
+            assert_eq!(&s[..], x);
+            r += 1;
+        }
+    }
+
+    #[test]
+    fn permutation_rank_roundtrip() {
+        use rank::*;
+        let n = 5;
+        let mut p = (0..n).collect::<Vec<usize>>();
+        let mut q = Vec::new();
+        for r in 0..factorial(n) {
+            assert_eq!(permutation_rank(&p), r);
+            permutation_unrank(n, r, &mut q);
+            assert_eq!(p, q);
+            next_permutation(&mut p);
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_combinations_sequential_order() {
+        use rayon::iter::ParallelIterator;
+        use par::ParallelCombinatorics;
+        let v = (0..8).collect::<Vec<usize>>();
+        let par = v.par_combinations(4).collect::<Vec<Vec<usize>>>();
+        let mut seq = Vec::new();
+        let mut c = IndexCombinations::new(8, 4);
+        while let Some(x) = c.next() { seq.push(x.to_vec()) }
+        assert_eq!(par, seq);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_product_not_clone() {
+        use rayon::iter::ParallelIterator;
+        use par::ParallelCombinatorics;
+        #[derive(Debug, PartialEq)]
+        struct NotClone(usize);
+        let a = (0..3).collect::<Vec<usize>>();
+        let b = (0..4).map(NotClone).collect::<Vec<_>>();
+        let pairs = a.par_product(&b).collect::<Vec<_>>();
+        assert_eq!(pairs.len(), 12);
+        assert_eq!(pairs[5], (&1, &NotClone(1)));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    #[should_panic(expected = "Cannot enumerate the subsequences")]
+    fn par_subsequences_too_long() {
+        use par::ParallelCombinatorics;
+        [(); 64].par_subsequences();
+    }
+
+    iter_test!(
+        submasks_count,
+        { let n = 6usize; },
+        iter: bits::Submasks::of_size(n),
+        count: 1usize << n
+    );
+
+    iter_test!(
+        mask_combinations_count,
+        { let n = 6usize; let k = 3usize; },
+        iter: bits::MaskCombinations::new(n, k),
+        count: choose(n, k)
+    );
+
+    #[test]
+    fn mask_combinations_full() {
+        let all = bits::MaskCombinations::new(64, 63).collect::<Vec<u64>>();
+        assert_eq!(all.len(), 64);
+        assert!(all.iter().all(|x| { x.count_ones() == 63 }));
+    }
+
+    #[test]
+    #[should_panic(expected = "up to 64 elements")]
+    fn mask_combinations_too_large() {
+        bits::MaskCombinations::new(65, 1);
+    }
+
+    #[test]
+    fn combinations_chunks() {
+        let v = (0..6).collect::<Vec<usize>>();
+        let mut sizes = Vec::new();
+        let mut chunks = v.combinations(3).chunks(8);
+        while let Some(chunk) = chunks.next() {
+            assert!(chunk.iter().all(|c| { c.len() == 3 }));
+            sizes.push(chunk.len());
+        }
+        assert_eq!(sizes, vec![8, 8, 4]);
+    }
+
+    #[test]
+    fn catalan_changed() {
+        let mut c = Catalan::new(7);
+        let mut prev = c.next().unwrap().to_vec();
+        assert_eq!(c.changed(), None);
+        while c.next().is_some() {
+            let (x, r) = (c.current_indices(), c.changed());
+            for i in 0..x.len() {
+                assert_eq!(x[i] != prev[i], r == Some(i));
+            }
+            prev = x.to_vec();
+        }
+    }
+
+    #[test]
+    fn binomial_table() {
+        let t = rank::BinomialTable::new(10, 4);
+        for n in 0..11 {
+            for k in 0..5 {
+                assert_eq!(t.choose(n, k), if k > n { 0 } else { choose(n, k) });
+            }
+        }
+        for c in IndexCombinations::new(10, 4).owned() {
+            assert_eq!(t.combination_rank(10, &c), rank::combination_rank(10, &c));
+        }
+        // Without a table, ranking takes constant memory
+        let c = (0..1000).collect::<Vec<usize>>();
+        assert_eq!(rank::combination_rank(100000, &c), 0);
+    }
+
+    iter_test!(
+        index_permutations_count,
+        { let n = 6usize; },
+        iter: index_permutations(n),
+        count: rank::factorial(n)
+    );
+
+    #[test]
+    fn index_permutations_swaps() {
+        let mut p = index_permutations(5);
+        let mut data = vec!['a', 'b', 'c', 'd', 'e'];
+        while let Some((i, j)) = p.advance() {
+            data.swap(i, j);
+            let expected = p.current().iter()
+                .map(|&k| { (b'a' + k as u8) as char }).collect::<Vec<char>>();
+            assert_eq!(data, expected);
+        }
+    }
+
+    iter_test!(
+        gray_combinations_count,
+        { let n = 7usize; let k = 4usize; },
+        iter: GrayCombinations::new(n, k),
+        count: choose(n, k)
+    );
+
+    #[test]
+    fn gray_combinations_minimal_change() {
+        let v = (10..18).collect::<Vec<usize>>();
+        let mut c = v.gray_combinations(3);
+        let mut prev: Option<Vec<usize>> = None;
+        while let Some(x) = c.next() {
+            let x = x.to_vec();
+            let expected = c.current_indices().iter()
+                .map(|&j| { v[j] }).collect::<Vec<usize>>();
+            assert_eq!(x, expected);
+            if let Some(p) = prev {
+                assert_eq!(x.iter().filter(|y| { !p.contains(y) }).count(), 1);
+            }
+            prev = Some(x);
+        }
+    }
+
+    #[test]
+    fn gray_combinations_all() {
+        // Every combination once, in the order given by unranking
+        for n in 0..10 {
+            for k in 0..(n + 1) {
+                let mut c = GrayCombinations::new(n, k);
+                let mut seen = ::std::collections::HashSet::new();
+                let mut r = 0;
+                while let Some(x) = c.next() {
+                    let mut u = GrayCombinations::new(n, k);
+                    u.advance_by(r).unwrap();
+                    assert_eq!(u.next(), Some(x));
+                    assert!(x.windows(2).all(|w| { w[0] < w[1] }));
+                    assert!(seen.insert(x.to_vec()));
+                    r += 1;
+                }
+                assert_eq!(r, choose(n, k));
+            }
+        }
+    }
+
+    #[test]
+    fn permutation_compose_inverse() {
+        use permutation::Permutation;
+        let mut ps = Vec::new();
+        let mut iter = index_permutations(4).as_permutations();
+        while let Some(p) = iter.next() { ps.push(p.clone()) }
+        let v = vec!['a', 'b', 'c', 'd'];
+        for p in &ps {
+            assert_eq!(p.compose(&p.inverse()), Permutation::identity(4));
+            for q in &ps {
+                assert_eq!(p.compose(q).apply(&v), q.apply(&p.apply(&v)));
+            }
+        }
+    }
+
+    #[test]
+    fn permutation_cycles() {
+        use permutation::Permutation;
+        let p = Permutation::from_vec(vec![2, 0, 1, 3, 5, 4]).unwrap();
+        let cycles = p.cycles();
+        assert_eq!(cycles, vec![vec![0, 2, 1], vec![3], vec![4, 5]]);
+        assert_eq!(p.cycle_type().parts(), &[3, 2, 1][..]);
+        assert_eq!(Permutation::from_cycles(6, &cycles), Some(p));
+        assert_eq!(Permutation::from_cycles(3, &[vec![0, 1], vec![1, 2]]), None);
+    }
+
+    #[test]
+    fn permutation_parity_alternates() {
+        use permutation::{parity, Parity};
+        // Each step is a transposition
+        let mut expected = Parity::Even;
+        let mut iter = index_permutations(5);
+        while let Some(p) = iter.next() {
+            assert_eq!(parity(p), expected);
+            expected = if expected == Parity::Even { Parity::Odd } else { Parity::Even };
+        }
+    }
+
+    #[test]
+    fn permutation_apply_in_place() {
+        let v = vec!['a', 'b', 'c', 'd', 'e'];
+        let mut iter = index_permutations(5).as_permutations();
+        while let Some(p) = iter.next() {
+            let mut w = v.clone();
+            p.apply_to(&mut w);
+            assert_eq!(w, p.apply(&v));
+            p.apply_inverse_to(&mut w);
+            assert_eq!(w, v);
+        }
+    }
+
+    #[test]
+    fn group_symmetric() {
+        use permutation::Permutation;
+        use group::Group;
+        let n = 5;
+        let swap = Permutation::from_cycles(n, &[vec![0, 1]]).unwrap();
+        let cycle = Permutation::from_cycles(n, &[(0..n).collect()]).unwrap();
+        let g = Group::generated_by(n, &[swap, cycle]);
+        assert_eq!(g.order(), rank::factorial(n) as u128);
+        let mut elements = g.elements().collect::<Vec<Permutation>>();
+        elements.sort();
+        elements.dedup();
+        assert_eq!(elements.len(), rank::factorial(n));
+    }
+
+    #[test]
+    fn group_dihedral() {
+        use permutation::Permutation;
+        use group::Group;
+        let n = 6;
+        let rotation = Permutation::from_cycles(n, &[(0..n).collect()]).unwrap();
+        let reflection = Permutation::from_vec((0..n).map(|i| { (n - i) % n }).collect()).unwrap();
+        let g = Group::generated_by(n, &[rotation.clone(), reflection]);
+        assert_eq!(g.order(), 2 * n as u128);
+        assert_eq!(g.elements().count(), 2 * n);
+        // S_25, whose order does not fit in a u64
+        let swap = Permutation::from_cycles(25, &[vec![0, 1]]).unwrap();
+        let cycle = Permutation::from_cycles(25, &[(0..25).collect()]).unwrap();
+        let s25 = Group::generated_by(25, &[swap, cycle]);
+        assert_eq!(s25.order(), rank::factorial_as::<u128>(25));
+        assert!(g.contains(&rotation.compose(&rotation)));
+        let swap = Permutation::from_cycles(n, &[vec![0, 1]]).unwrap();
+        assert!(!g.contains(&swap));
+        assert_eq!(g.orbit(2), (0..n).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn group_orbits_of_sets() {
+        use permutation::Permutation;
+        use group::{Action, Group};
+        let n = 6;
+        let rotation = Permutation::from_cycles(n, &[(0..n).collect()]).unwrap();
+        let g = Group::generated_by(n, &[rotation]);
+        // Burnside: (20 + 2 + 2) / 6
+        assert_eq!(g.orbits(Action::Sets, IndexCombinations::new(n, 3)).count(), 4);
+    }
+
+    #[test]
+    fn permutation_notations() {
+        use permutation::Permutation;
+        let p = Permutation::from_one_line("[2, 0 1 3 5 4]").unwrap();
+        assert_eq!(format!("{}", p), "[2 0 1 3 5 4]");
+        assert_eq!(format!("{}", p.cycle_notation()), "(0 2 1)(3)(4 5)");
+        assert_eq!(Permutation::from_cycle_notation(6, "(0 2 1)(4 5)"), Some(p.clone()));
+        assert_eq!(Permutation::from_word(6, &p.reduced_word()), Some(p.clone()));
+        assert_eq!(Permutation::from_one_line("0 0"), None);
+        assert_eq!(Permutation::from_cycle_notation(3, "(0 1"), None);
+        assert_eq!(Permutation::from_word(3, &[2]), None);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn random_samples_are_valid() {
+        use rand::Rng;
+        use random::*;
+        use permutation;
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let p = rng.sample(UniformPermutation(7));
+            assert!(permutation::is_permutation(p.as_slice()));
+            let c = rng.sample(UniformCombination { n: 7, k: 3 });
+            assert_eq!(c.len(), 3);
+            assert!(c.windows(2).all(|w| { w[0] < w[1] }) && c[2] < 7);
+            let s = rng.sample(UniformSubsequence(7));
+            assert!(s.windows(2).all(|w| { w[0] < w[1] }));
+        }
+    }
+
+    #[test]
+    fn permutation_pow_order() {
+        use permutation::Permutation;
+        let p = Permutation::from_cycle_notation(7, "(0 1 2)(3 4)").unwrap();
+        assert_eq!(p.order(), 6);
+        assert_eq!(p.pow(-1), p.inverse());
+        let mut q = Permutation::identity(7);
+        for k in 0..10 {
+            assert_eq!(p.pow(k), q);
+            q = q.compose(&p);
+        }
+    }
+
+    iter_test!(
+        derangements_count,
+        { let n = 6usize; },
+        iter: ConstrainedPermutations::derangements(n),
+        count: 265
+    );
+
+    #[test]
+    fn constrained_permutations() {
+        let n = 6;
+        let mut count = 0;
+        let mut iter = ConstrainedPermutations::with_points(n, &[0, 5], &[1, 2]);
+        while let Some(p) = iter.next() {
+            assert!(p[0] == 0 && p[5] == 5 && p[1] != 1 && p[2] != 2);
+            count += 1;
+        }
+        // Permutations of {1, 2, 3, 4} moving 1 and 2: 24 - 2 * 6 + 2
+        assert_eq!(count, 14);
+    }
+
+    #[test]
+    #[should_panic(expected = "6 is not a point of 0..6")]
+    fn constrained_permutations_out_of_range() {
+        ConstrainedPermutations::with_points(6, &[0], &[6]);
+    }
+
+    #[cfg(feature = "quickcheck")]
+    #[test]
+    fn quickcheck_shrinks_are_valid() {
+        use quickcheck::{quickcheck, Arbitrary};
+        use permutation::{self, Permutation};
+        use partition::Partition;
+        use catalan::BinaryTree;
+        use view::SubsetMask;
+        fn permutations(p: Permutation) -> bool {
+            permutation::is_permutation(p.as_slice()) &&
+                p.shrink().all(|q| { permutation::is_permutation(q.as_slice()) })
+        }
+        fn partitions(l: Partition) -> bool {
+            l.shrink().all(|m| { m.size() < l.size() })
+        }
+        fn trees(t: BinaryTree) -> bool {
+            t.shrink().all(|u| { u.size() < t.size() })
+        }
+        fn masks(s: SubsetMask) -> bool {
+            s.shrink().all(|t| {
+                t.mask() & !s.mask() == 0 && t.len() + t.universe() < s.len() + s.universe()
+            })
+        }
+        quickcheck(permutations as fn(Permutation) -> bool);
+        quickcheck(partitions as fn(Partition) -> bool);
+        quickcheck(trees as fn(BinaryTree) -> bool);
+        quickcheck(masks as fn(SubsetMask) -> bool);
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn proptest_strategies(p in ::strategy::any_permutation(6),
+                               l in ::strategy::any_partition_of(6),
+                               s in ::strategy::any_subset(6)) {
+            use permutation;
+            proptest::prop_assert!(permutation::is_permutation(p.as_slice()));
+            proptest::prop_assert_eq!(l.size(), 6);
+            proptest::prop_assert!(s < 1 << 6);
+        }
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_decodes_valid_objects() {
+        use arbitrary::{Arbitrary, Unstructured};
+        use permutation::{self, Permutation};
+        use partition::Partition;
+        let bytes = (0..255).map(|x: u8| { x.wrapping_mul(37) }).collect::<Vec<u8>>();
+        for i in 0..bytes.len() {
+            let mut u = Unstructured::new(&bytes[i..]);
+            let p = Permutation::arbitrary(&mut u).unwrap();
+            assert!(permutation::is_permutation(p.as_slice()));
+            let mut u = Unstructured::new(&bytes[i..]);
+            let l = Partition::arbitrary(&mut u).unwrap();
+            assert!(l.parts().windows(2).all(|w| { w[0] >= w[1] }));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_deserialize_validates() {
+        use serde::Deserialize;
+        use serde::de::value::{Error, SeqDeserializer};
+        use permutation::Permutation;
+        use partition::Partition;
+        fn seq(v: Vec<usize>) -> SeqDeserializer<::std::vec::IntoIter<usize>, Error> {
+            SeqDeserializer::new(v.into_iter())
+        }
+        assert_eq!(Permutation::deserialize(seq(vec![2, 0, 1])).ok(),
+                   Permutation::from_vec(vec![2, 0, 1]));
+        assert!(Permutation::deserialize(seq(vec![2, 0, 2])).is_err());
+        assert_eq!(Partition::deserialize(seq(vec![3, 1, 1])).ok(),
+                   Some(Partition::from_parts(vec![1, 3, 1])));
+        assert!(Partition::deserialize(seq(vec![1, 3])).is_err());
+        assert!(Partition::deserialize(seq(vec![3, 0])).is_err());
+    }
+
+    #[test]
+    fn owned_pipeline() {
+        let v = (0..5).collect::<Vec<usize>>();
+        let windows = v.lex_combinations(2).owned()
+            .zip(v.lex_combinations(2).owned().skip(1))
+            .filter(|(a, b)| { a[0] == b[0] })
+            .count();
+        // Consecutive pairs sharing their first element
+        assert_eq!(windows, 3 + 2 + 1);
+        let all = index_permutations(3).as_permutations().owned()
+            .map(|p| { p.inverse() }).collect::<Vec<_>>();
+        assert_eq!(all.len(), 6);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn random_streams() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+        use random::*;
+        let ps = RandomPermutations::new(4, StdRng::seed_from_u64(0));
+        // Monte-Carlo estimate of the proportion of derangements, 9/24
+        let deranged = ps.take(10000)
+            .filter(|p| { (0..4).all(|i| { p[i] != i }) }).count();
+        assert!(3000 < deranged && deranged < 4500);
+        let cs = RandomCombinations::new(6, 2, StdRng::seed_from_u64(1));
+        assert!(cs.take(100).all(|c| { c.len() == 2 && c[0] < c[1] }));
+        let ss = RandomSubsequences::new(6, StdRng::seed_from_u64(2));
+        assert!(ss.take(100).all(|s| { s.iter().all(|&i| { i < 6 }) }));
+    }
+
+    #[cfg(feature = "ffi")]
+    #[test]
+    fn ffi_callbacks() {
+        use std::os::raw::{c_int, c_void};
+        use ffi::*;
+        // Sum the first indices, stopping after 10 items
+        extern "C" fn sum(p: *const usize, len: usize, data: *mut c_void) -> c_int {
+            let x = unsafe { ::std::slice::from_raw_parts(p, len) };
+            let total = unsafe { &mut *(data as *mut (usize, usize)) };
+            total.0 += x.first().map_or(0, |&i| { i });
+            total.1 += 1;
+            (total.1 == 10) as c_int
+        }
+        let mut total = (0usize, 0usize);
+        let data = &mut total as *mut (usize, usize) as *mut c_void;
+        assert_eq!(combinatorust_combinations(5, 2, sum, data), 10);
+        // Four combinations start with 0, three with 1, two with 2, one with 3
+        assert_eq!(total, (3 + 2 * 2 + 3, 10));
+        assert_eq!(combinatorust_combinations(2, 3, sum, data), 0);
+        let mut total = (0usize, 0usize);
+        let data = &mut total as *mut (usize, usize) as *mut c_void;
+        assert_eq!(combinatorust_permutations(3, sum, data), 6);
+        assert_eq!(combinatorust_subsequences(2, sum, data), 4);
+    }
+
+    #[test]
+    fn semistandard_tableaux() {
+        use partition::Partition;
+        use tableau::SemistandardTableaux;
+        // Hook-content formula: prod (m + c(u)) / h(u)
+        let count = |parts: Vec<usize>, m| {
+            let mut t = SemistandardTableaux::new(&Partition::from_parts(parts), m);
+            let mut count = 0;
+            while let Some(rows) = t.next() {
+                for (i, r) in rows.iter().enumerate() {
+                    assert!(r.windows(2).all(|w| { w[0] <= w[1] }));
+                    assert!(r.iter().all(|&x| { 1 <= x && x <= m }));
+                    if i > 0 {
+                        assert!(r.iter().zip(&rows[i - 1]).all(|(x, y)| { x > y }));
+                    }
+                }
+                count += 1;
+            }
+            count
+        };
+        assert_eq!(count(vec![2, 1], 3), 8);
+        assert_eq!(count(vec![3, 1], 3), 15);
+        assert_eq!(count(vec![2, 2], 3), 6);
+        assert_eq!(count(vec![3, 2, 1], 4), 64);
+        assert_eq!(count(vec![1, 1, 1], 2), 0);
+        assert_eq!(count(vec![], 0), 1);
+    }
+
+    #[test]
+    fn plane_partitions_macmahon() {
+        use plane_partition::PlanePartitions;
+        for &(a, b, c) in &[(1, 1, 3), (2, 2, 2), (2, 3, 4), (3, 3, 3), (0, 2, 2)] {
+            let mut macmahon = 1.0;
+            for i in 1..(a + 1) {
+                for j in 1..(b + 1) {
+                    for k in 1..(c + 1) {
+                        macmahon *= (i + j + k - 1) as f64 / (i + j + k - 2) as f64;
+                    }
+                }
+            }
+            assert_eq!(PlanePartitions::new(a, b, c).count(), macmahon.round() as usize);
+        }
+    }
+
+    #[test]
+    fn latin_squares() {
+        use matrix::LatinSquares;
+        let mut squares = LatinSquares::new(4);
+        let mut count = 0;
+        while let Some(rows) = squares.next() {
+            for i in 0..4 {
+                let mut row = rows[i].clone();
+                let mut col = rows.iter().map(|r| { r[i] }).collect::<Vec<usize>>();
+                row.sort();
+                col.sort();
+                assert!(row == [0, 1, 2, 3] && col == [0, 1, 2, 3]);
+            }
+            count += 1;
+        }
+        assert_eq!(count, 576);
+        assert_eq!(LatinSquares::reduced(4).count(), 4);
+        assert_eq!(LatinSquares::reduced(5).count(), 56);
+        assert_eq!(LatinSquares::reduced(0).count(), 1);
+    }
+
+    #[test]
+    fn magic_squares() {
+        use matrix::MagicSquares;
+        let mut squares = MagicSquares::new(3);
+        assert_eq!(squares.next().unwrap(), &[vec![2, 7, 6], vec![9, 5, 1], vec![4, 3, 8]][..]);
+        assert_eq!(squares.count(), 7);
+        assert_eq!(MagicSquares::new(2).count(), 0);
+        assert_eq!(MagicSquares::new(1).count(), 1);
+    }
+
+    #[test]
+    fn alternating_sign_matrices_count() {
+        use matrix::AlternatingSignMatrices;
+        let counts = (0..6).map(|n| { AlternatingSignMatrices::new(n).count() })
+            .collect::<Vec<usize>>();
+        assert_eq!(counts, vec![1, 1, 2, 7, 42, 429]);
+    }
+
+    #[test]
+    fn multisets() {
+        let mut m = Multisets::new(3, 2);
+        let mut all = Vec::new();
+        while let Some(x) = m.next() { all.push(x.to_vec()) }
+        assert_eq!(all, vec![vec![2, 0, 0], vec![1, 1, 0], vec![1, 0, 1],
+                             vec![0, 2, 0], vec![0, 1, 1], vec![0, 0, 2]]);
+        assert_eq!(Multisets::new(5, 4).count(), choose(8, 4));
+        assert_eq!(Multisets::new(0, 0).count(), 1);
+        assert_eq!(Multisets::new(0, 1).count(), 0);
+    }
+
+    #[test]
+    fn compositions() {
+        let mut c = Compositions::new(3);
+        let mut all = Vec::new();
+        while let Some(x) = c.next() { all.push(x.to_vec()) }
+        assert_eq!(all, vec![vec![1, 1, 1], vec![1, 2], vec![2, 1], vec![3]]);
+        assert_eq!(Compositions::new(8).count(), 1 << 7);
+        // Tilings by squares and dominoes
+        assert_eq!(Compositions::with_parts(10, &[1, 2]).count(), 89);
+        assert_eq!(Compositions::with_parts(7, &[2, 4]).count(), 0);
+        assert_eq!(Compositions::new(0).count(), 1);
+    }
+
+    #[test]
+    fn partitions() {
+        let mut p = Partitions::new(4);
+        let mut all = Vec::new();
+        while let Some(x) = p.next() { all.push(x.to_vec()) }
+        assert_eq!(all, vec![vec![4], vec![3, 1], vec![2, 2], vec![2, 1, 1], vec![1, 1, 1, 1]]);
+        assert_eq!(Partitions::new(10).count(), 42);
+        assert_eq!(Partitions::with_max_part(10, 3).count(), 14);
+        assert_eq!(Partitions::with_max_part(3, 0).count(), 0);
+        assert_eq!(Partitions::new(0).count(), 1);
+    }
+
+    #[test]
+    fn partition_conjugate_containment() {
+        use partition::Partition;
+        let l = Partition::from_parts(vec![4, 2, 1]);
+        assert_eq!(l.conjugate().parts(), &[3, 2, 1, 1][..]);
+        assert!(l.contains(&Partition::from_parts(vec![3, 2])));
+        assert!(!l.contains(&Partition::from_parts(vec![3, 3])));
+        assert!(l.fits_in(3, 4) && !l.fits_in(2, 4) && !l.fits_in(3, 3));
+        let mut ps = Partitions::new(4).as_partitions();
+        let mut count = 0;
+        while let Some(p) = ps.next() {
+            assert_eq!(p.conjugate().conjugate(), *p);
+            assert_eq!(p.conjugate().size(), 4);
+            if p.fits_in(3, 3) { count += 1 }
+        }
+        // Coefficient of q^4 in the Gaussian binomial [6 choose 3]_q
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn search_queens() {
+        use search::{Problem, Solutions};
+        // Queens placed on the first rows, by column
+        struct Queens(usize);
+        impl Problem for Queens {
+            type State = Vec<usize>;
+            fn children(&self, q: &Vec<usize>, out: &mut Vec<Vec<usize>>) {
+                if q.len() == self.0 { return }
+                for j in 0..self.0 {
+                    let mut r = q.clone();
+                    r.push(j);
+                    out.push(r);
+                }
+            }
+            fn is_solution(&self, q: &Vec<usize>) -> bool {
+                q.len() == self.0
+            }
+            fn prune(&self, q: &Vec<usize>) -> bool {
+                let (&j, init) = match q.split_last() { Some(x) => x, None => return false };
+                let i = init.len();
+                init.iter().enumerate().any(|(k, &l)| {
+                    l == j || i - k == l.abs_diff(j)
+                })
+            }
+        }
+        let counts = [1, 1, 0, 0, 2, 10, 4, 40];
+        for (n, &c) in counts.iter().enumerate() {
+            assert_eq!(Solutions::new(Queens(n), Vec::new()).count(), c);
+        }
+        let first = Solutions::new(Queens(4), Vec::new()).next();
+        assert_eq!(first, Some(vec![1, 3, 0, 2]));
+    }
+
+    #[test]
+    fn weighted_combinations() {
+        let weights = [5, 1, 8, 3, 3, 0, 7, 2, 6, 4];
+        for k in 0..(weights.len() + 1) {
+            for &(min, max) in &[(0, 0), (0, 100), (10, 15), (12, 12), (40, 100)] {
+                let mut expected = 0;
+                let mut it = IndexCombinations::new(weights.len(), k);
+                while let Some(c) = it.next() {
+                    let w: u64 = c.iter().map(|&i| { weights[i] }).sum();
+                    if min <= w && w <= max { expected += 1 }
+                }
+                let mut count = 0;
+                let mut it = WeightedCombinations::new(&weights, k, min, max);
+                while let Some(c) = it.next() {
+                    let w: u64 = c.iter().map(|&i| { weights[i] }).sum();
+                    assert!(min <= w && w <= max);
+                    assert!(c.windows(2).all(|p| { p[0] < p[1] }));
+                    assert_eq!(w, it.weight());
+                    count += 1;
+                }
+                assert_eq!(count, expected, "k = {}, {}..={}", k, min, max);
+            }
+        }
+        // Sums of weights beyond u64
+        let big = u64::MAX / 2;
+        let it = WeightedCombinations::new(&[big, 1, big, big], 2, big, u64::MAX);
+        assert_eq!(it.owned().collect::<Vec<_>>(), vec![vec![0, 1], vec![1, 2], vec![1, 3],
+                                                         vec![0, 2], vec![0, 3], vec![2, 3]]);
+        assert_eq!(WeightedCombinations::new(&[big, 1, big, big], 3, 0, u64::MAX).count(), 3);
+    }
+
+    #[test]
+    fn subset_sums() {
+        let weights = [5, 1, 8, 3, 3, 0, 7, 2, 6, 4];
+        for target in 0..45 {
+            let mut expected = Vec::new();
+            let mut it = IndexSubsequences::new(weights.len());
+            while let Some(c) = it.next() {
+                if c.iter().map(|&i| { weights[i] }).sum::<usize>() == target {
+                    expected.push(c.to_vec());
+                }
+            }
+            expected.sort_by_key(|c| {
+                (0..weights.len()).map(|i| { c.contains(&i) }).collect::<Vec<bool>>()
+            });
+            let sums = SubsetSums::new(&weights, target).owned().collect::<Vec<_>>();
+            assert_eq!(sums, expected);
+        }
+        // Pairs among 40
+        assert_eq!(SubsetSums::new(&[1; 40], 2).count(), 780);
+    }
+
+    #[test]
+    fn maximal_subsets() {
+        let weights = [5, 1, 8, 3, 3, 0, 7, 2, 6, 4];
+        let total: usize = weights.iter().sum();
+        for capacity in 0..(total + 2) {
+            let mut expected = Vec::new();
+            let mut it = IndexSubsequences::new(weights.len());
+            while let Some(c) = it.next() {
+                let w: usize = c.iter().map(|&i| { weights[i] }).sum();
+                let maximal = (0..weights.len()).all(|i| {
+                    c.contains(&i) || w + weights[i] > capacity
+                });
+                if w <= capacity && maximal { expected.push(c.to_vec()) }
+            }
+            expected.sort_by_key(|c| {
+                (0..weights.len()).map(|i| { !c.contains(&i) }).collect::<Vec<bool>>()
+            });
+            let mut it = MaximalSubsets::new(&weights, capacity);
+            let mut subsets = Vec::new();
+            while let Some(c) = it.next() {
+                subsets.push(c.to_vec());
+                assert!(it.weight() <= capacity);
+            }
+            assert_eq!(subsets, expected, "capacity {}", capacity);
+        }
+    }
+
+    #[test]
+    fn linear_extensions() {
+        use poset::LinearExtensions;
+        let pairs = [(3, 0), (3, 5), (0, 1), (5, 1), (2, 4)];
+        let mut expected = Vec::new();
+        let mut it = index_permutations(6);
+        while let Some(p) = it.next() {
+            let pos = |x| { p.iter().position(|&y| { y == x }).unwrap() };
+            if pairs.iter().all(|&(a, b)| { pos(a) < pos(b) }) { expected.push(p.to_vec()) }
+        }
+        let mut exts = LinearExtensions::new(6, &pairs).owned().collect::<Vec<_>>();
+        exts.sort();
+        expected.sort();
+        assert_eq!(exts, expected);
+        assert_eq!(LinearExtensions::new(4, &[]).count(), 24);
+        assert_eq!(LinearExtensions::new(4, &[(0, 1), (2, 3)]).count(), 6);
+        assert_eq!(LinearExtensions::new(3, &[(0, 1), (1, 2), (2, 0)]).count(), 0);
+        assert_eq!(LinearExtensions::new(0, &[]).count(), 1);
+    }
+
+    #[test]
+    fn latin_row_extensions() {
+        use matrix::{LatinRowExtensions, LatinSquares};
+        // Extending row by row counts the Latin squares
+        fn count(n: usize, rows: &mut Vec<Vec<usize>>) -> usize {
+            if rows.len() == n { return 1 }
+            let mut total = 0;
+            let mut it = LatinRowExtensions::new(n, rows);
+            while let Some(r) = it.next() {
+                rows.push(r.to_vec());
+                total += count(n, rows);
+                rows.pop();
+            }
+            total
+        }
+        for n in 0..5 {
+            assert_eq!(count(n, &mut Vec::new()), LatinSquares::new(n).count());
+        }
+        // Derangements of the first row
+        assert_eq!(LatinRowExtensions::new(5, &[vec![0, 1, 2, 3, 4]]).count(), 44);
+        let mut it = LatinRowExtensions::new(3, &[vec![0, 1, 2], vec![1, 2, 0]]);
+        assert_eq!(it.next(), Some(&[2, 0, 1][..]));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn graph_colorings() {
+        use graph::{Graph, Colorings};
+        // The chromatic polynomial of the cycle C_5 is (k-1)^5 - (k-1)
+        let c5 = Graph::from_edges(5, &[(0, 1), (1, 2), (2, 3), (3, 4), (4, 0)]);
+        for k in 0..5 {
+            let l = k as i64 - 1;
+            assert_eq!(Colorings::new(&c5, k).count() as i64, l.pow(5) - l);
+        }
+        // The colorings of the empty graph with enough colors are the set
+        // partitions, counted by Bell numbers
+        assert_eq!(Colorings::canonical(&Graph::new(5), 5).count(), 52);
+        // Canonical colorings with k colors times k!/(k-used)! give all
+        // colorings
+        let g = Graph::from_edges(5, &[(0, 1), (1, 2), (0, 2), (2, 3), (3, 4)]);
+        let mut it = Colorings::canonical(&g, 4);
+        let mut total = 0;
+        while it.next().is_some() {
+            total += (0..it.colors_used()).map(|i| { 4 - i }).product::<usize>();
+        }
+        assert_eq!(total, Colorings::new(&g, 4).count());
+        assert_eq!(Colorings::new(&Graph::complete(4), 3).count(), 0);
+    }
+
+    #[test]
+    fn independent_sets_and_cliques() {
+        use graph::Graph;
+        let g = Graph::from_edges(6, &[(0, 1), (1, 2), (0, 2), (2, 3), (3, 4), (4, 5), (5, 3), (1, 4)]);
+        let n = g.order();
+        let independent = |s: u64| {
+            (0..n).all(|v| { s & 1 << v == 0 || s & g.neighbors(v) == 0 })
+        };
+        let clique = |s: u64| {
+            (0..n).all(|v| { s & 1 << v == 0 || s & !(1 << v) & !g.neighbors(v) == 0 })
+        };
+        let all = bits::Submasks::of_size(n).collect::<Vec<u64>>();
+        let check = |mut found: Vec<u64>, p: &dyn Fn(u64) -> bool, maximal: bool| {
+            let mut expected = all.iter().cloned().filter(|&s| {
+                p(s) && (!maximal || (0..n).all(|v| { s & 1 << v != 0 || !p(s | 1 << v) }))
+            }).collect::<Vec<u64>>();
+            found.sort();
+            expected.sort();
+            assert_eq!(found, expected);
+        };
+        check(g.independent_sets().collect(), &independent, false);
+        check(g.cliques().collect(), &clique, false);
+        check(g.maximal_independent_sets().collect(), &independent, true);
+        check(g.maximal_cliques().collect(), &clique, true);
+        assert_eq!(Graph::new(0).maximal_cliques().collect::<Vec<u64>>(), vec![0]);
+        assert_eq!(Graph::new(10).independent_sets().count(), 1024);
+    }
+
+    #[test]
+    fn with_rank() {
+        let mut it = IndexCombinations::new(6, 3).with_rank();
+        let mut count = 0;
+        while let Some((r, c)) = it.next() {
+            assert_eq!(r, rank::combination_rank(6, c) as u128);
+            count += 1;
+        }
+        assert_eq!(count, 20);
+        assert!(it.get().is_none());
+        let mut it = IndexCombinations::new(6, 3).with_rank().starting_at(7);
+        assert_eq!(it.next(), Some((7, &[0, 1, 2][..])));
+        assert_eq!(it.get(), Some((7, &[0, 1, 2][..])));
+        // An item yielded before wrapping has no rank
+        let mut c = IndexCombinations::new(6, 3);
+        c.next();
+        let mut it = c.with_rank();
+        assert!(it.get().is_none());
+        assert_eq!(it.next(), Some((0, &[0, 1, 3][..])));
+    }
+
+    #[test]
+    fn rev_lex() {
+        for n in 0..7 {
+            for k in 0..(n + 1) {
+                let mut lex = IndexCombinations::new(n, k).owned().collect::<Vec<_>>();
+                lex.reverse();
+                assert_eq!(IndexCombinations::rev_lex(n, k).owned().collect::<Vec<_>>(), lex);
+            }
+            let mut lex = IndexSubsequences::new(n).owned().collect::<Vec<_>>();
+            lex.reverse();
+            let mut it = IndexSubsequences::rev_lex(n);
+            let mut rev = Vec::new();
+            while let Some(s) = it.next() { rev.push(s.to_vec()) }
+            assert_eq!(rev, lex);
+            // Wraps around
+            assert_eq!(it.next().map(|s| { s.to_vec() }), lex.first().cloned());
+        }
+        let v = ['a', 'b', 'c', 'd'];
+        let mut it = v.rev_lex_combinations(2);
+        assert_eq!(it.next(), Some(&['c', 'd'][..]));
+        assert_eq!(it.next(), Some(&['b', 'd'][..]));
+        let mut it = v.rev_lex_subsequences();
+        assert_eq!(it.next(), Some(&['d'][..]));
+        assert_eq!(it.next(), Some(&['c', 'd'][..]));
+        assert_eq!(it.next(), Some(&['c'][..]));
+        assert_eq!(it.next(), Some(&['b', 'd'][..]));
+    }
+
+    #[test]
+    fn orders() {
+        for n in 0..7 {
+            for k in 0..(n + 1) {
+                let mut colex = IndexCombinations::new(n, k).owned().collect::<Vec<_>>();
+                colex.sort_by(|a, b| { a.iter().rev().cmp(b.iter().rev()) });
+                let it = IndexCombinations::with_order(n, k, Order::Colex);
+                assert_eq!(it.owned().collect::<Vec<_>>(), colex);
+            }
+            let mut lex = Compositions::new(n).owned().collect::<Vec<_>>();
+            lex.reverse();
+            let parts = (1..(n + 1)).collect::<Vec<usize>>();
+            let it = Compositions::with_order(n, &parts, Order::RevLex);
+            assert_eq!(it.owned().collect::<Vec<_>>(), lex);
+        }
+        let mut lex = Compositions::with_parts(10, &[2, 3, 7]).owned().collect::<Vec<_>>();
+        lex.reverse();
+        let it = Compositions::with_order(10, &[2, 3, 7], Order::RevLex);
+        assert_eq!(it.owned().collect::<Vec<_>>(), lex);
+        assert_eq!(IndexPermutations::with_order(4, Order::GrayMinimalChange).count(), 24);
+    }
+
+    #[test]
+    #[should_panic]
+    fn unsupported_order() {
+        IndexSubsequences::with_order(3, Order::Colex);
+    }
+
+    #[test]
+    fn transitions() {
+        let mut c = (0..3).collect::<Vec<usize>>();
+        let mut it = GrayCombinations::new(6, 3);
+        it.next();
+        let mut count = 1;
+        for (removed, added) in it.clone().transitions() {
+            let i = c.iter().position(|&x| { x == removed }).unwrap();
+            c[i] = added;
+            c.sort();
+            assert_eq!(it.next(), Some(&c[..]));
+            count += 1;
+        }
+        assert_eq!(count, 20);
+        assert_eq!(it.next(), None);
+
+        let mut p = (0..4).collect::<Vec<usize>>();
+        let mut it = index_permutations(4);
+        it.next();
+        for (i, j) in it.clone().transitions() {
+            p.swap(i, j);
+            assert_eq!(it.next(), Some(&p[..]));
+        }
+        assert_eq!(it.next(), None);
+
+        let mut mask = 0;
+        let mut masks = bits::GrayMasks::new(5);
+        assert_eq!(masks.next(), Some(0));
+        for i in bits::GrayMasks::new(5).transitions() {
+            mask ^= 1 << i;
+            assert_eq!(masks.next(), Some(mask));
+        }
+        assert_eq!(masks.next(), None);
+        let mut all = bits::GrayMasks::new(5).collect::<Vec<u64>>();
+        all.sort();
+        assert_eq!(all, (0..32).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn notations() {
+        use partition::Partition;
+        let l = Partition::from_parts(vec![1, 4, 2, 4, 1, 4]);
+        assert_eq!(l.to_string(), "[4 4 4 2 1 1]");
+        assert_eq!(l.exponent_notation().to_string(), "4^3 2 1^2");
+        assert_eq!(Partition::from_parts(vec![3, 1]).young_diagram().to_string(), "###\n#");
+        assert_eq!(bits::bitstring(bits::indices_mask(&[1, 2]), 4).to_string(), "0110");
+        assert_eq!(catalan_parens(&[0, 1, 2, 3, 3, 4]).to_string(), "()()()(()())");
+        // Parenthesis strings are distinct and balanced
+        let mut it = Catalan::new(5);
+        let mut seen = ::std::collections::HashSet::new();
+        while let Some(t) = it.next() {
+            let s = catalan_parens(t).to_string();
+            let mut depth = 0i32;
+            for c in s.chars() {
+                depth += if c == '(' { 1 } else { -1 };
+                assert!(depth >= 0);
+            }
+            assert_eq!(depth, 0);
+            assert!(seen.insert(s));
+        }
+        assert_eq!(seen.len(), 14);
+        let p = ::permutation::Permutation::from_vec(vec![2, 0, 1, 3]).unwrap();
+        assert_eq!(p.cycle_notation().to_string(), "(0 2 1)(3)");
+    }
+
+    #[test]
+    fn necklaces() {
+        use necklace::*;
+        use group::Group;
+        use permutation::Permutation;
+        for n in 0..8 {
+            for k in 0..4 {
+                let mut it = Necklaces::new(n, k);
+                let mut count = 0;
+                let mut prev: Option<Vec<usize>> = None;
+                while let Some(w) = it.next() {
+                    // Smallest of its rotations, and in increasing order
+                    assert!((0..n).all(|r| {
+                        w.iter().le(w[r..].iter().chain(&w[..r]))
+                    }));
+                    assert!(prev.is_none_or(|p| { &p[..] < w }));
+                    prev = Some(w.to_vec());
+                    count += 1;
+                }
+                assert_eq!(count, count_necklaces(n, k));
+                assert_eq!(Necklaces::bracelets(n, k).count() as u128, count_bracelets(n, k));
+            }
+        }
+        // Bracelets of length 6 over 2 letters, by the dihedral group
+        let rotation = Permutation::from_cycles(6, &[vec![0, 1, 2, 3, 4, 5]]).unwrap();
+        let reflection = Permutation::from_cycles(6, &[vec![1, 5], vec![2, 4]]).unwrap();
+        let dihedral = Group::generated_by(6, &[rotation, reflection]);
+        assert_eq!(count_word_orbits(&dihedral, 2), 13);
+        assert_eq!(count_bracelets(6, 2), 13);
+    }
+
+    #[test]
+    fn canonical_words() {
+        use necklace::*;
+        assert_eq!(canonical_rotation(&[2, 0, 1, 0]), vec![0, 1, 0, 2]);
+        assert_eq!(canonical_rotation(&[1, 0, 1, 0]), vec![0, 1, 0, 1]);
+        assert_eq!(canonical_reflection(&[0, 0, 2, 1]), vec![0, 0, 1, 2]);
+        assert!(is_lyndon(&[0, 0, 1]) && is_lyndon(&[0]) && is_lyndon(&[0, 1, 0, 1, 1]));
+        assert!(!is_lyndon(&[0, 1, 0, 1]) && !is_lyndon(&[0, 1, 0]) && !is_lyndon::<u8>(&[]));
+        // Agree with the generators and the definitions
+        for n in 0..7 {
+            // Words over 3 letters in lexicographic order, as base 3 numbers
+            let words = (0..3usize.pow(n as u32)).map(|x| {
+                (0..n).rev().map(|i| { x / 3usize.pow(i as u32) % 3 }).collect::<Vec<usize>>()
+            }).collect::<Vec<_>>();
+            let necklaces = words.iter().filter(|w| { is_necklace(w) }).cloned().collect::<Vec<_>>();
+            assert_eq!(necklaces, Necklaces::new(n, 3).owned().collect::<Vec<_>>());
+            let bracelets = words.iter().filter(|w| { is_bracelet(w) }).cloned().collect::<Vec<_>>();
+            assert_eq!(bracelets, Necklaces::bracelets(n, 3).owned().collect::<Vec<_>>());
+            for w in &words {
+                let rotations = (0..n).map(|r| {
+                    w[r..].iter().chain(&w[..r]).cloned().collect::<Vec<usize>>()
+                }).collect::<Vec<_>>();
+                if n > 0 {
+                    assert_eq!(&canonical_rotation(w), rotations.iter().min().unwrap());
+                }
+                assert_eq!(is_lyndon(w), n > 0 && rotations[1..].iter().all(|r| { w < r }));
+            }
+        }
+    }
+
+    #[test]
+    fn dedup() {
+        let v = ['a', 'b', 'a', 'c', 'b'];
+        // Multinomial coefficient 5! / (2! 2! 1!)
+        let mut all = Dedup::new(index_permutations(5), &v).owned().collect::<Vec<_>>();
+        assert_eq!(all.len(), 30);
+        all.sort();
+        all.dedup();
+        assert_eq!(all.len(), 30);
+        // Subsequences and combinations, compared as sequences
+        let all = Dedup::new(IndexSubsequences::new(5), &v).owned().collect::<Vec<_>>();
+        let mut expected = v.subsequences().owned().collect::<Vec<_>>();
+        expected.sort();
+        expected.dedup();
+        assert_eq!(all.len(), expected.len());
+        let mut sorted = all.clone();
+        sorted.sort();
+        assert_eq!(sorted, expected);
+        let all = Dedup::new(IndexCombinations::new(3, 2), &v[..3]).owned().collect::<Vec<_>>();
+        assert_eq!(all, vec![vec!['a', 'b'], vec!['a', 'a'], vec!['b', 'a']]);
+        for k in 0..6 {
+            let all = Dedup::new(GrayCombinations::new(5, k), &v).owned().collect::<Vec<_>>();
+            let mut expected = v.combinations(k).owned().collect::<Vec<_>>();
+            expected.sort();
+            expected.dedup();
+            let mut sorted = all.clone();
+            sorted.sort();
+            assert_eq!(sorted, expected);
+        }
+    }
+
+    #[test]
+    fn testing_helpers() {
+        use testing::*;
+        assert_eq!(assert_ordered(IndexCombinations::new(6, 3), Order::Lex), 20);
+        assert_ordered(IndexCombinations::with_order(6, 3, Order::Colex), Order::Colex);
+        assert_ordered(IndexSubsequences::rev_lex(5), Order::RevLex);
+        assert_ordered(Compositions::new(6), Order::Lex);
+        assert_count(Catalan::new(6), 42);
+        assert_eq!(assert_distinct(index_permutations(5)), 120);
+        assert_distinct(GrayCombinations::new(7, 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "out of Colex order")]
+    fn testing_helpers_fail() {
+        ::testing::assert_ordered(IndexCombinations::new(6, 3), Order::Colex);
+    }
+
+    #[test]
+    fn write_lines() {
+        use output::{write_lines, Format};
+        let mut out = Vec::new();
+        assert_eq!(write_lines(IndexCombinations::new(4, 2), Format::Csv, &mut out).unwrap(), 6);
+        assert_eq!(String::from_utf8(out).unwrap(), "0,1\n0,2\n0,3\n1,2\n1,3\n2,3\n");
+        let mut out = Vec::new();
+        write_lines(IndexSubsequences::new(2), Format::Json, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "[]\n[0]\n[0,1]\n[1]\n");
+        let mut out = Vec::new();
+        write_lines(IndexCombinations::new(3, 1), Format::Bitstring(3), &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "100\n010\n001\n");
+    }
+
+    #[test]
+    fn hook_lengths() {
+        use partition::Partition;
+        use tableau::SemistandardTableaux;
+        let l = Partition::from_parts(vec![3, 2, 1]);
+        assert_eq!(l.hook_lengths(), vec![vec![5, 3, 1], vec![3, 1], vec![1]]);
+        assert_eq!(l.standard_tableaux_count(), 16);
+        // Standard tableaux are the semistandard ones with distinct entries
+        let mut ps = Partitions::new(6).as_partitions();
+        let mut total = 0;
+        while let Some(p) = ps.next() {
+            let mut it = SemistandardTableaux::new(p, 6);
+            let mut count = 0;
+            while let Some(t) = it.next() {
+                let mut entries = t.iter().flat_map(|r| { r.iter() }).collect::<Vec<_>>();
+                entries.sort();
+                entries.dedup();
+                if entries.len() == 6 { count += 1 }
+            }
+            assert_eq!(count, p.standard_tableaux_count());
+            total += count * count;
+        }
+        // Sum of squares: the number of permutations
+        assert_eq!(total, 720);
+        // The Catalan number C_10
+        assert_eq!(Partition::from_parts(vec![10, 10]).standard_tableaux_count(), 16796);
+    }
+
+    #[test]
+    fn rsk() {
+        use tableau::{rsk, rsk_inverse, Tableau};
+        use permutation::Permutation;
+        let p = Permutation::from_vec(vec![3, 0, 2, 4, 1]).unwrap();
+        let (ins, rec) = rsk(&p);
+        assert_eq!(ins.rows(), &[vec![0, 1, 4], vec![2], vec![3]][..]);
+        assert_eq!(rec.rows(), &[vec![0, 2, 3], vec![1], vec![4]][..]);
+        // A bijection onto pairs of standard tableaux of the same shape
+        let mut pairs = ::std::collections::HashSet::new();
+        let mut it = index_permutations(6).as_permutations();
+        while let Some(p) = it.next() {
+            let (ins, rec) = rsk(p);
+            assert!(ins.is_standard() && rec.is_standard());
+            assert_eq!(Tableau::from_rows(ins.rows().to_vec()).as_ref(), Some(&ins));
+            assert_eq!(ins.shape(), rec.shape());
+            assert_eq!(rsk_inverse(&ins, &rec).as_ref(), Some(p));
+            pairs.insert((ins, rec));
+        }
+        assert_eq!(pairs.len(), 720);
+        let t = Tableau::from_rows(vec![vec![0, 1]]).unwrap();
+        let u = Tableau::from_rows(vec![vec![0], vec![1]]).unwrap();
+        assert_eq!(rsk_inverse(&t, &u), None);
+        assert_eq!(Tableau::from_rows(vec![vec![1, 2], vec![1]]), None);
+    }
+
+    #[test]
+    fn inversion_tables() {
+        use permutation::Permutation;
+        // The example of Knuth
+        let p = Permutation::from_vec(vec![4, 8, 0, 7, 1, 5, 3, 6, 2]).unwrap();
+        assert_eq!(p.inversion_table(), vec![2, 3, 6, 4, 0, 2, 2, 1, 0]);
+        assert_eq!(Permutation::from_inversion_table(&[2, 3, 6, 4, 0, 2, 2, 1, 0]), Some(p));
+        assert_eq!(Permutation::from_inversion_table(&[0, 2]), None);
+        let mut it = InversionTables::new(5);
+        let mut seen = ::std::collections::HashSet::new();
+        let mut r = 0;
+        while let Some(p) = it.next() {
+            let p = Permutation::from_vec(p.to_vec()).unwrap();
+            assert_eq!(&p.inversion_table()[..], it.table());
+            assert_eq!(it.rank(), r);
+            let mut from_rank = InversionTables::from_rank(5, r);
+            assert_eq!(from_rank.next(), Some(p.as_slice()));
+            assert!(seen.insert(p));
+            r += 1;
+        }
+        assert_eq!(r, 120);
+    }
+
+    #[test]
+    fn series() {
+        use series::Series;
+        let p = Series::partitions(12);
+        let q = Series::distinct_partitions(12);
+        for n in 0..13 {
+            let mut it = Partitions::new(n);
+            let (mut all, mut distinct) = (0, 0);
+            while let Some(x) = it.next() {
+                all += 1;
+                if x.windows(2).all(|w| { w[0] > w[1] }) { distinct += 1 }
+            }
+            assert_eq!(p.coefficient(n), all);
+            assert_eq!(q.coefficient(n), distinct);
+            let c = Series::compositions_with_parts(12, &[1, 3]);
+            assert_eq!(c.coefficient(n), Compositions::with_parts(n, &[1, 3]).count() as u128);
+        }
+        // Euler: as many partitions into distinct parts as into odd parts
+        assert_eq!(Series::partitions_with_parts(12, &[1, 3, 5, 7, 9, 11]), q);
+        let s = Series::from_coefficients(vec![1, 1, 1, 1]);
+        assert_eq!(Series::one(3).over_one_minus(1), s);
+        assert_eq!(s.times(&s).coefficients(), &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn dominance() {
+        use partition::Partition;
+        let p = Partition::from_parts(vec![3, 1, 1, 1]);
+        let q = Partition::from_parts(vec![2, 2, 2]);
+        assert_eq!(p.dominance_cmp(&q), None);
+        assert_eq!(p.dominance_cmp(&Partition::from_parts(vec![2, 2, 1, 1])), Some(::std::cmp::Ordering::Greater));
+        assert_eq!(p.dominance_cmp(&p), Some(::std::cmp::Ordering::Equal));
+        assert!(!p.dominates(&Partition::from_parts(vec![1, 1])));
+        let mut it = Partitions::new(8).as_partitions();
+        while let Some(bound) = it.next() {
+            let mut below = Dominance::dominated_by(bound);
+            let mut above = Dominance::dominating(bound);
+            let mut all = Partitions::new(8).as_partitions();
+            let (mut b, mut a) = (0, 0);
+            while let Some(x) = all.next() {
+                if bound.dominates(x) {
+                    assert_eq!(below.next(), Some(x.parts()));
+                    b += 1;
+                }
+                if x.dominates(bound) {
+                    assert_eq!(above.next(), Some(x.parts()));
+                    a += 1;
+                }
+            }
+            assert_eq!(below.next(), None);
+            assert_eq!(above.next(), None);
+            assert_eq!((below.get(), above.get()), (None, None));
+            assert!(a > 0 && b > 0);
+        }
+    }
+
+    #[test]
+    fn partition_statistics() {
+        use partition::Partition;
+        let p = Partition::from_parts(vec![5, 4, 4, 2, 1, 1]);
+        assert_eq!(p.durfee_square(), 3);
+        assert_eq!(p.rank(), -1);
+        assert_eq!(p.ones(), 2);
+        assert_eq!(p.crank(), 1);
+        assert_eq!(Partition::from_parts(vec![4, 2]).crank(), 4);
+        assert_eq!(Partition::from_parts(vec![]).durfee_square(), 0);
+        // Dyson: the rank splits the partitions of 5k+4 into 5 equal classes
+        let mut classes = vec![0; 5];
+        let mut it = Partitions::new(9).as_partitions();
+        while let Some(p) = it.next() {
+            classes[p.rank().rem_euclid(5) as usize] += 1;
+        }
+        assert_eq!(classes, vec![6; 5]);
+        for r in -8..9 {
+            let mut it = Partitions::new(9).as_partitions();
+            let mut ranked = PartitionsOfRank::new(9, r);
+            while let Some(p) = it.next() {
+                if p.rank() == r { assert_eq!(ranked.next(), Some(p.parts())) }
+            }
+            assert_eq!(ranked.next(), None);
+        }
+    }
+
+    #[test]
+    fn patterns() {
+        use permutation::{contains_pattern, occurrences};
+        let p = [2, 0, 4, 1, 3];
+        assert!(contains_pattern(&p, &[1, 0, 2]));
+        assert!(!contains_pattern(&p, &[2, 1, 0]));
+        assert!(contains_pattern(&p, &[]));
+        let mut it = occurrences(&p, &[0, 2, 1]);
+        let mut found = Vec::new();
+        while let Some(x) = it.next() { found.push(x.to_vec()) }
+        assert_eq!(found, vec![vec![0, 2, 4], vec![1, 2, 3], vec![1, 2, 4]]);
+        assert_eq!(occurrences(&p, &[0]).count(), 5);
+        assert_eq!(occurrences(&p, &[]).count(), 1);
+        // The 231-avoiding permutations are counted by the Catalan numbers
+        let mut it = index_permutations(6);
+        let mut avoiding = 0;
+        while let Some(p) = it.next() {
+            if !contains_pattern(p, &[1, 2, 0]) { avoiding += 1 }
+        }
+        assert_eq!(avoiding, 132);
+    }
+
+    #[test]
+    fn longest_increasing_subsequences() {
+        use permutation::{lis_length, longest_increasing_subsequence, longest_increasing_subsequences};
+        let p = [3, 1, 4, 1, 5, 9, 2, 6];
+        assert_eq!(lis_length(&p), 4);
+        assert_eq!(longest_increasing_subsequence(&p), vec![1, 2, 4, 7]);
+        let mut it = longest_increasing_subsequences(&p);
+        let mut found = Vec::new();
+        while let Some(x) = it.next() { found.push(x.to_vec()) }
+        assert_eq!(found, vec![vec![0, 2, 4, 5], vec![0, 2, 4, 7], vec![1, 2, 4, 5], vec![1, 2, 4, 7]]);
+        assert_eq!(longest_increasing_subsequences::<usize>(&[]).count(), 1);
+        // The length is the first row of the RSK shape, and the witnesses are
+        // all the increasing subsequences of that length
+        let mut it = index_permutations(6);
+        while let Some(p) = it.next() {
+            let q = ::permutation::Permutation::from_vec(p.to_vec()).unwrap();
+            let len = lis_length(p);
+            assert_eq!(len, ::tableau::rsk(&q).0.shape().parts()[0]);
+            let count = IndexCombinations::new(6, len).owned().filter(|c| {
+                c.windows(2).all(|w| { p[w[0]] < p[w[1]] })
+            }).count();
+            assert_eq!(longest_increasing_subsequences(p).count(), count);
+        }
+    }
+
+    #[test]
+    fn cycle_index() {
+        use group::{CycleIndex, Group};
+        use partition::Partition;
+        use permutation::Permutation;
+        use series::Series;
+        let s3 = CycleIndex::symmetric(3);
+        assert_eq!(s3.order(), 6);
+        assert_eq!(s3.terms().map(|(l, &c)| { (l.parts().to_vec(), c) }).collect::<Vec<_>>(),
+                   vec![(vec![1, 1, 1], 1), (vec![2, 1], 3), (vec![3], 2)]);
+        let n = 6;
+        let rotation = Permutation::from_cycles(n, &[(0..n).collect()]).unwrap();
+        let reflection = Permutation::from_vec((0..n).map(|i| { (n - i) % n }).collect()).unwrap();
+        let g = Group::generated_by(n, &[rotation.clone(), reflection]);
+        assert_eq!(CycleIndex::of_group(&g), CycleIndex::dihedral(n));
+        assert_eq!(CycleIndex::of_group(&Group::generated_by(n, &[rotation])), CycleIndex::cyclic(n));
+        for n in 1..9 {
+            for k in 0..4 {
+                assert_eq!(CycleIndex::cyclic(n).count_colorings(k), ::necklace::count_necklaces(n, k));
+                assert_eq!(CycleIndex::dihedral(n).count_colorings(k), ::necklace::count_bracelets(n, k));
+            }
+            let total = CycleIndex::symmetric(n).terms().map(|(_, &c)| { c }).sum::<u128>();
+            assert_eq!(total, rank::factorial(n) as u128);
+        }
+        // Necklaces of 6 beads, 3 of them black
+        let black = |i: usize| { Series::one(6).times_one_plus(i) };
+        assert_eq!(CycleIndex::cyclic(6).substitute_series(black).coefficients(), &[1, 1, 3, 4, 3, 1, 1]);
+        // Multisets of 5 black or white elements, one per number of black ones
+        let multisets = CycleIndex::symmetric(5).substitute_series(black);
+        assert_eq!(multisets.coefficients(), &[1, 1, 1, 1, 1, 1, 0]);
+        assert_eq!(CycleIndex::symmetric(0).terms().next(), Some((&Partition::from_parts(vec![]), &1)));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn boltzmann() {
+        use boltzmann::*;
+        use rand::Rng;
+        // Binary trees counted by internal nodes
+        let trees = Grammar::new(vec![
+            Spec::Union(vec![Spec::Empty, Spec::Product(vec![Spec::Atom, Spec::Ref(0), Spec::Ref(0)])]),
+        ]);
+        let b = trees.values(0.2).unwrap()[0];
+        assert!((b - (1.0 - 0.2f64.sqrt()) / 0.4).abs() < 1e-9);
+        assert!(trees.values(0.3).is_none());
+        assert!((trees.singularity(0) - 0.25).abs() < 1e-3);
+        let sampler = Boltzmann::tuned(trees, 0, 100);
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let t = sampler.sample_sized(&mut rng, 90, 110);
+            assert!(90 <= t.size() && t.size() <= 110);
+        }
+        // Compositions, whose generating function (1-x)/(1-2x) has no
+        // singularity at which the expected size stays finite
+        let compositions = Grammar::new(vec![
+            Spec::Sequence(Box::new(Spec::Product(vec![Spec::Atom, Spec::Sequence(Box::new(Spec::Atom))]))),
+        ]);
+        let sampler = Boltzmann::tuned(compositions.clone(), 0, 50);
+        let e = compositions.expected_size(0, sampler.parameter()).unwrap();
+        assert!((e - 50.0).abs() < 0.5);
+        let c = rng.sample(&sampler);
+        match c {
+            Derivation::Sequence(parts) => assert!(parts.iter().all(|p| { p.size() > 0 })),
+            _ => panic!("Not a sequence"),
+        }
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn random_binary_trees() {
+        use rand::Rng;
+        use random::UniformBinaryTree;
+        use std::collections::HashMap;
+        let mut counts = HashMap::new();
+        let mut it = Catalan::new(5);
+        while let Some(t) = it.next() { counts.insert(t.to_vec(), 0); }
+        assert_eq!(counts.len(), 14);
+        let mut rng = rand::thread_rng();
+        for _ in 0..14000 {
+            *counts.get_mut(&rng.sample(UniformBinaryTree(5))).unwrap() += 1;
+        }
+        assert!(counts.values().all(|&c| { 800 < c && c < 1200 }));
+        assert_eq!(rng.sample(UniformBinaryTree(1)), Vec::<usize>::new());
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn random_set_partitions() {
+        use rand::Rng;
+        use random::UniformSetPartition;
+        use std::collections::HashMap;
+        let mut counts = HashMap::new();
+        let mut rng = rand::thread_rng();
+        for _ in 0..15000 {
+            let p = rng.sample(UniformSetPartition(4));
+            // Restricted growth strings
+            let mut max = 0;
+            for (i, &b) in p.iter().enumerate() {
+                assert!(if i == 0 { b == 0 } else { b <= max + 1 });
+                max = ::std::cmp::max(max, b);
+            }
+            *counts.entry(p).or_insert(0) += 1;
+        }
+        // The Bell number B_4
+        assert_eq!(counts.len(), 15);
+        assert!(counts.values().all(|&c| { 800 < c && c < 1200 }));
+        assert_eq!(rng.sample(UniformSetPartition(0)), Vec::<usize>::new());
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn random_derangements() {
+        use rand::Rng;
+        use random::UniformDerangement;
+        use std::collections::HashMap;
+        let mut counts = HashMap::new();
+        let mut it = ConstrainedPermutations::derangements(4);
+        while let Some(p) = it.next() { counts.insert(p.to_vec(), 0); }
+        assert_eq!(counts.len(), 9);
+        let mut rng = rand::thread_rng();
+        for _ in 0..9000 {
+            *counts.get_mut(rng.sample(UniformDerangement(4)).as_slice()).unwrap() += 1;
+        }
+        assert!(counts.values().all(|&c| { 800 < c && c < 1200 }));
+        for n in (0..20).filter(|&n| { n != 1 }) {
+            let p = rng.sample(UniformDerangement(n));
+            assert!((0..n).all(|i| { p[i] != i }));
+        }
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn random_latin_squares() {
+        use rand::Rng;
+        use random::UniformLatinSquare;
+        use std::collections::HashMap;
+        let mut counts = HashMap::new();
+        let mut rng = rand::thread_rng();
+        for _ in 0..12000 {
+            *counts.entry(rng.sample(UniformLatinSquare(3))).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 12);
+        assert!(counts.values().all(|&c| { 700 < c && c < 1300 }));
+        let n = 10;
+        let square = rng.sample(UniformLatinSquare(n));
+        for i in 0..n {
+            let mut row = square[i].clone();
+            let mut col = square.iter().map(|r| { r[i] }).collect::<Vec<_>>();
+            row.sort();
+            col.sort();
+            assert_eq!(row, (0..n).collect::<Vec<_>>());
+            assert_eq!(col, (0..n).collect::<Vec<_>>());
+        }
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn random_weighted_combinations() {
+        use random::RandomWeightedCombinations;
+        let weights = vec![1.0, 0.0, 2.0, 1.0];
+        let mut counts = [0; 4];
+        let it = RandomWeightedCombinations::new(weights, 1, rand::thread_rng());
+        for c in it.take(8000) {
+            assert_eq!(c.len(), 1);
+            counts[c[0]] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!(1800 < counts[0] && counts[0] < 2200);
+        assert!(3700 < counts[2] && counts[2] < 4300);
+        // Two out of three: {0, 3} has probability 1/4 * 1/3 + 1/4 * 1/3
+        let it = RandomWeightedCombinations::new(vec![1.0, 0.0, 2.0, 1.0], 2, rand::thread_rng());
+        let both = it.take(6000).filter(|c| {
+            assert!(c.len() == 2 && c[0] < c[1] && !c.contains(&1));
+            c == &vec![0, 3]
+        }).count();
+        assert!(800 < both && both < 1200);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn random_spanning_trees() {
+        use rand::Rng;
+        use random::UniformSpanningTree;
+        use graph::Graph;
+        use std::collections::HashMap;
+        // The spanning trees of K_4 minus an edge, by brute force: the sets
+        // of 3 edges connecting the 4 vertices
+        let edges = [(0, 1), (0, 2), (0, 3), (1, 2), (2, 3)];
+        let g = Graph::from_edges(4, &edges);
+        let mut counts = HashMap::new();
+        let mut it = IndexCombinations::new(edges.len(), 3);
+        while let Some(c) = it.next() {
+            let tree = c.iter().map(|&i| { edges[i] }).collect::<Vec<_>>();
+            let mut component = [0, 1, 2, 3];
+            for &(a, b) in &tree {
+                let (x, y) = (component[a], component[b]);
+                for v in component.iter_mut() { if *v == y { *v = x } }
+            }
+            if component.iter().all(|&v| { v == component[0] }) { counts.insert(tree, 0); }
+        }
+        assert_eq!(counts.len(), 8);
+        let mut rng = rand::thread_rng();
+        for _ in 0..8000 {
+            *counts.get_mut(&rng.sample(UniformSpanningTree(&g))).unwrap() += 1;
+        }
+        assert!(counts.values().all(|&c| { 800 < c && c < 1200 }));
+    }
+
+    #[test]
+    fn shuffled() {
+        use rank::{Shuffled, ShuffledRanks};
+        for &len in &[0, 1, 2, 5, 16, 17, 1000] {
+            let mut ranks = ShuffledRanks::new(len, 42).collect::<Vec<usize>>();
+            assert_eq!(ranks.len(), len);
+            ranks.sort();
+            assert_eq!(ranks, (0..len).collect::<Vec<usize>>());
+        }
+        assert_eq!(ShuffledRanks::new(100, 7).collect::<Vec<_>>(), ShuffledRanks::new(100, 7).collect::<Vec<_>>());
+        assert!(ShuffledRanks::new(100, 7).collect::<Vec<_>>() != ShuffledRanks::new(100, 8).collect::<Vec<_>>());
+        assert!(ShuffledRanks::new(100, 7).collect::<Vec<_>>() != (0..100).collect::<Vec<_>>());
+        let mut it = Shuffled::combinations(8, 3, 1);
+        let mut seen = ::std::collections::HashSet::new();
+        while let Some(c) = it.next() {
+            let c = c.to_vec();
+            assert_eq!(rank::combination_rank(8, &c), it.rank());
+            assert!(seen.insert(c));
+        }
+        assert_eq!(seen.len(), 56);
+        assert_eq!(Shuffled::subsequences(6, 1).count(), 64);
+        assert_eq!(Shuffled::permutations(5, 1).count(), 120);
+        // Few combinations, though choose(70, 35) does not fit
+        let mut it = Shuffled::combinations(70, 68, 1);
+        let mut seen = ::std::collections::HashSet::new();
+        while let Some(c) = it.next() {
+            assert!(c.len() == 68 && c.windows(2).all(|w| { w[0] < w[1] }) && c[67] < 70);
+            assert!(seen.insert(c.to_vec()));
+        }
+        assert_eq!(seen.len(), 2415);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot shuffle the combinations of 35 elements out of 70")]
+    fn shuffled_too_many_combinations() {
+        rank::Shuffled::combinations(70, 35, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot shuffle the subsequences of 64 elements")]
+    fn shuffled_too_many_subsequences() {
+        rank::Shuffled::subsequences(64, 1);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn stratified_sampling() {
+        use random::*;
+        use std::collections::HashMap;
+        let mut rng = rand::thread_rng();
+        let c = stratified_combinations(&mut rng, 6, 3);
+        assert_eq!(c.len(), 21);
+        for (i, x) in c.iter().enumerate() {
+            assert_eq!(x.len(), i / 3);
+        }
+        let p = stratified_partitions(&mut rng, 7, 2);
+        assert_eq!(p.len(), 14);
+        for (i, x) in p.iter().enumerate() {
+            assert_eq!((x.size(), x.len()), (7, 1 + i / 2));
+        }
+        assert_eq!(stratified_partitions(&mut rng, 0, 2).len(), 2);
+        // 8 1 1, 7 2 1, 6 3 1, 6 2 2, 5 4 1, 5 3 2, 4 4 2, 4 3 3
+        let d = UniformPartition::new(10, 3);
+        assert_eq!(d.count(), 8);
+        let mut counts = HashMap::new();
+        let samples = stratified(&mut rng, 3..4, 8000, |parts| { UniformPartition::new(10, parts) });
+        for x in samples { *counts.entry(x).or_insert(0) += 1 }
+        assert_eq!(counts.len(), 8);
+        assert!(counts.values().all(|&c| { 800 < c && c < 1200 }));
+    }
+
+    #[cfg(feature = "python")]
+    #[test]
+    fn python_module() {
+        use pyo3::prelude::*;
+        use pyo3::types::PyModule;
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let m = PyModule::new(py, "combinatorust").unwrap();
+            ::python::combinatorust(py, m).unwrap();
+            let run = |f: &str, n: usize| {
+                let it = m.getattr(f).unwrap().call1((n,)).unwrap();
+                it.iter().unwrap().map(|x| { x.unwrap().extract::<Vec<usize>>().unwrap() })
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(run("partitions", 4),
+                       vec![vec![4], vec![3, 1], vec![2, 2], vec![2, 1, 1], vec![1, 1, 1, 1]]);
+            assert_eq!(run("permutations", 3).len(), 6);
+            assert_eq!(run("catalan", 4).len(), 5);
+            assert!(m.getattr("combinations").unwrap().call1((2, 3)).is_err());
+        });
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_explore() {
+        use par::*;
+        let v = (0..12).collect::<Vec<usize>>();
+        let evens = explore(v.par_combinations(5), |c| {
+            (c.iter().sum::<usize>() % 2 == 0) as usize
+        }, |a, b| { a + b });
+        let mut c = IndexCombinations::new(12, 5);
+        let mut expected = 0;
+        while let Some(x) = c.next() {
+            if x.iter().sum::<usize>() % 2 == 0 { expected += 1 }
+        }
+        assert_eq!(evens, Some(expected));
+        // Concatenation is associative: the order is kept
+        let all = explore(v[..4].par_permutations(), |p| { vec![p] }, |mut a, b| { a.extend(b); a });
+        assert_eq!(all.unwrap().len(), 24);
+        let cancel = Cancellation::new();
+        let found = explore_until(v[..10].par_permutations(), &cancel, |p| {
+            if p[0] >= 5 { cancel.cancel() }
+            1usize
+        }, |a, b| { a + b });
+        assert!(cancel.is_cancelled());
+        assert!(found.unwrap() < rank::factorial(10));
+    }
+
+    #[test]
+    fn progress() {
+        use std::time::Duration;
+        let mut reports = Vec::new();
+        let total = rank::choose(20, 10) as u128;
+        let mut it = IndexCombinations::new(20, 10)
+            .progress(Some(total), |r| { reports.push(*r) })
+            .every(Duration::from_secs(0));
+        let mut count = 0;
+        while let Some(c) = it.next() {
+            assert_eq!(c.len(), 10);
+            count += 1;
+        }
+        assert_eq!(it.next(), None);
+        drop(it);
+        assert_eq!(count, total);
+        // Every 1024 items, and at the end
+        assert_eq!(reports.len() as u128, total / 1024 + 1);
+        let last = reports.last().unwrap();
+        assert_eq!((last.done, last.fraction()), (total, Some(1.0)));
+        assert_eq!(last.eta(), Some(Duration::from_secs(0)));
+        assert!(reports.windows(2).all(|w| { w[0].done < w[1].done }));
+    }
+
+    #[test]
+    fn persistent_iter() {
+        use output::PersistentIter;
+        let path = ::std::env::temp_dir().join(format!("combinatorust-{}.state", ::std::process::id()));
+        let _ = ::std::fs::remove_file(&path);
+        let resume = |r: u128| { IndexCombinations::from_rank(10, 4, r as usize) };
+        // Interrupted after 100 items, and then some more
+        let mut it = PersistentIter::open(&path, 16, resume).unwrap();
+        let mut seen = Vec::new();
+        for _ in 0..100 { seen.push(it.next().unwrap().to_vec()) }
+        assert_eq!(it.completed(), 99);
+        drop(it);
+        assert_eq!(::std::fs::read_to_string(&path).unwrap(), "96\n");
+        // The items after the last checkpoint are yielded again
+        let mut it = PersistentIter::open(&path, 16, resume).unwrap();
+        seen.truncate(96);
+        while let Some(c) = it.next() { seen.push(c.to_vec()) }
+        assert_eq!(seen, IndexCombinations::new(10, 4).owned().collect::<Vec<_>>());
+        drop(it);
+        assert_eq!(::std::fs::read_to_string(&path).unwrap(), "210\n");
+        ::std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn binomial_table_files() {
+        let t = rank::BinomialTable::new(30, 7);
+        let mut bytes = Vec::new();
+        t.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 24 + 8 * 31 * 8);
+        let u = rank::BinomialTable::read_from(&bytes[..]).unwrap();
+        assert_eq!((u.choose(30, 7), u.choose(12, 5)), (2035800, 792));
+        assert!(rank::BinomialTable::read_from(&bytes[..100]).is_err());
+        assert!(rank::BinomialTable::read_from(&b"not a table"[..]).is_err());
+    }
+
+    #[cfg(feature = "memmap2")]
+    #[test]
+    fn mapped_binomial_table() {
+        use rank::MappedBinomialTable;
+        let path = ::std::env::temp_dir().join(format!("combinatorust-{}.binom", ::std::process::id()));
+        let _ = ::std::fs::remove_file(&path);
+        let t = MappedBinomialTable::open_or_build(&path, 20, 6).unwrap();
+        let mut c = Vec::new();
+        for r in 0..rank::choose(20, 6) {
+            t.combination_unrank(20, 6, r, &mut c);
+            assert_eq!(t.combination_rank(20, &c), r);
+            assert_eq!(rank::combination_rank(20, &c), r);
+        }
+        // Reused when large enough, rebuilt otherwise
+        assert_eq!(MappedBinomialTable::open_or_build(&path, 10, 3).unwrap().choose(20, 6), 38760);
+        assert_eq!(MappedBinomialTable::open_or_build(&path, 40, 3).unwrap().choose(40, 3), 9880);
+        ::std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn cached() {
+        let mut cache = IndexCombinations::new(6, 3).cached();
+        assert_eq!(cache.item(4), Some(&[0, 2, 3][..]));
+        assert_eq!(cache.cached_len(), 5);
+        assert_eq!(cache.item(1), Some(&[0, 1, 3][..]));
+        let expected = IndexCombinations::new(6, 3).owned().collect::<Vec<_>>();
+        for _ in 0..2 {
+            assert_eq!(cache.iter().owned().collect::<Vec<_>>(), expected);
+        }
+        assert_eq!(cache.fill(), 20);
+        assert_eq!(cache.item(20), None);
+        assert!(!cache.is_truncated());
+        // 10 elements: the first 3 combinations
+        let mut cache = IndexCombinations::new(6, 3).cached().with_limit(10);
+        assert_eq!(cache.fill(), 3);
+        assert!(cache.is_truncated());
+        assert_eq!(cache.item(3), None);
+        assert_eq!(cache.iter().count(), 3);
+    }
+
+    #[test]
+    fn catalan_objects() {
+        use catalan::*;
+        use permutation::contains_pattern;
+        assert_eq!(DyckWord::from_code(&[0, 1, 2, 3, 3, 4]).unwrap().to_string(), "()()()(()())");
+        assert_eq!(DyckWord::from_parens("(()").map(|w| { w.to_code() }), None);
+        assert_eq!(BinaryTree::from_code(&[0, 2]), None);
+        assert_eq!(BinaryTree::from_code(&[]), Some(BinaryTree::Leaf));
+        let t: Triangulation = BinaryTree::from_code(&[0, 0]).unwrap().convert();
+        assert_eq!(t.triangles(), &[[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(t.diagonals(), vec![(0, 2)]);
+        assert_eq!(NoncrossingMatching::from_partners(vec![2, 3, 0, 1]), None);
+        // Every family is in bijection with the codes
+        let mut it = Catalan::new(6);
+        let mut seen = ::std::collections::HashSet::new();
+        while let Some(code) = it.next() {
+            let w = DyckWord::from_code(code).unwrap();
+            let b = BinaryTree::from_code(code).unwrap();
+            let t = Triangulation::from_code(code).unwrap();
+            let m = NoncrossingMatching::from_code(code).unwrap();
+            let p = Avoiding231::from_code(code).unwrap();
+            assert_eq!(w.to_code(), code);
+            assert_eq!(b.to_code(), code);
+            assert_eq!(t.to_code(), code);
+            assert_eq!(m.to_code(), code);
+            assert_eq!(p.to_code(), code);
+            assert_eq!(b.size(), 5);
+            assert_eq!(t.diagonals().len(), 4);
+            assert_eq!(NoncrossingMatching::from_partners(m.partners().to_vec()).as_ref(), Some(&m));
+            assert!(!contains_pattern(p.permutation().as_slice(), &[1, 2, 0]));
+            assert!(seen.insert(p.into_permutation()));
+        }
+        assert_eq!(seen.len(), 42);
+    }
+
+    #[test]
+    fn views() {
+        use view::{SubsetMask, PermutationRef};
+        let src = ['a', 'b', 'c', 'd', 'e'];
+        let mut it = src.lex_combinations(2);
+        it.next();
+        it.next();
+        let c = it.current_combination().unwrap();
+        assert_eq!(&*c, &['a', 'c']);
+        assert_eq!(c.as_indices(), &[0, 2]);
+        assert_eq!(c.complement(), vec![1, 3, 4]);
+        assert_eq!(c.complement_items(&src), vec!['b', 'd', 'e']);
+        assert_eq!(c.to_mask().to_string(), "10100");
+        let mut it = IndexCombinations::new(4, 2);
+        while let Some(x) = it.next() {
+            let x = x.to_vec();
+            let c = it.current_combination();
+            assert_eq!(&*c, &x[..]);
+            assert_eq!(c.complement().len(), 2);
+            assert!(x.iter().all(|&i| { c.contains_index(i) }));
+        }
+        let m = SubsetMask::from_indices(&[1, 2], 4);
+        assert_eq!(*m, 0b0110);
+        assert_eq!(m.complement().as_indices().collect::<Vec<usize>>(), vec![0, 3]);
+        assert_eq!(m.apply(&src[..4]), vec!['b', 'c']);
+        assert!(m.contains(2) && !m.contains(3) && !m.contains(10));
+        assert_eq!(m.len(), 2);
+        let p = PermutationRef::new(&['c', 'a', 'd', 'b'][..]);
+        assert_eq!(p.pattern().as_slice(), &[2, 0, 3, 1]);
+        assert_eq!(p.inversions(), 3);
+        assert_eq!(p.len(), 4);
+        let q = PermutationRef::from(&[2, 0, 1][..]);
+        assert_eq!(q.apply(&['x', 'y', 'z']), vec!['z', 'x', 'y']);
+        assert_eq!(q.to_permutation().map(|p| { p.inversion_table().len() }), Some(3));
+        assert_eq!(PermutationRef::new(&[0, 0][..]).to_permutation(), None);
+    }
+
+    #[test]
+    fn big_ranks() {
+        use rank::*;
+        assert_eq!(choose_as::<u128>(100, 50), 100891344545564193334812497256);
+        assert_eq!(factorial_as::<u128>(25), 15511210043330985984000000);
+        assert_eq!(choose_as::<u64>(10, 3), 120);
+        // Agrees with the usize functions
+        let mut it = IndexCombinations::new(7, 3);
+        while let Some(c) = it.next() {
+            let r = combination_rank(7, c);
+            assert_eq!(combination_rank_as::<u128>(7, c), r as u128);
+            let mut dest = Vec::new();
+            combination_unrank_as(7, 3, &(r as u64), &mut dest);
+            assert_eq!(dest, c);
+        }
+        for r in 0..32 {
+            let mut s = Vec::new();
+            subsequence_unrank_as(5, &(r as u128), &mut s);
+            assert_eq!(subsequence_rank(5, &s), r);
+            assert_eq!(subsequence_rank_as::<u128>(5, &s), r as u128);
+        }
+        let mut p = Vec::new();
+        for r in 0..24 {
+            permutation_unrank_as(4, &(r as u128), &mut p);
+            assert_eq!(permutation_rank(&p), r);
+        }
+        // Beyond u64
+        let c = [10, 30, 50, 70, 90, 95, 96, 97, 98, 99];
+        let r = combination_rank_as::<u128>(100, &c);
+        let mut dest = Vec::new();
+        combination_unrank_as(100, 10, &r, &mut dest);
+        assert_eq!(dest, c);
+        let last = (0..25).rev().collect::<Vec<usize>>();
+        assert_eq!(permutation_rank_as::<u128>(&last), factorial_as::<u128>(25) - 1);
+        permutation_unrank_as(25, &(factorial_as::<u128>(25) - 1), &mut p);
+        assert_eq!(p, last);
+        let s = (0..100).collect::<Vec<usize>>();
+        assert_eq!(subsequence_rank_as::<u128>(100, &s), 100);
+        // Small ranks of long sequences
+        assert_eq!(subsequence_rank(64, &[0]), 1);
+        assert_eq!(subsequence_rank(200, &[0, 1]), 2);
+        subsequence_unrank(200, 2, &mut p);
+        assert_eq!(p, [0, 1]);
+        #[cfg(feature = "num-bigint")]
+        {
+            use num_bigint::BigUint;
+            let r = combination_rank_as::<BigUint>(100, &c);
+            assert_eq!(r.to_string(), combination_rank_as::<u128>(100, &c).to_string());
+            combination_unrank_as(100, 10, &r, &mut dest);
+            assert_eq!(dest, c);
+        }
+    }
+
+    exhaustive_test!(exhaustive_catalan, Catalan::new(6), |t| t[0] == 0, count: 42);
+
+    exhaustive_test!(
+        exhaustive_permutations,
+        index_permutations(4),
+        |p| { assert!(::permutation::is_permutation(p)) }
+    );
+
+    #[test]
+    fn assert_all_counterexample() {
+        use testing::assert_all;
+        assert_eq!(assert_all(IndexCombinations::new(5, 2), |c| { c[0] < c[1] }), 10);
+        let failure = ::std::panic::catch_unwind(|| {
+            assert_all(IndexCombinations::new(5, 2), |c| -> Result<(), &str> {
+                if c[1] < 4 { Ok(()) } else { Err("too large") }
+            })
+        }).unwrap_err();
+        assert_eq!(failure.downcast_ref::<String>().map(|s| { &s[..] }),
+                   Some("Counterexample at rank 3: [0, 4] (\"too large\")"));
+    }
+
+    #[test]
+    fn errors() {
+        use error::Error;
+        use permutation::Permutation;
+        use catalan::DyckWord;
+        assert_eq!(IndexCombinations::try_new(3, 4).err(), Some(Error::InvalidK { n: 3, k: 4 }));
+        assert_eq!(IndexCombinations::try_from_rank(5, 2, 10).err(), Some(Error::RankOutOfRange));
+        let mut it = IndexCombinations::try_from_rank(5, 2, 9).unwrap();
+        assert_eq!(it.next(), Some(&[3, 4][..]));
+        assert!(GrayCombinations::try_new(2, 3).is_err());
+        assert_eq!(bits::MaskCombinations::try_new(65, 1).err(), Some(Error::TooLarge { n: 65, max: 64 }));
+        assert_eq!(InversionTables::try_from_rank(3, 6).err(), Some(Error::RankOutOfRange));
+        assert!(IndexSubsequences::try_from_rank(3, 7).is_ok());
+        assert_eq!(IndexSubsequences::try_from_rank(3, 8).err(), Some(Error::RankOutOfRange));
+        assert_eq!(rank::try_choose(10, 3), Ok(120));
+        assert_eq!(rank::try_choose(100, 50), Err(Error::Overflow));
+        assert_eq!(rank::try_factorial(30), Err(Error::Overflow));
+        let mut dest = Vec::new();
+        assert_eq!(rank::try_permutation_unrank(3, 6, &mut dest), Err(Error::RankOutOfRange));
+        // All ranks are valid when n! overflows
+        assert_eq!(rank::try_permutation_unrank(25, 0, &mut dest), Ok(()));
+        assert_eq!(dest, (0..25).collect::<Vec<usize>>());
+        assert_eq!(rank::try_permutation_unrank(40, 0, &mut dest), Err(Error::Overflow));
+        assert_eq!(rank::try_subsequence_unrank(100, 1, &mut dest), Ok(()));
+        assert_eq!(dest, vec![0]);
+        assert_eq!(rank::try_combination_unrank(2, 3, 0, &mut dest), Err(Error::InvalidK { n: 2, k: 3 }));
+        assert_eq!("[2 0 1]".parse::<Permutation>().map(|p| { p.into_vec() }), Ok(vec![2, 0, 1]));
+        let e = "[2 0 0]".parse::<Permutation>().unwrap_err();
+        assert_eq!(e.to_string(), "Not a permutation: \"[2 0 0]\"");
+        assert!("(()".parse::<DyckWord>().is_err());
+        let e: Box<dyn (::std::error::Error)> = Box::new(Error::InvalidK { n: 2, k: 3 });
+        assert_eq!(e.to_string(), "Cannot choose 3 elements out of 2");
+    }
+
+    #[test]
+    fn antichains() {
+        use poset::Antichains;
+        use testing::{assert_count, assert_ordered};
+        for (n, &d) in [2, 3, 6, 20, 168, 7581].iter().enumerate() {
+            assert_count(Antichains::new(n), d);
+        }
+        assert_ordered(Antichains::new(4), Order::Lex);
+        // The truth tables are exactly the monotone functions
+        let mut it = Antichains::new(3);
+        let mut functions = ::std::collections::HashSet::new();
+        while let Some(a) = it.next() {
+            for (i, &s) in a.iter().enumerate() {
+                assert!(a[..i].iter().all(|&t| { s & t != t && s & t != s }));
+            }
+            functions.insert(it.current_function());
+        }
+        let monotone = (0..256u64).filter(|&f| {
+            (0..8).all(|x| { (0..8).all(|y| { x & y != x || f >> x & 1 <= f >> y & 1 }) })
+        }).count();
+        assert_eq!(functions.len(), monotone);
+    }
+
+    #[test]
+    fn order_ideals() {
+        use poset::OrderIdeals;
+        use testing::{assert_count, assert_distinct};
+        assert_count(OrderIdeals::new(4, &[(0, 1), (1, 2), (2, 3)]), 5);
+        assert_count(OrderIdeals::new(4, &[]), 16);
+        assert_count(OrderIdeals::new(2, &[(0, 1), (1, 0)]), 0);
+        // The downsets of the Boolean lattice of subsets of 0..3, given by
+        // its covers, are the complements of the upsets, one per antichain
+        let mut covers = Vec::new();
+        for x in 0..8usize {
+            for i in 0..3 {
+                if x & 1 << i == 0 { covers.push((x, x | 1 << i)) }
+            }
+        }
+        assert_distinct(OrderIdeals::new(8, &covers));
+        assert_count(OrderIdeals::new(8, &covers), 20);
+        let pairs = [(3, 0), (3, 1), (0, 2), (1, 2)];
+        let mut it = OrderIdeals::new(4, &pairs);
+        let mut n = 0;
+        while let Some(ideal) = it.next() {
+            for &(a, b) in &pairs {
+                assert!(!ideal.contains(&b) || ideal.contains(&a));
+            }
+            n += 1;
+        }
+        assert_eq!(n, 6);
+    }
+
+    #[test]
+    fn polytope_faces() {
+        use polytope::*;
+        use testing::{assert_count, assert_distinct, assert_ordered};
+        assert_count(CubeFaces::new(4), 81);
+        assert_distinct(CubeFaces::new(4));
+        assert_ordered(CubeFaces::new(4), Order::Lex);
+        // The f-vector of the cube
+        for (d, &f) in [8, 12, 6, 1, 0].iter().enumerate() {
+            let mut it = CubeFaces::of_dimension(3, d);
+            let mut count = 0;
+            while let Some(face) = it.next() {
+                assert_eq!(cube_face_dimension(face), d);
+                count += 1;
+            }
+            assert_eq!(count, f);
+        }
+        let edges: Vec<String> = CubeFaces::of_dimension(2, 1).owned().map(|e| {
+            e.iter().map(|c| { c.to_string() }).collect()
+        }).collect();
+        assert_eq!(edges, vec!["0*", "1*", "*0", "*1"]);
+        let mut it = CubeFaces::new(0);
+        assert_eq!(it.next(), Some(&[][..]));
+        assert_eq!(it.next(), None);
+        assert_count(SimplexFaces::new(4), 31);
+        assert_distinct(SimplexFaces::new(4));
+        for (d, &f) in [4, 6, 4, 1, 0].iter().enumerate() {
+            assert_count(SimplexFaces::of_dimension(3, d), f);
+        }
+        let faces: Vec<Vec<usize>> = SimplexFaces::new(2).owned().collect();
+        assert_eq!(faces, vec![vec![0], vec![1], vec![2], vec![0, 1], vec![0, 2], vec![1, 2], vec![0, 1, 2]]);
+    }
+
+    #[test]
+    fn binary_matrices() {
+        use matrix::BinaryMatrices;
+        use testing::{assert_count, assert_ordered};
+        assert_count(BinaryMatrices::new(0, 3), 1);
+        assert_count(BinaryMatrices::new(1, 3), 4);
+        assert_count(BinaryMatrices::new(2, 2), 7);
+        assert_count(BinaryMatrices::new(2, 3), 13);
+        assert_count(BinaryMatrices::new(3, 2), 13);
+        assert_count(BinaryMatrices::new(3, 3), 36);
+        assert_count(BinaryMatrices::new(4, 4), 317);
+        assert_ordered(BinaryMatrices::new(3, 3), Order::Lex);
+        // Every 2 × 2 matrix is equivalent to exactly one representative
+        let canonical = BinaryMatrices::new(2, 2).owned().collect::<Vec<Vec<u64>>>();
+        let swap = |r: u64| { (r & 1) << 1 | r >> 1 };
+        for a in 0..4u64 {
+            for b in 0..4u64 {
+                let found = canonical.iter().filter(|c| {
+                    [[a, b], [b, a], [swap(a), swap(b)], [swap(b), swap(a)]].iter()
+                        .any(|x| { x[..] == c[..] })
+                }).count();
+                assert_eq!(found, 1);
+            }
+        }
+    }
+
+    #[test]
+    fn lattice_points() {
+        use lattice::{LatticePoints, LinearConstraint};
+        use testing::{assert_count, assert_ordered};
+        assert_count(LatticePoints::new(&[0..2, 0..3, 0..4], vec![]), 24);
+        let sum = LinearConstraint::at_most(vec![1, 1, 1], 2);
+        assert_count(LatticePoints::new(&[0..3, 0..3, 0..3], vec![sum]), 10);
+        assert_count(LatticePoints::new(&[0..3, 0..0], vec![]), 0);
+        assert_count(LatticePoints::new(&[], vec![]), 1);
+        assert_count(LatticePoints::new(&[], vec![LinearConstraint::at_most(vec![], -1)]), 0);
+        // Same as filtering the box
+        let ranges = [-2..3, 0..4, -1..2];
+        let constraints = vec![
+            LinearConstraint::at_most(vec![1, -2, 1], 1),
+            LinearConstraint::at_least(vec![1, 1, 3], 0),
+            LinearConstraint::at_most(vec![0, 3, -1], 5),
+        ];
+        let mut expected = Vec::new();
+        for x in ranges[0].clone() {
+            for y in ranges[1].clone() {
+                for z in ranges[2].clone() {
+                    let p = [x, y, z];
+                    if constraints.iter().all(|c| { c.holds(&p) }) { expected.push(p.to_vec()) }
+                }
+            }
+        }
+        let found = LatticePoints::new(&ranges, constraints.clone()).owned().collect::<Vec<Vec<i64>>>();
+        assert_eq!(found, expected);
+        assert_ordered(LatticePoints::new(&ranges, constraints), Order::Lex);
+    }
+
+    #[test]
+    fn vector_compositions() {
+        use testing::{assert_count, assert_distinct};
+        assert_count(VectorCompositions::new(&[4]), 8);
+        assert_count(VectorCompositions::new(&[1, 1]), 3);
+        // Ordered set partitions (Fubini numbers)
+        assert_count(VectorCompositions::new(&[1, 1, 1]), 13);
+        assert_count(VectorCompositions::new(&[1, 1, 1, 1]), 75);
+        assert_count(VectorCompositions::new(&[]), 1);
+        assert_distinct(VectorCompositions::new(&[2, 1, 2]));
+        // Lattice paths
+        let steps = [vec![1, 0], vec![0, 1]];
+        assert_count(VectorCompositions::with_parts(&[3, 2], &steps), 10);
+        assert_count(VectorCompositions::with_parts(&[3, 2], &[vec![2, 0], vec![0, 2]]), 0);
+        let mut it = VectorCompositions::with_parts(&[2, 2], &[vec![1, 1], vec![2, 0], vec![0, 2]]);
+        let mut found = Vec::new();
+        while let Some(c) = it.next() {
+            let sum = c.iter().fold(vec![0, 0], |s, p| { vec![s[0] + p[0], s[1] + p[1]] });
+            assert_eq!(sum, vec![2, 2]);
+            found.push(c.to_vec());
+        }
+        assert_eq!(found, vec![
+            vec![vec![0, 2], vec![2, 0]],
+            vec![vec![1, 1], vec![1, 1]],
+            vec![vec![2, 0], vec![0, 2]],
+        ]);
+    }
+
+    #[test]
+    fn fixed_sum_tuples() {
+        use lattice::{FixedSumTuples, LatticePoints, LinearConstraint};
+        use testing::{assert_count, assert_ordered};
+        // Weak compositions of 4 into 3 parts
+        assert_count(FixedSumTuples::new(&[0..5, 0..5, 0..5], 4), 15);
+        assert_count(FixedSumTuples::new(&[0..2, 0..2], 3), 0);
+        assert_count(FixedSumTuples::new(&[0..2, 3..3], 1), 0);
+        assert_count(FixedSumTuples::new(&[], 0), 1);
+        assert_count(FixedSumTuples::new(&[], 1), 0);
+        assert_count(FixedSumTuples::new(&[-3..4, 0..1], 2), 1);
+        // Same as the points with the sum constrained both ways
+        let ranges = [-1..3, 0..4, 2..5, -2..1];
+        for s in -2..11 {
+            let points = LatticePoints::new(&ranges, vec![
+                LinearConstraint::at_most(vec![1; 4], s),
+                LinearConstraint::at_least(vec![1; 4], s),
+            ]).owned().collect::<Vec<Vec<i64>>>();
+            let tuples = FixedSumTuples::new(&ranges, s).owned().collect::<Vec<Vec<i64>>>();
+            assert_eq!(tuples, points);
+        }
+        assert_ordered(FixedSumTuples::new(&ranges, 4), Order::Lex);
+    }
+
+    #[test]
+    fn signed_permutations() {
+        use permutation::{Permutation, SignedPermutation, SignedPermutations};
+        use testing::{assert_count, assert_distinct};
+        assert_count(SignedPermutations::new(0), 1);
+        assert_count(SignedPermutations::new(3), 48);
+        assert_distinct(SignedPermutations::new(3));
+        let p = SignedPermutation::new(Permutation::from_vec(vec![2, 0, 1]).unwrap(),
+                                       vec![false, true, false]).unwrap();
+        assert_eq!(p.to_string(), "[2 -0 1]");
+        assert_eq!(p.apply(&[1, 2, 3]), vec![3, -1, 2]);
+        assert_eq!(p.apply_index(1), (0, true));
+        assert_eq!(SignedPermutation::new(Permutation::identity(2), vec![true]), None);
+        // Group laws
+        let all = SignedPermutations::new(3).owned().collect::<Vec<SignedPermutation>>();
+        let id = SignedPermutation::identity(3);
+        let v = [10, 20, 30];
+        for a in &all {
+            assert_eq!(a.compose(&a.inverse()), id);
+            assert_eq!(a.inverse().compose(a), id);
+            for b in &all {
+                let ab = a.compose(b);
+                assert!(all.contains(&ab));
+                // Applying a ∘ b is applying a, then b
+                assert_eq!(ab.apply(&v), b.apply(&a.apply(&v)));
+            }
+        }
+    }
+
+    #[test]
+    fn circular_permutations() {
+        use necklace::{CircularPermutations, canonical_rotation, canonical_reflection};
+        use iter::PermutationsIterator;
+        assert_eq!(CircularPermutations::new(&[0u8; 0]).count(), 1);
+        assert_eq!(CircularPermutations::new(&[1, 2, 3, 4, 5]).count(), 24);
+        assert_eq!(CircularPermutations::up_to_reflection(&[1, 2, 3, 4, 5]).count(), 12);
+        assert_eq!(CircularPermutations::new(&['a', 'b', 'c']).owned().collect::<Vec<_>>(),
+                   vec![vec!['a', 'b', 'c'], vec!['a', 'c', 'b']]);
+        // Against the classes of all permutations, with repeated elements
+        for src in &[vec![0, 1, 2, 3, 4], vec![0, 0, 1, 1, 2, 2], vec![3, 1, 3, 1, 3, 1], vec![2, 2, 2]] {
+            let mut rotations = src.permutations_iter().owned()
+                .map(|w| { canonical_rotation(&w) }).collect::<Vec<_>>();
+            rotations.sort();
+            rotations.dedup();
+            assert_eq!(CircularPermutations::new(src).owned().collect::<Vec<_>>(), rotations);
+            let mut reflections = rotations.iter()
+                .map(|w| { canonical_reflection(w) }).collect::<Vec<_>>();
+            reflections.sort();
+            reflections.dedup();
+            assert_eq!(CircularPermutations::up_to_reflection(src).owned().collect::<Vec<_>>(), reflections);
+        }
+    }
+
+    #[test]
+    fn snapshots() {
+        use iter::Snapshot;
+        // Fork at every position, run to the end, and come back
+        fn check<I>(mut it: I) where
+            I: StreamingIterator<Item = [char]> + Snapshot
+        {
+            let all = {
+                let mut all = Vec::new();
+                let state = it.snapshot();
+                while let Some(x) = it.next() { all.push(x.to_vec()) }
+                it.restore(&state);
+                all
+            };
+            for x in &all {
+                assert_eq!(it.next().map(|y| { y.to_vec() }).as_ref(), Some(x));
+                let state = it.snapshot();
+                while it.next().is_some() {}
+                assert_eq!(it.get(), None);
+                it.restore(&state);
+                assert_eq!(it.get().map(|y| { y.to_vec() }).as_ref(), Some(x));
+            }
+            assert_eq!(it.next(), None);
+        }
+        let v = ['a', 'b', 'c', 'd', 'e'];
+        check(v.lex_combinations(2));
+        check(v.gray_combinations(3));
+        check(v.subsequences());
+        check(v.permutations_iter());
+        check(::necklace::CircularPermutations::new(&['a', 'a', 'b', 'b', 'c']));
+        let mut it = index_permutations(4);
+        it.next();
+        let state = it.snapshot();
+        let rest = it.clone().owned().collect::<Vec<_>>();
+        it.next();
+        it.restore(&state);
+        assert_eq!(it.owned().collect::<Vec<_>>(), rest);
+    }
+
+    #[test]
+    fn size_hints() {
+        // Check against the remaining count at every step
+        fn check<I: StreamingIterator + Clone>(mut it: I) {
+            loop {
+                let mut rest = it.clone();
+                let mut remaining = 0;
+                while rest.next().is_some() { remaining += 1 }
+                assert_eq!(it.size_hint(), (remaining, Some(remaining)));
+                if it.next().is_none() { break }
+            }
+        }
+        for n in 0..6 {
+            check(IndexSubsequences::new(n));
+            check(IndexSubsequences::rev_lex(n));
+            check(index_permutations(n));
+            check(Catalan::new(n + 1));
+        }
+        for n in 6..10 {
+            check(Catalan::new(n + 1));
+        }
+        check(IndexSubsequences::from_rank(5, 12));
+        let v = [1, 2, 3, 4];
+        check(v.subsequences());
+        check(v.permutations_iter());
+        assert_eq!(v.permutations_iter().owned().size_hint(), (24, Some(24)));
+        assert_eq!(Catalan::new(8).size_hint(), (429, Some(429)));
+        assert_eq!(Catalan::new(200).size_hint(), (usize::MAX, None));
+        assert_eq!(index_permutations(40).size_hint(), (usize::MAX, None));
+        let mut total = None;
+        v.subsequences().progress(None, |r| { total = r.total }).count();
+        assert_eq!(total, Some(16));
+    }
+
+    #[test]
+    fn last_and_count() {
+        // Against stepping through the items, at every position
+        fn check<I>(mut it: I) where
+            I: StreamingIterator<Item = [usize]> + Clone
+        {
+            loop {
+                let (mut rest, mut last, mut count) = (it.clone(), None, 0);
+                while let Some(x) = rest.next() { last = Some(x.to_vec()); count += 1 }
+                assert_eq!(it.clone().count(), count);
+                assert_eq!(it.clone().last(), last);
+                if it.next().is_none() { break }
+            }
+        }
+        for n in 0..6 {
+            for k in 0..(n + 1) {
+                check(IndexCombinations::new(n, k));
+                check(IndexCombinations::with_order(n, k, Order::Colex));
+                check(IndexCombinations::rev_lex(n, k));
+                check(GrayCombinations::new(n, k));
+            }
+            check(IndexSubsequences::new(n));
+            check(IndexSubsequences::rev_lex(n));
+            check(index_permutations(n));
+            check(Catalan::new(n + 1));
+        }
+        check(Catalan::new(9));
+        assert_eq!(Catalan::new(9).last(), Some(vec![0, 0, 2, 2, 4, 5, 6, 6]));
+        let v = ['a', 'b', 'c', 'd', 'e'];
+        assert_eq!(v.combinations(3).last(), Some(vec!['a', 'b', 'e']));
+        assert_eq!(v.lex_combinations(3).last(), Some(vec!['c', 'd', 'e']));
+        assert_eq!(v.subsequences().last(), Some(vec!['e']));
+        assert_eq!(v.permutations_iter().last(), Some(vec!['b', 'a', 'c', 'd', 'e']));
+        assert_eq!(index_permutations(3).as_permutations().last().unwrap().as_slice(), &[1, 0, 2]);
+        assert_eq!(v.permutations_iter().owned().last(), Some(vec!['b', 'a', 'c', 'd', 'e']));
+        // Without stepping through the 155117520 combinations
+        assert_eq!(IndexCombinations::new(30, 15).count(), rank::choose(30, 15));
+        assert_eq!(IndexCombinations::new(30, 15).last(), Some((15..30).collect()));
+        let mut it = v.permutations_iter();
+        it.next();
+        it.next();
+        assert_eq!(it.owned().count(), 118);
+    }
+
+    #[test]
+    fn advance_by() {
+        // Against stepping through the items, from every position
+        fn check<I>(mut it: I) where
+            I: StreamingIterator + Clone,
+            I::Item: PartialEq + ::std::fmt::Debug
+        {
+            let mut len = 0;
+            let mut rest = it.clone();
+            while rest.next().is_some() { len += 1 }
+            for _ in 0..(len + 1) {
+                for m in 0..(len + 2) {
+                    let (mut skipped, mut stepped) = (it.clone(), it.clone());
+                    let mut expected = Ok(());
+                    for k in 0..m {
+                        if stepped.next().is_none() { expected = Err(k); break }
+                    }
+                    assert_eq!(skipped.advance_by(m), expected);
+                    loop {
+                        let (x, y) = (skipped.next(), stepped.next());
+                        assert_eq!(x, y);
+                        if x.is_none() { break }
+                    }
+                }
+                it.next();
+            }
+        }
+        for n in 0..6 {
+            for k in 0..(n + 1) {
+                check(IndexCombinations::new(n, k));
+                check(IndexCombinations::with_order(n, k, Order::Colex));
+                check(IndexCombinations::rev_lex(n, k));
+                check(GrayCombinations::new(n, k));
+            }
+            check(IndexSubsequences::new(n));
+            check(IndexSubsequences::rev_lex(n));
+            check(index_permutations(n));
+        }
+        let v = ['a', 'b', 'c', 'd', 'e'];
+        check(v.lex_combinations(2));
+        check(v.gray_combinations(3));
+        check(v.subsequences());
+        check(v.permutations_iter());
+        check(index_permutations(4).as_permutations());
+        // Sampling
+        let sample = v.permutations_iter().step_by(50).owned().collect::<Vec<_>>();
+        let all = v.permutations_iter().owned().collect::<Vec<_>>();
+        assert_eq!(sample, all.iter().step_by(50).cloned().collect::<Vec<_>>());
+        assert_eq!(v.permutations_iter().owned().step_by(50).collect::<Vec<_>>(), sample);
+        assert_eq!(v.permutations_iter().step_by(50).size_hint(), (3, Some(3)));
+        let mut it = IndexCombinations::new(40, 20);
+        assert_eq!(it.nth(rank::choose(40, 20) - 1), Some(&(20..40).collect::<Vec<_>>()[..]));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn lex_permutations() {
+        for src in &[vec![], vec![1], vec![2, 1, 3, 4], vec![1, 2, 1, 2, 3]] {
+            let mut all = src.permutations_iter().owned().collect::<Vec<_>>();
+            all.sort();
+            all.dedup();
+            assert_eq!(src.lex_permutations().owned().collect::<Vec<_>>(), all);
+            let mut rev = all.clone();
+            rev.reverse();
+            assert_eq!(src.lex_permutations().owned().rev().collect::<Vec<_>>(), rev);
+            for p in &all {
+                let mut q = p.clone();
+                if prev_permutation(&mut q) {
+                    next_permutation(&mut q);
+                    assert_eq!(&q, p);
+                }
+            }
+            // Meeting in the middle, from every split and with alternating
+            // ends
+            for split in 0..(all.len() + 1) {
+                let mut it = src.lex_permutations();
+                let mut front = Vec::new();
+                let mut back = Vec::new();
+                for _ in 0..split { front.push(it.next().unwrap().to_vec()) }
+                assert_eq!(it.get().map(|x| { x.to_vec() }), front.last().cloned());
+                while let Some(x) = it.next_back() { back.push(x.to_vec()) }
+                assert_eq!(it.next(), None);
+                back.reverse();
+                front.extend(back);
+                assert_eq!(front, all);
+            }
+            let mut it = src.lex_permutations();
+            let (mut front, mut back) = (Vec::new(), Vec::new());
+            while let Some(x) = it.next() {
+                front.push(x.to_vec());
+                match it.next_back() { Some(x) => back.push(x.to_vec()), None => break }
+            }
+            back.reverse();
+            front.extend(back);
+            assert_eq!(front, all);
+        }
+    }
+}
@@ -0,0 +1,573 @@
+//! Arrays with combinatorial constraints
+//!
+//! The arrays are yielded as their rows.
+
+use std::*;
+use bits::full_mask;
+use iter::{StreamingIterator, Snapshot, index_permutations};
+
+/// An iterator over Latin squares of order `n`: `n × n` arrays of entries
+/// in `0..n` where each entry appears once in every row and column.
+///
+/// The squares are enumerated in lexicographic order of the entries read
+/// row by row, by backtracking on the entries allowed in each cell given
+/// its row and column.
+// rows_used[i] and cols_used[j] are the masks of the entries of the row i
+// and the column j before the current cell.
+#[derive(Clone, Debug)]
+pub struct LatinSquares {
+    rows: Vec<Vec<usize>>,
+    rows_used: Vec<u64>,
+    cols_used: Vec<u64>,
+    reduced: bool,
+    first: bool,
+    done: bool,
+}
+
+impl LatinSquares {
+    /// # Panics
+    ///
+    /// If `n > 64`.
+    pub fn new(n: usize) -> LatinSquares {
+        assert!(n <= 64, "Latin squares of order at most 64");
+        LatinSquares {
+            rows: vec![vec![0; n]; n],
+            rows_used: vec![0; n],
+            cols_used: vec![0; n],
+            reduced: false,
+            first: true,
+            done: false,
+        }
+    }
+
+    /// Reduced Latin squares: the first row and the first column are
+    /// `0, 1, ..., n-1`.
+    pub fn reduced(n: usize) -> LatinSquares {
+        LatinSquares { reduced: true, .. LatinSquares::new(n) }
+    }
+
+    /// The current square, as its rows.
+    pub fn current(&self) -> &[Vec<usize>] {
+        &self.rows
+    }
+
+    fn place(&mut self, i: usize, j: usize, v: usize) {
+        self.rows[i][j] = v;
+        self.rows_used[i] |= 1 << v;
+        self.cols_used[j] |= 1 << v;
+    }
+
+    // Remove the entry at (i, j), and return it
+    fn unplace(&mut self, i: usize, j: usize) -> usize {
+        let v = self.rows[i][j];
+        self.rows_used[i] &= !(1 << v);
+        self.cols_used[j] &= !(1 << v);
+        v
+    }
+
+    // Fill the cells from l on, in reading order, starting with the entry
+    // start at l, backtracking if necessary.
+    fn search(&mut self, mut l: usize, mut start: usize) -> bool {
+        let n = self.rows.len();
+        while l < n * n {
+            let (i, j) = (l / n, l % n);
+            let mut free = !(self.rows_used[i] | self.cols_used[j]) & full_mask(n);
+            free &= !full_mask(start);
+            if self.reduced && (i == 0 || j == 0) {
+                free &= 1 << (i + j);
+            }
+            if free != 0 {
+                self.place(i, j, free.trailing_zeros() as usize);
+                l += 1;
+                start = 0;
+            } else {
+                if l == 0 { return false }
+                l -= 1;
+                start = self.unplace(l / n, l % n) + 1;
+            }
+        }
+        true
+    }
+}
+
+/// Iterate through the Latin squares.
+///
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for LatinSquares {
+    type Item = [Vec<usize>];
+    fn next(&mut self) -> Option<&[Vec<usize>]> {
+        if self.done { return None }
+        let n = self.rows.len();
+        let found = if self.first {
+            self.first = false;
+            self.search(0, 0)
+        } else if n == 0 {
+            false
+        } else {
+            let v = self.unplace(n - 1, n - 1);
+            self.search(n * n - 1, v + 1)
+        };
+        self.done = !found;
+        if found { Some(&self.rows) } else { None }
+    }
+
+    fn get(&self) -> Option<&[Vec<usize>]> {
+        if self.first || self.done { None } else { Some(&self.rows) }
+    }
+}
+
+/// An iterator over the ways to extend a `k × n` Latin rectangle by one
+/// row: the rows of entries in `0..n`, each appearing once, which differ
+/// from the entries above them in every column. These are the systems of
+/// distinct representatives of the sets of entries missing from the
+/// columns.
+///
+/// The rows are enumerated in lexicographic order, by backtracking.
+///
+/// # Panics
+///
+/// If `n > 64`, or if the given rows are not a Latin rectangle with `n`
+/// columns.
+// cols_used[j] is the mask of the entries of the column j in the
+// rectangle, used the mask of the entries of the new row before the
+// current cell.
+#[derive(Clone, Debug)]
+pub struct LatinRowExtensions {
+    row: Vec<usize>,
+    cols_used: Vec<u64>,
+    used: u64,
+    first: bool,
+    done: bool,
+}
+
+impl LatinRowExtensions {
+    pub fn new(n: usize, rows: &[Vec<usize>]) -> LatinRowExtensions {
+        assert!(n <= 64, "Latin rectangles with at most 64 columns");
+        let mut cols_used = vec![0u64; n];
+        for r in rows {
+            assert_eq!(r.len(), n, "Row of the wrong length");
+            let mut row_used = 0u64;
+            for (j, &v) in r.iter().enumerate() {
+                assert!(v < n, "Entry out of range");
+                assert!(row_used & 1 << v == 0 && cols_used[j] & 1 << v == 0,
+                        "Not a Latin rectangle");
+                row_used |= 1 << v;
+                cols_used[j] |= 1 << v;
+            }
+        }
+        LatinRowExtensions {
+            row: vec![0; n],
+            cols_used: cols_used,
+            used: 0,
+            first: true,
+            done: false,
+        }
+    }
+
+    /// The current row.
+    pub fn current(&self) -> &[usize] {
+        &self.row
+    }
+
+    // Fill the cells from j on, starting with the entry start at j,
+    // backtracking if necessary.
+    fn search(&mut self, mut j: usize, mut start: usize) -> bool {
+        let n = self.row.len();
+        while j < n {
+            let free = !(self.used | self.cols_used[j] | full_mask(start)) & full_mask(n);
+            if free != 0 {
+                let v = free.trailing_zeros() as usize;
+                self.row[j] = v;
+                self.used |= 1 << v;
+                j += 1;
+                start = 0;
+            } else {
+                if j == 0 { return false }
+                j -= 1;
+                start = self.unplace(j) + 1;
+            }
+        }
+        true
+    }
+
+    // Remove the entry at j, and return it
+    fn unplace(&mut self, j: usize) -> usize {
+        let v = self.row[j];
+        self.used &= !(1 << v);
+        v
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for LatinRowExtensions {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        if self.done { return None }
+        let n = self.row.len();
+        let found = if self.first {
+            self.first = false;
+            self.search(0, 0)
+        } else if n == 0 {
+            false
+        } else {
+            let v = self.unplace(n - 1);
+            self.search(n - 1, v + 1)
+        };
+        self.done = !found;
+        if found { Some(&self.row) } else { None }
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(&self.row) }
+    }
+}
+
+/// An iterator over magic squares of order `n`: `n × n` arrays of the
+/// entries `1..=n²`, each appearing once, where every row, column and
+/// diagonal sums to `n (n² + 1) / 2`.
+///
+/// The squares are enumerated in lexicographic order of the entries read
+/// row by row, by backtracking: an entry is only placed if its row, column
+/// and diagonals can still reach the magic sum, which forces the last entry
+/// of every line.
+#[derive(Clone, Debug)]
+pub struct MagicSquares {
+    rows: Vec<Vec<usize>>,
+    used: u64,
+    // Sums of the entries placed in every row, column and diagonal
+    row_sums: Vec<usize>,
+    col_sums: Vec<usize>,
+    diag_sum: usize,
+    anti_sum: usize,
+    first: bool,
+    done: bool,
+}
+
+impl MagicSquares {
+    /// # Panics
+    ///
+    /// If `n > 8`.
+    pub fn new(n: usize) -> MagicSquares {
+        assert!(n <= 8, "Magic squares of order at most 8");
+        MagicSquares {
+            rows: vec![vec![0; n]; n],
+            used: 0,
+            row_sums: vec![0; n],
+            col_sums: vec![0; n],
+            diag_sum: 0,
+            anti_sum: 0,
+            first: true,
+            done: false,
+        }
+    }
+
+    /// The current square, as its rows.
+    pub fn current(&self) -> &[Vec<usize>] {
+        &self.rows
+    }
+
+    // Whether v can be placed at (i, j)
+    fn fits(&self, i: usize, j: usize, v: usize) -> bool {
+        let n = self.rows.len();
+        let (magic, max) = (n * (n * n + 1) / 2, n * n);
+        // A line with the given sum, and the given number of cells left,
+        // can still reach the magic sum
+        let reachable = |sum: usize, left: usize| {
+            if left == 0 { sum == magic } else { sum + left <= magic && sum + left * max >= magic }
+        };
+        self.used & 1 << (v - 1) == 0 &&
+            reachable(self.row_sums[i] + v, n - 1 - j) &&
+            reachable(self.col_sums[j] + v, n - 1 - i) &&
+            (i != j || reachable(self.diag_sum + v, n - 1 - i)) &&
+            (i + j != n - 1 || reachable(self.anti_sum + v, n - 1 - i))
+    }
+
+    fn place(&mut self, i: usize, j: usize, v: usize) {
+        let n = self.rows.len();
+        self.rows[i][j] = v;
+        self.used |= 1 << (v - 1);
+        self.row_sums[i] += v;
+        self.col_sums[j] += v;
+        if i == j { self.diag_sum += v }
+        if i + j == n - 1 { self.anti_sum += v }
+    }
+
+    // Remove the entry at (i, j), and return it
+    fn unplace(&mut self, i: usize, j: usize) -> usize {
+        let n = self.rows.len();
+        let v = self.rows[i][j];
+        self.used &= !(1 << (v - 1));
+        self.row_sums[i] -= v;
+        self.col_sums[j] -= v;
+        if i == j { self.diag_sum -= v }
+        if i + j == n - 1 { self.anti_sum -= v }
+        v
+    }
+
+    // Fill the cells from l on, in reading order, starting with the entry
+    // start at l, backtracking if necessary.
+    fn search(&mut self, mut l: usize, mut start: usize) -> bool {
+        let n = self.rows.len();
+        while l < n * n {
+            let (i, j) = (l / n, l % n);
+            match (start..(n * n + 1)).find(|&v| { self.fits(i, j, v) }) {
+                Some(v) => {
+                    self.place(i, j, v);
+                    l += 1;
+                    start = 1;
+                }
+                None => {
+                    if l == 0 { return false }
+                    l -= 1;
+                    start = self.unplace(l / n, l % n) + 1;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Iterate through the magic squares.
+///
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for MagicSquares {
+    type Item = [Vec<usize>];
+    fn next(&mut self) -> Option<&[Vec<usize>]> {
+        if self.done { return None }
+        let n = self.rows.len();
+        let found = if self.first {
+            self.first = false;
+            self.search(0, 1)
+        } else if n == 0 {
+            false
+        } else {
+            let v = self.unplace(n - 1, n - 1);
+            self.search(n * n - 1, v + 1)
+        };
+        self.done = !found;
+        if found { Some(&self.rows) } else { None }
+    }
+
+    fn get(&self) -> Option<&[Vec<usize>]> {
+        if self.first || self.done { None } else { Some(&self.rows) }
+    }
+}
+
+/// An iterator over alternating sign matrices of order `n`: `n × n`
+/// matrices of entries in `{-1, 0, 1}` where, in every row and column, the
+/// nonzero entries alternate in sign, starting and ending with `1`.
+///
+/// Permutation matrices are the alternating sign matrices without `-1`.
+/// There are `prod (3k + 1)! / (n + k)!` of them, for `k` in `0..n`.
+///
+/// The matrices are enumerated in lexicographic order of the entries read
+/// row by row, by backtracking: the partial sums of every row and column
+/// must stay in `{0, 1}`.
+#[derive(Clone, Debug)]
+pub struct AlternatingSignMatrices {
+    rows: Vec<Vec<i32>>,
+    // Sums of the entries placed in every row and column
+    row_sums: Vec<i32>,
+    col_sums: Vec<i32>,
+    first: bool,
+    done: bool,
+}
+
+impl AlternatingSignMatrices {
+    pub fn new(n: usize) -> AlternatingSignMatrices {
+        AlternatingSignMatrices {
+            rows: vec![vec![0; n]; n],
+            row_sums: vec![0; n],
+            col_sums: vec![0; n],
+            first: true,
+            done: false,
+        }
+    }
+
+    /// The current matrix, as its rows.
+    pub fn current(&self) -> &[Vec<i32>] {
+        &self.rows
+    }
+
+    // Whether v can be placed at (i, j)
+    fn fits(&self, i: usize, j: usize, v: i32) -> bool {
+        let n = self.rows.len();
+        let (r, c) = (self.row_sums[i] + v, self.col_sums[j] + v);
+        (r == 0 || r == 1) && (c == 0 || c == 1) &&
+            (j + 1 < n || r == 1) && (i + 1 < n || c == 1)
+    }
+
+    fn place(&mut self, i: usize, j: usize, v: i32) {
+        self.rows[i][j] = v;
+        self.row_sums[i] += v;
+        self.col_sums[j] += v;
+    }
+
+    // Remove the entry at (i, j), and return it
+    fn unplace(&mut self, i: usize, j: usize) -> i32 {
+        let v = self.rows[i][j];
+        self.row_sums[i] -= v;
+        self.col_sums[j] -= v;
+        v
+    }
+
+    // Fill the cells from l on, in reading order, starting with the entry
+    // start at l, backtracking if necessary.
+    fn search(&mut self, mut l: usize, mut start: i32) -> bool {
+        let n = self.rows.len();
+        while l < n * n {
+            let (i, j) = (l / n, l % n);
+            match (start..2).find(|&v| { self.fits(i, j, v) }) {
+                Some(v) => {
+                    self.place(i, j, v);
+                    l += 1;
+                    start = -1;
+                }
+                None => {
+                    if l == 0 { return false }
+                    l -= 1;
+                    start = self.unplace(l / n, l % n) + 1;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Iterate through the alternating sign matrices.
+///
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for AlternatingSignMatrices {
+    type Item = [Vec<i32>];
+    fn next(&mut self) -> Option<&[Vec<i32>]> {
+        if self.done { return None }
+        let n = self.rows.len();
+        let found = if self.first {
+            self.first = false;
+            self.search(0, -1)
+        } else if n == 0 {
+            false
+        } else {
+            let v = self.unplace(n - 1, n - 1);
+            self.search(n * n - 1, v + 1)
+        };
+        self.done = !found;
+        if found { Some(&self.rows) } else { None }
+    }
+
+    fn get(&self) -> Option<&[Vec<i32>]> {
+        if self.first || self.done { None } else { Some(&self.rows) }
+    }
+}
+
+/// An iterator over the `m × n` matrices with entries in `{0, 1}` up to
+/// permutations of the rows and of the columns, i.e., the bipartite graphs
+/// with `m` and `n` labelled vertices up to isomorphism preserving the
+/// sides. The rows are yielded as masks, the bit `j` being the entry of the
+/// column `j` (see `bits::bitstring` to print them).
+///
+/// The representative of a class is its canonical form: the matrix with
+/// nondecreasing rows which is the smallest, in lexicographic order of its
+/// rows, among those obtained by permuting its columns, then sorting its
+/// rows. The rows are added in nondecreasing order (orderly generation),
+/// and a partial matrix is pruned as soon as it is not canonical itself,
+/// since none of its extensions can be, so every class is generated exactly
+/// once. Canonicity is checked against the `n!` permutations of the columns,
+/// so this is only practical for a few columns.
+///
+/// # Panics
+///
+/// If `n >= 64`.
+#[derive(Clone, Debug)]
+pub struct BinaryMatrices {
+    m: usize,
+    n: usize,
+    rows: Vec<u64>,
+    first: bool,
+    done: bool,
+}
+
+impl BinaryMatrices {
+    pub fn new(m: usize, n: usize) -> BinaryMatrices {
+        assert!(n < 64, "Binary matrices with less than 64 columns");
+        BinaryMatrices {
+            m: m,
+            n: n,
+            rows: Vec::with_capacity(m),
+            first: true,
+            done: false,
+        }
+    }
+
+    /// The current matrix, as its rows.
+    pub fn current(&self) -> &[u64] {
+        &self.rows
+    }
+
+    // Whether the rows are smaller than their images by every permutation
+    // of the columns, the images being sorted
+    fn is_canonical(&self) -> bool {
+        let mut image = Vec::with_capacity(self.rows.len());
+        let mut perms = index_permutations(self.n);
+        while let Some(p) = perms.next() {
+            image.clear();
+            image.extend(self.rows.iter().map(|&r| {
+                p.iter().enumerate().fold(0u64, |x, (j, &pj)| { x | (r >> j & 1) << pj })
+            }));
+            image.sort();
+            if image < self.rows { return false }
+        }
+        true
+    }
+
+    // Add rows from start on, not less than the last row, backtracking if
+    // necessary.
+    fn search(&mut self, mut start: u64) -> bool {
+        let end = 1u64 << self.n;
+        while self.rows.len() < self.m {
+            let lo = cmp::max(start, self.rows.last().map_or(0, |&r| { r }));
+            let mut found = false;
+            for v in lo..end {
+                self.rows.push(v);
+                if self.is_canonical() { found = true; break }
+                self.rows.pop();
+            }
+            if found {
+                start = 0;
+            } else {
+                match self.rows.pop() {
+                    None => return false,
+                    Some(r) => start = r + 1,
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Iterate through the canonical matrices.
+///
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for BinaryMatrices {
+    type Item = [u64];
+    fn next(&mut self) -> Option<&[u64]> {
+        if self.done { return None }
+        let found = if self.first {
+            self.first = false;
+            self.search(0)
+        } else {
+            match self.rows.pop() {
+                None => false,
+                Some(r) => self.search(r + 1),
+            }
+        };
+        self.done = !found;
+        if found { Some(&self.rows) } else { None }
+    }
+
+    fn get(&self) -> Option<&[u64]> {
+        if self.first || self.done { None } else { Some(&self.rows) }
+    }
+}
+
+impl_snapshot!(LatinSquares, LatinRowExtensions, MagicSquares, AlternatingSignMatrices, BinaryMatrices);
@@ -0,0 +1,328 @@
+//! Words up to rotation (necklaces) and reflection (bracelets)
+//!
+//! A subset of `n` points on a circle is a binary word of length `n`, the
+//! `i`-th letter being `1` if the point `i` is in the subset, so subsets up
+//! to rotation are binary necklaces. Likewise, arrangements of elements
+//! around a circle are permutations up to rotation, see
+//! `CircularPermutations`.
+
+use std::*;
+use group::{Group, CycleIndex};
+use iter::{StreamingIterator, Snapshot, next_permutation};
+
+/// An iterator over the necklaces of length `n` over the alphabet `0..k`:
+/// the words which are the smallest of their rotations, in lexicographic
+/// order.
+///
+/// This is the algorithm of Fredricksen, Kessler and Maiorana, which
+/// generates the prenecklaces (prefixes of necklaces) in constant amortized
+/// time, and keeps the necklaces among them.
+// a[1..] is the current prenecklace, and a[0] = 0 a sentinel. reversed is a
+// buffer for the reflection test of bracelets.
+#[derive(Clone, Debug)]
+pub struct Necklaces {
+    a: Vec<usize>,
+    k: usize,
+    bracelets: bool,
+    reversed: Vec<usize>,
+    first: bool,
+    done: bool,
+}
+
+impl Necklaces {
+    pub fn new(n: usize, k: usize) -> Necklaces {
+        Necklaces {
+            a: vec![0; n + 1],
+            k: k,
+            bracelets: false,
+            reversed: Vec::with_capacity(n),
+            first: true,
+            // No word of positive length over an empty alphabet
+            done: k == 0 && n > 0,
+        }
+    }
+
+    /// Bracelets: the necklaces which are also the smallest of the rotations
+    /// of their reversal, i.e., the words up to rotation and reflection.
+    pub fn bracelets(n: usize, k: usize) -> Necklaces {
+        Necklaces { bracelets: true, .. Necklaces::new(n, k) }
+    }
+
+    // Move to the next prenecklace. Returns its period, or None if there is
+    // no more.
+    fn advance(&mut self) -> Option<usize> {
+        let n = self.a.len() - 1;
+        let mut i = n;
+        while i > 0 && self.a[i] + 1 == self.k { i -= 1 }
+        if i == 0 { return None }
+        self.a[i] += 1;
+        for j in (i + 1)..(n + 1) { self.a[j] = self.a[j - i] }
+        Some(i)
+    }
+
+    // Whether the current necklace is the smallest of the rotations of its
+    // reversal. This is is_bracelet, without allocating.
+    fn is_bracelet(&mut self) -> bool {
+        self.reversed.clear();
+        self.reversed.extend(self.a[1..].iter().rev().cloned());
+        let r = least_rotation(&self.reversed);
+        let n = self.reversed.len();
+        let rotated = self.reversed[r..].iter().chain(&self.reversed[..r]);
+        self.a[1..].iter().le(rotated.take(n))
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for Necklaces {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        if self.done { return None }
+        let n = self.a.len() - 1;
+        if self.first {
+            // 0^n is a necklace and a bracelet
+            self.first = false;
+            return Some(&self.a[1..]);
+        }
+        loop {
+            match self.advance() {
+                None => { self.done = true; return None }
+                // A prenecklace is a necklace if its period divides n
+                Some(p) => if n.is_multiple_of(p) && (!self.bracelets || self.is_bracelet()) {
+                    return Some(&self.a[1..]);
+                },
+            }
+        }
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(&self.a[1..]) }
+    }
+}
+
+/// An iterator over the arrangements of the elements of a slice around a
+/// circle, i.e., its permutations up to rotation, or also up to reflection
+/// with `up_to_reflection`. Every arrangement is yielded once, even if some
+/// elements are equal, as its smallest rotation (and reflection), in
+/// lexicographic order.
+///
+/// There are `(n - 1)!` arrangements of `n > 0` distinct elements, and
+/// `(n - 1)! / 2` up to reflection for `n >= 3`.
+///
+/// The elements are replaced by their ranks among the distinct ones. A
+/// necklace starts with a smallest letter, so that one stays in front while
+/// the others run through their permutations in lexicographic order, and the
+/// words which are not necklaces (or bracelets) are skipped. For distinct
+/// elements, none is skipped up to rotation, and about half up to
+/// reflection.
+// values are the distinct elements in increasing order, word the current
+// arrangement as indices into values, and dest the arrangement itself.
+#[derive(Clone, Debug)]
+pub struct CircularPermutations<T> {
+    values: Vec<T>,
+    word: Vec<usize>,
+    dest: Vec<T>,
+    reflection: bool,
+    first: bool,
+    done: bool,
+}
+
+impl<T: Ord + Clone> CircularPermutations<T> {
+    pub fn new(src: &[T]) -> CircularPermutations<T> {
+        let mut dest = src.to_vec();
+        dest.sort();
+        let mut values = dest.clone();
+        values.dedup();
+        let word = dest.iter().map(|x| { values.binary_search(x).unwrap() }).collect();
+        CircularPermutations {
+            values: values,
+            word: word,
+            dest: dest,
+            reflection: false,
+            first: true,
+            done: false,
+        }
+    }
+
+    /// The arrangements up to rotation and reflection, e.g., for necklaces
+    /// which can be turned over.
+    pub fn up_to_reflection(src: &[T]) -> CircularPermutations<T> {
+        CircularPermutations { reflection: true, .. CircularPermutations::new(src) }
+    }
+
+    // Whether the current word is the canonical representative of its class
+    fn is_canonical(&self) -> bool {
+        if self.reflection { is_bracelet(&self.word) } else { is_necklace(&self.word) }
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl<T: Ord + Clone> StreamingIterator for CircularPermutations<T> {
+    type Item = [T];
+    fn next(&mut self) -> Option<&[T]> {
+        if self.done { return None }
+        if self.first {
+            // The sorted arrangement is the smallest of all
+            self.first = false;
+            return Some(&self.dest);
+        }
+        loop {
+            if self.word.is_empty() || !next_permutation(&mut self.word[1..]) {
+                self.done = true;
+                return None;
+            }
+            if self.is_canonical() { break }
+        }
+        let values = &self.values;
+        for (x, &i) in self.dest.iter_mut().zip(&self.word) {
+            x.clone_from(&values[i]);
+        }
+        Some(&self.dest)
+    }
+
+    fn get(&self) -> Option<&[T]> {
+        if self.first || self.done { None } else { Some(&self.dest) }
+    }
+}
+
+/// The position where the least rotation of `w` starts, the first one if
+/// `w` is periodic, in linear time.
+pub fn least_rotation<T: Ord>(w: &[T]) -> usize {
+    let n = w.len();
+    // Candidates i < j, whose rotations agree on k letters
+    let (mut i, mut j, mut k) = (0, 1, 0);
+    while i < n && j < n && k < n {
+        match w[(i + k) % n].cmp(&w[(j + k) % n]) {
+            cmp::Ordering::Equal => { k += 1; continue }
+            cmp::Ordering::Greater => i += k + 1,
+            cmp::Ordering::Less => j += k + 1,
+        }
+        if i == j { j += 1 }
+        k = 0;
+    }
+    cmp::min(i, j)
+}
+
+/// The smallest of the rotations of `w`: the canonical representative of
+/// its necklace.
+pub fn canonical_rotation<T: Ord + Clone>(w: &[T]) -> Vec<T> {
+    let r = least_rotation(w);
+    w[r..].iter().chain(&w[..r]).cloned().collect()
+}
+
+/// The smallest of the rotations of `w` and of its reversal: the canonical
+/// representative of its bracelet.
+pub fn canonical_reflection<T: Ord + Clone>(w: &[T]) -> Vec<T> {
+    let mut reversed = w.to_vec();
+    reversed.reverse();
+    cmp::min(canonical_rotation(w), canonical_rotation(&reversed))
+}
+
+/// Whether `w` is a necklace: the smallest of its rotations.
+pub fn is_necklace<T: Ord>(w: &[T]) -> bool {
+    least_rotation(w) == 0
+}
+
+/// Whether `w` is a bracelet: the smallest of its rotations and of those of
+/// its reversal.
+pub fn is_bracelet<T: Ord>(w: &[T]) -> bool {
+    let reversed = w.iter().rev().collect::<Vec<&T>>();
+    let r = least_rotation(&reversed);
+    is_necklace(w) && w.iter().le(reversed[r..].iter().chain(&reversed[..r]).cloned())
+}
+
+/// Whether `w` is a Lyndon word: nonempty and strictly smaller than all its
+/// other rotations, i.e., an aperiodic necklace.
+pub fn is_lyndon<T: Ord>(w: &[T]) -> bool {
+    if w.is_empty() { return false }
+    // Duval's algorithm: w[..j] is a power of a Lyndon word of length j - k,
+    // followed by a prefix of it
+    let mut k = 0;
+    for j in 1..w.len() {
+        match w[k].cmp(&w[j]) {
+            cmp::Ordering::Less => k = 0,
+            cmp::Ordering::Equal => k += 1,
+            cmp::Ordering::Greater => return false,
+        }
+    }
+    k == 0
+}
+
+/// The number of necklaces of length `n` over `k` letters, by Burnside's
+/// lemma: `(1/n) Σ φ(d) k^(n/d)` over the divisors `d` of `n`.
+///
+/// # Panics
+///
+/// On overflow.
+pub fn count_necklaces(n: usize, k: usize) -> u128 {
+    if n == 0 { return 1 }
+    let sum = (1..(n + 1)).filter(|&d| { n.is_multiple_of(d) })
+        .map(|d| { totient(d) as u128 * pow(k, n / d) })
+        .fold(0u128, |s, x| { s.checked_add(x).expect("Overflow") });
+    sum / n as u128
+}
+
+/// The number of bracelets of length `n` over `k` letters.
+///
+/// # Panics
+///
+/// On overflow.
+pub fn count_bracelets(n: usize, k: usize) -> u128 {
+    if n == 0 { return 1 }
+    // Burnside's lemma for the dihedral group: the reflections fix
+    // k^ceil(n/2) words each if n is odd, and alternately k^(n/2 + 1) and
+    // k^(n/2) words if n is even
+    let reflections = if n % 2 == 1 {
+        n as u128 * pow(k, n.div_ceil(2))
+    } else {
+        (n / 2) as u128 * (pow(k, n / 2 + 1) + pow(k, n / 2))
+    };
+    (count_necklaces(n, k) * n as u128 + reflections) / (2 * n as u128)
+}
+
+/// The number of orbits of the words of length `group.degree()` over `k`
+/// letters, when the group acts on their positions, by Burnside's lemma:
+/// the average over the group of `k^c`, where `c` is the number of cycles.
+///
+/// This takes time proportional to the order of the group. See `CycleIndex`
+/// for the common groups, and for counting words by content.
+///
+/// # Panics
+///
+/// On overflow.
+pub fn count_word_orbits(group: &Group, k: usize) -> u128 {
+    CycleIndex::of_group(group).count_colorings(k)
+}
+
+fn pow(k: usize, e: usize) -> u128 {
+    (0..e).fold(1u128, |p, _| { p.checked_mul(k as u128).expect("Overflow") })
+}
+
+// Euler's totient function
+fn totient(n: usize) -> usize {
+    (1..(n + 1)).filter(|&i| { gcd(i, n) == 1 }).count()
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+impl_snapshot!(Necklaces);
+
+/// The state is the arrangement as indices into the distinct elements, and
+/// the `first` and `done` flags.
+impl<T: Ord + Clone> Snapshot for CircularPermutations<T> {
+    type State = (Vec<usize>, bool, bool);
+    fn snapshot(&self) -> (Vec<usize>, bool, bool) {
+        (self.word.clone(), self.first, self.done)
+    }
+
+    fn restore(&mut self, state: &(Vec<usize>, bool, bool)) {
+        self.word.clone_from(&state.0);
+        self.first = state.1;
+        self.done = state.2;
+        let values = &self.values;
+        for (x, &i) in self.dest.iter_mut().zip(&self.word) {
+            x.clone_from(&values[i]);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Writing enumerations to files and streams, and resuming them
+
+use std::*;
+use std::io::Write;
+use iter::StreamingIterator;
+
+/// Line formats for the items of an enumeration of indices.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// The indices separated by commas, e.g., `0,2,3`.
+    Csv,
+    /// A string of `n` bits, where the bit at position `i` is `1` if `i` is
+    /// one of the indices, e.g., `10110` for `0,2,3` with `n = 5`.
+    Bitstring(usize),
+    /// A JSON array, e.g., `[0,2,3]`.
+    Json,
+}
+
+/// Write every item of `iter` to `out`, one per line, in the given format,
+/// and return the number of items.
+///
+/// The output is buffered, and flushed at the end.
+///
+/// # Panics
+///
+/// With `Format::Bitstring(n)`, if an index is not less than `n`.
+pub fn write_lines<I, W>(mut iter: I, format: Format, out: W) -> io::Result<usize> where
+    I: StreamingIterator<Item = [usize]>,
+    W: io::Write
+{
+    let mut out = io::BufWriter::new(out);
+    let mut bits = Vec::new();
+    let mut count = 0;
+    while let Some(x) = iter.next() {
+        match format {
+            Format::Csv => write_separated(&mut out, x)?,
+            Format::Json => {
+                out.write_all(b"[")?;
+                write_separated(&mut out, x)?;
+                out.write_all(b"]")?;
+            }
+            Format::Bitstring(n) => {
+                bits.clear();
+                bits.resize(n, b'0');
+                for &i in x { bits[i] = b'1' }
+                out.write_all(&bits)?;
+            }
+        }
+        out.write_all(b"\n")?;
+        count += 1;
+    }
+    out.flush()?;
+    Ok(count)
+}
+
+fn write_separated<W: io::Write>(out: &mut W, x: &[usize]) -> io::Result<()> {
+    for (i, j) in x.iter().enumerate() {
+        if i > 0 { out.write_all(b",")? }
+        write!(out, "{}", j)?;
+    }
+    Ok(())
+}
+
+/// A `StreamingIterator` which saves its progress to a file, to resume the
+/// enumeration after a crash or an interruption.
+///
+/// The file holds the number `r` of items completed, in decimal: the items
+/// before the last one yielded, since the caller is done with an item when
+/// it asks for the next. Opening the iterator again starts from the item of
+/// rank `r`, with a function building the underlying iterator from a rank,
+/// e.g., `|r| { IndexCombinations::from_rank(n, k, r as usize) }`.
+///
+/// The file is written every `every` items, and at the end, with the total
+/// number of items, by writing a temporary file and renaming it, so that it
+/// is never left half-written.
+///
+/// # Panics
+///
+/// If writing the file fails during the iteration. Call `checkpoint` to
+/// handle such errors.
+#[derive(Clone, Debug)]
+pub struct PersistentIter<I> {
+    iter: I,
+    path: path::PathBuf,
+    every: u128,
+    next_rank: u128,
+    finished: bool,
+}
+
+impl<I: StreamingIterator> PersistentIter<I> {
+    /// Resume from the rank saved in the file at `path`, or start from 0 if
+    /// there is no such file.
+    ///
+    /// # Panics
+    ///
+    /// If `every` is 0.
+    pub fn open<P, F>(path: P, every: u128, resume: F) -> io::Result<PersistentIter<I>> where
+        P: AsRef<path::Path>,
+        F: FnOnce(u128) -> I
+    {
+        assert!(every > 0, "Checkpoints every 0 items");
+        let path = path.as_ref().to_path_buf();
+        let rank = match fs::read_to_string(&path) {
+            Ok(s) => s.trim().parse().map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, e)
+            })?,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e),
+        };
+        Ok(PersistentIter {
+            iter: resume(rank),
+            path: path,
+            every: every,
+            next_rank: rank,
+            finished: false,
+        })
+    }
+
+    /// The number of items completed, i.e., the rank of the next item to
+    /// be yielded.
+    pub fn completed(&self) -> u128 {
+        self.next_rank.saturating_sub(if self.iter.get().is_some() { 1 } else { 0 })
+    }
+
+    /// Save the number of items completed now.
+    pub fn checkpoint(&self) -> io::Result<()> {
+        self.save(self.completed())
+    }
+
+    fn save(&self, completed: u128) -> io::Result<()> {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, format!("{}\n", completed))?;
+        fs::rename(&tmp, &self.path)
+    }
+
+    /// Recover the underlying iterator.
+    pub fn into_inner(self) -> I {
+        self.iter
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl<I: StreamingIterator> StreamingIterator for PersistentIter<I> {
+    type Item = I::Item;
+    fn next(&mut self) -> Option<&I::Item> {
+        if self.finished { return None }
+        // The items before next_rank are completed
+        if self.next_rank > 0 && self.next_rank.is_multiple_of(self.every) {
+            self.save(self.next_rank).expect("Failed to save the enumeration state");
+        }
+        if self.iter.next().is_some() {
+            self.next_rank += 1;
+        } else {
+            self.finished = true;
+            self.save(self.next_rank).expect("Failed to save the enumeration state");
+        }
+        self.iter.get()
+    }
+
+    fn get(&self) -> Option<&I::Item> {
+        self.iter.get()
+    }
+}
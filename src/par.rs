@@ -0,0 +1,329 @@
+//! Parallel iterators, with the `rayon` feature
+//!
+//! An enumeration is split into ranges of ranks, and each range is
+//! enumerated sequentially, starting by unranking its first object.
+
+use std::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use rayon::iter::plumbing::*;
+use rayon::iter::*;
+use iter::{IndexCombinations, IndexSubsequences, next_permutation};
+use rank;
+
+/// A finite enumeration which can be started at an arbitrary rank.
+pub trait Space: Clone + Send {
+    /// The state of a sequential enumeration.
+    type State;
+    type Item: Send;
+    /// The number of objects.
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool { self.len() == 0 }
+    /// The state whose current object has rank `r`.
+    fn unrank(&self, r: usize) -> Self::State;
+    /// Move to the next object. There must be one.
+    fn step(&self, state: &mut Self::State);
+    /// Build the current object.
+    fn item(&self, state: &Self::State) -> Self::Item;
+}
+
+/// Combinations of `k` elements of a slice, in lexicographic order.
+#[derive(Debug)]
+pub struct CombinationSpace<'a, T: 'a> {
+    src: &'a [T],
+    k: usize,
+}
+
+// Not derived, which would require `T: Clone`.
+impl<'a, T> Clone for CombinationSpace<'a, T> {
+    fn clone(&self) -> Self {
+        CombinationSpace { src: self.src, k: self.k }
+    }
+}
+
+impl<'a, T: Clone + Sync + Send> Space for CombinationSpace<'a, T> {
+    type State = IndexCombinations;
+    type Item = Vec<T>;
+    fn len(&self) -> usize { rank::choose(self.src.len(), self.k) }
+    fn unrank(&self, r: usize) -> IndexCombinations {
+        let mut c = IndexCombinations::from_rank(self.src.len(), self.k, r);
+        c.advance();
+        c
+    }
+    fn step(&self, state: &mut IndexCombinations) { state.advance(); }
+    fn item(&self, state: &IndexCombinations) -> Vec<T> {
+        state.current().iter().map(|&j| { self.src[j].clone() }).collect()
+    }
+}
+
+/// Subsequences of a slice, in the same order as `Subsequences`.
+#[derive(Debug)]
+pub struct SubsequenceSpace<'a, T: 'a> {
+    src: &'a [T],
+}
+
+impl<'a, T> Clone for SubsequenceSpace<'a, T> {
+    fn clone(&self) -> Self {
+        SubsequenceSpace { src: self.src }
+    }
+}
+
+impl<'a, T: Clone + Sync + Send> Space for SubsequenceSpace<'a, T> {
+    type State = IndexSubsequences;
+    type Item = Vec<T>;
+    fn len(&self) -> usize { 1 << self.src.len() }
+    fn unrank(&self, r: usize) -> IndexSubsequences {
+        let mut s = IndexSubsequences::from_rank(self.src.len(), r);
+        s.advance();
+        s
+    }
+    fn step(&self, state: &mut IndexSubsequences) { state.advance(); }
+    fn item(&self, state: &IndexSubsequences) -> Vec<T> {
+        state.current().iter().map(|&j| { self.src[j].clone() }).collect()
+    }
+}
+
+/// Permutations of a slice, in lexicographic order of the positions.
+///
+/// This is not the order of `Permutations`.
+#[derive(Debug)]
+pub struct PermutationSpace<'a, T: 'a> {
+    src: &'a [T],
+}
+
+impl<'a, T> Clone for PermutationSpace<'a, T> {
+    fn clone(&self) -> Self {
+        PermutationSpace { src: self.src }
+    }
+}
+
+impl<'a, T: Clone + Sync + Send> Space for PermutationSpace<'a, T> {
+    type State = Vec<usize>;
+    type Item = Vec<T>;
+    fn len(&self) -> usize { rank::factorial(self.src.len()) }
+    fn unrank(&self, r: usize) -> Vec<usize> {
+        let mut p = Vec::with_capacity(self.src.len());
+        rank::permutation_unrank(self.src.len(), r, &mut p);
+        p
+    }
+    fn step(&self, state: &mut Vec<usize>) { next_permutation(state); }
+    fn item(&self, state: &Vec<usize>) -> Vec<T> {
+        state.iter().map(|&j| { self.src[j].clone() }).collect()
+    }
+}
+
+/// Pairs of elements of two slices, in lexicographic order.
+#[derive(Debug)]
+pub struct ProductSpace<'a, A: 'a, B: 'a> {
+    a: &'a [A],
+    b: &'a [B],
+}
+
+impl<'a, A, B> Clone for ProductSpace<'a, A, B> {
+    fn clone(&self) -> Self {
+        ProductSpace { a: self.a, b: self.b }
+    }
+}
+
+impl<'a, A: Sync, B: Sync> Space for ProductSpace<'a, A, B> {
+    type State = usize;
+    type Item = (&'a A, &'a B);
+    fn len(&self) -> usize { self.a.len() * self.b.len() }
+    fn unrank(&self, r: usize) -> usize { r }
+    fn step(&self, state: &mut usize) { *state += 1; }
+    fn item(&self, &r: &usize) -> (&'a A, &'a B) {
+        let m = self.b.len();
+        (&self.a[r / m], &self.b[r % m])
+    }
+}
+
+/// A parallel iterator over a `Space`.
+#[derive(Clone, Debug)]
+pub struct ParIter<S> {
+    space: S,
+}
+
+impl<S: Space> ParIter<S> {
+    /// A parallel iterator over a user-defined `Space`.
+    pub fn new(space: S) -> ParIter<S> {
+        ParIter { space: space }
+    }
+}
+
+impl<S: Space> ParallelIterator for ParIter<S> {
+    type Item = S::Item;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+        where C: UnindexedConsumer<Self::Item>
+    {
+        bridge(self, consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.space.len())
+    }
+}
+
+impl<S: Space> IndexedParallelIterator for ParIter<S> {
+    fn drive<C: Consumer<Self::Item>>(self, consumer: C) -> C::Result {
+        bridge(self, consumer)
+    }
+
+    fn len(&self) -> usize {
+        self.space.len()
+    }
+
+    fn with_producer<CB: ProducerCallback<Self::Item>>(self, callback: CB)
+        -> CB::Output
+    {
+        let end = self.space.len();
+        callback.callback(RankProducer { space: self.space, start: 0, end: end })
+    }
+}
+
+struct RankProducer<S> {
+    space: S,
+    start: usize,
+    end: usize,
+}
+
+impl<S: Space> Producer for RankProducer<S> {
+    type Item = S::Item;
+    type IntoIter = RankIter<S>;
+
+    fn into_iter(self) -> RankIter<S> {
+        RankIter {
+            space: self.space,
+            front: None,
+            start: self.start,
+            end: self.end,
+        }
+    }
+
+    fn split_at(self, mid: usize) -> (Self, Self) {
+        let mid = self.start + mid;
+        (RankProducer { space: self.space.clone(), start: self.start, end: mid },
+         RankProducer { space: self.space, start: mid, end: self.end })
+    }
+}
+
+/// Sequential enumeration of a range of ranks.
+// The front is enumerated incrementally, the back is unranked.
+struct RankIter<S: Space> {
+    space: S,
+    front: Option<S::State>,
+    start: usize,
+    end: usize,
+}
+
+impl<S: Space> Iterator for RankIter<S> {
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        if self.start == self.end { return None }
+        match self.front {
+            None => self.front = Some(self.space.unrank(self.start)),
+            Some(ref mut state) => self.space.step(state),
+        }
+        self.start += 1;
+        self.front.as_ref().map(|state| { self.space.item(state) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let l = self.end - self.start;
+        (l, Some(l))
+    }
+}
+
+impl<S: Space> DoubleEndedIterator for RankIter<S> {
+    fn next_back(&mut self) -> Option<S::Item> {
+        if self.start == self.end { return None }
+        self.end -= 1;
+        Some(self.space.item(&self.space.unrank(self.end)))
+    }
+}
+
+impl<S: Space> ExactSizeIterator for RankIter<S> {}
+
+/// Parallel iterators over the combinatorial derivatives of slices.
+///
+/// The objects are yielded as owned vectors, since they are produced in
+/// different threads.
+pub trait ParallelCombinatorics<T> {
+    fn par_combinations<'a>(&'a self, k: usize) -> ParIter<CombinationSpace<'a, T>>;
+    fn par_subsequences<'a>(&'a self) -> ParIter<SubsequenceSpace<'a, T>>;
+    fn par_permutations<'a>(&'a self) -> ParIter<PermutationSpace<'a, T>>;
+    fn par_product<'a, U>(&'a self, other: &'a [U]) -> ParIter<ProductSpace<'a, T, U>>;
+}
+
+impl<T: Clone + Sync + Send> ParallelCombinatorics<T> for [T] {
+    fn par_combinations<'a>(&'a self, k: usize) -> ParIter<CombinationSpace<'a, T>> {
+        assert!(k <= self.len(), "Cannot choose {} elements out of {}", k, self.len());
+        ParIter { space: CombinationSpace { src: self, k: k } }
+    }
+
+    fn par_subsequences<'a>(&'a self) -> ParIter<SubsequenceSpace<'a, T>> {
+        assert!(self.len() < usize::BITS as usize,
+                "Cannot enumerate the subsequences of {} elements by rank", self.len());
+        ParIter { space: SubsequenceSpace { src: self } }
+    }
+
+    fn par_permutations<'a>(&'a self) -> ParIter<PermutationSpace<'a, T>> {
+        ParIter { space: PermutationSpace { src: self } }
+    }
+
+    fn par_product<'a, U>(&'a self, other: &'a [U]) -> ParIter<ProductSpace<'a, T, U>> {
+        ParIter { space: ProductSpace { a: self, b: other } }
+    }
+}
+
+/// A flag to stop an exploration early, from any thread, e.g., when a
+/// counterexample is found.
+#[derive(Debug, Default)]
+pub struct Cancellation {
+    cancelled: AtomicBool,
+}
+
+impl Cancellation {
+    pub fn new() -> Cancellation {
+        Cancellation { cancelled: AtomicBool::new(false) }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Map every object of a space, in parallel, and reduce the results with an
+/// associative function, in the order of the ranks. `None` if the space is
+/// empty.
+///
+/// The ranks are split into ranges run by the threads of the current rayon
+/// pool, and every range is enumerated sequentially after unranking its
+/// first object (see `Space`).
+pub fn explore<S, T, M, R>(iter: ParIter<S>, map: M, reduce: R) -> Option<T> where
+    S: Space,
+    T: Send,
+    M: Fn(S::Item) -> T + Sync + Send,
+    R: Fn(T, T) -> T + Sync + Send,
+{
+    explore_until(iter, &Cancellation::new(), map, reduce)
+}
+
+/// Like `explore`, but stop mapping objects once `cancel` is cancelled, by
+/// `map` itself or by another thread. The result then only covers the
+/// objects mapped before, on a best-effort basis.
+pub fn explore_until<S, T, M, R>(iter: ParIter<S>, cancel: &Cancellation, map: M, reduce: R)
+    -> Option<T> where
+    S: Space,
+    T: Send,
+    M: Fn(S::Item) -> T + Sync + Send,
+    R: Fn(T, T) -> T + Sync + Send,
+{
+    iter.map(|x| { if cancel.is_cancelled() { None } else { Some(map(x)) } })
+        .while_some()
+        .reduce_with(reduce)
+}
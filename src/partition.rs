@@ -0,0 +1,235 @@
+//! Integer partitions as values
+
+use std::*;
+
+/// A partition of an integer: a nonincreasing sequence of positive parts.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Partition {
+    parts: Vec<usize>,
+}
+
+impl Partition {
+    /// The partition with the given parts, in any order. Zeros are ignored.
+    pub fn from_parts(mut parts: Vec<usize>) -> Partition {
+        parts.retain(|&x| { x != 0 });
+        parts.sort_by(|x, y| { y.cmp(x) });
+        Partition { parts: parts }
+    }
+
+    /// The parts, in nonincreasing order.
+    pub fn parts(&self) -> &[usize] {
+        &self.parts
+    }
+
+    /// The number of parts.
+    pub fn len(&self) -> usize {
+        self.parts.len()
+    }
+
+    /// Whether this is the partition of 0.
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// The partitioned integer, i.e., the sum of the parts.
+    pub fn size(&self) -> usize {
+        self.parts.iter().sum()
+    }
+
+    // For iterators updating a partition in place. The parts must stay
+    // positive and nonincreasing.
+    pub(crate) fn parts_mut(&mut self) -> &mut Vec<usize> {
+        &mut self.parts
+    }
+}
+
+impl Partition {
+    /// The conjugate partition, whose Young diagram is the transpose: its
+    /// `j`-th part is the number of parts greater than `j`.
+    pub fn conjugate(&self) -> Partition {
+        let width = self.parts.first().map_or(0, |&x| { x });
+        let mut conj = Vec::with_capacity(width);
+        let mut i = self.parts.len();
+        for j in 0..width {
+            while self.parts[i - 1] <= j { i -= 1 }
+            conj.push(i);
+        }
+        Partition { parts: conj }
+    }
+
+    /// Whether the Young diagram of `other` is contained in that of `self`,
+    /// i.e., every part of `other` is at most the corresponding part of
+    /// `self`.
+    pub fn contains(&self, other: &Partition) -> bool {
+        other.len() <= self.len() &&
+            other.parts.iter().zip(&self.parts).all(|(x, y)| { x <= y })
+    }
+
+    /// Whether the Young diagram fits in a box of `k` rows and `m` columns,
+    /// i.e., there are at most `k` parts, all at most `m`.
+    pub fn fits_in(&self, k: usize, m: usize) -> bool {
+        self.len() <= k && self.parts.first().map_or(0, |&x| { x }) <= m
+    }
+}
+
+impl Partition {
+    /// The side of the Durfee square, the largest square contained in the
+    /// Young diagram: the largest `d` such that there are at least `d` parts
+    /// at least `d`.
+    pub fn durfee_square(&self) -> usize {
+        self.parts.iter().enumerate().take_while(|&(i, &x)| { x > i }).count()
+    }
+
+    /// Dyson's rank: the largest part minus the number of parts.
+    pub fn rank(&self) -> isize {
+        self.parts.first().map_or(0, |&x| { x }) as isize - self.len() as isize
+    }
+
+    /// The number of parts equal to 1.
+    pub fn ones(&self) -> usize {
+        self.parts.iter().rev().take_while(|&&x| { x == 1 }).count()
+    }
+
+    /// The crank of Andrews and Garvan: the largest part if there is no part
+    /// equal to 1, and otherwise, the number of parts greater than the
+    /// number `ω` of ones, minus `ω`.
+    pub fn crank(&self) -> isize {
+        let ones = self.ones();
+        if ones == 0 {
+            self.rank() + self.len() as isize
+        } else {
+            self.parts.iter().filter(|&&x| { x > ones }).count() as isize - ones as isize
+        }
+    }
+}
+
+impl Partition {
+    /// Whether `self` dominates `other`: both partition the same integer,
+    /// and for every `k`, the `k` largest parts of `self` sum to at least
+    /// those of `other`.
+    pub fn dominates(&self, other: &Partition) -> bool {
+        self.size() == other.size() && {
+            let (mut s, mut t) = (0, 0);
+            (0..cmp::max(self.len(), other.len())).all(|k| {
+                s += self.parts.get(k).map_or(0, |&x| { x });
+                t += other.parts.get(k).map_or(0, |&x| { x });
+                s >= t
+            })
+        }
+    }
+
+    /// Compare in the dominance order, a partial order: `None` if the
+    /// partitions are incomparable, including if their sizes differ.
+    pub fn dominance_cmp(&self, other: &Partition) -> Option<cmp::Ordering> {
+        match (self.dominates(other), other.dominates(self)) {
+            (true, true) => Some(cmp::Ordering::Equal),
+            (true, false) => Some(cmp::Ordering::Greater),
+            (false, true) => Some(cmp::Ordering::Less),
+            (false, false) => None,
+        }
+    }
+}
+
+impl Partition {
+    /// The hook lengths of the cells of the Young diagram, row by row: the
+    /// hook of a cell is made of the cell itself and the cells to its right
+    /// and below it.
+    pub fn hook_lengths(&self) -> Vec<Vec<usize>> {
+        let conj = self.conjugate();
+        self.parts.iter().enumerate().map(|(i, &x)| {
+            (0..x).map(|j| { x - j + conj.parts[j] - i - 1 }).collect()
+        }).collect()
+    }
+
+    /// The number of standard Young tableaux of this shape, by the hook
+    /// length formula: `n!` divided by the product of the hook lengths.
+    ///
+    /// # Panics
+    ///
+    /// On overflow.
+    pub fn standard_tableaux_count(&self) -> u128 {
+        // Exponents of the primes in the result
+        let n = self.size();
+        let mut exps = vec![0isize; n + 1];
+        for k in 2..(n + 1) { add_factors(&mut exps, k, 1) }
+        for h in self.hook_lengths().iter().flat_map(|r| { r.iter() }) {
+            add_factors(&mut exps, *h, -1);
+        }
+        exps.iter().enumerate().fold(1u128, |r, (p, &e)| {
+            (0..e).fold(r, |r, _| { r.checked_mul(p as u128).expect("Overflow") })
+        })
+    }
+}
+
+// Add the exponents of the prime factors of k, times sign, to exps
+fn add_factors(exps: &mut [isize], mut k: usize, sign: isize) {
+    let mut p = 2;
+    while k > 1 {
+        while k.is_multiple_of(p) { exps[p] += sign; k /= p }
+        p += 1;
+    }
+}
+
+impl Partition {
+    /// Format the Young diagram in English notation: a row of `#` for every
+    /// part, from the largest one, on separate lines.
+    pub fn young_diagram(&self) -> YoungDiagram<'_> {
+        YoungDiagram { partition: self }
+    }
+
+    /// Format in exponent notation, e.g., `4^3 2 1^2` for
+    /// `[4 4 4 2 1 1]`: every distinct part, with its multiplicity if it is
+    /// not 1.
+    pub fn exponent_notation(&self) -> ExponentNotation<'_> {
+        ExponentNotation { partition: self }
+    }
+}
+
+/// Format the parts, e.g., `[4 2 1]`.
+impl fmt::Display for Partition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, x) in self.parts.iter().enumerate() {
+            if i > 0 { write!(f, " ")? }
+            write!(f, "{}", x)?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// The Young diagram of a partition, see `Partition::young_diagram`.
+#[derive(Clone, Copy, Debug)]
+pub struct YoungDiagram<'a> {
+    partition: &'a Partition,
+}
+
+impl<'a> fmt::Display for YoungDiagram<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, &x) in self.partition.parts.iter().enumerate() {
+            if i > 0 { writeln!(f)? }
+            for _ in 0..x { write!(f, "#")? }
+        }
+        Ok(())
+    }
+}
+
+/// The exponent notation of a partition, see `Partition::exponent_notation`.
+#[derive(Clone, Copy, Debug)]
+pub struct ExponentNotation<'a> {
+    partition: &'a Partition,
+}
+
+impl<'a> fmt::Display for ExponentNotation<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts = &self.partition.parts;
+        let mut i = 0;
+        while i < parts.len() {
+            let m = parts[i..].iter().take_while(|&&x| { x == parts[i] }).count();
+            if i > 0 { write!(f, " ")? }
+            write!(f, "{}", parts[i])?;
+            if m > 1 { write!(f, "^{}", m)? }
+            i += m;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,783 @@
+//! Permutations as values
+
+use std::*;
+use partition::Partition;
+use iter::{StreamingIterator, Snapshot, IndexPermutations, index_permutations};
+use error::Error;
+
+/// A permutation of `0..n`, in one-line notation: the sequence of the
+/// images of `0, 1, ..., n-1`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Permutation {
+    image: Vec<usize>,
+}
+
+impl Permutation {
+    /// The identity permutation of `0..n`.
+    pub fn identity(n: usize) -> Permutation {
+        Permutation { image: (0..n).collect() }
+    }
+
+    /// The permutation with the given images, if it is one.
+    pub fn from_vec(image: Vec<usize>) -> Option<Permutation> {
+        if is_permutation(&image) {
+            Some(Permutation { image: image })
+        } else {
+            None
+        }
+    }
+
+    /// The permutation of `0..n` with the given disjoint cycles, mapping
+    /// each element of a cycle to the next one. Elements which do not appear
+    /// are fixed points.
+    ///
+    /// Returns `None` if the cycles are not disjoint or contain elements
+    /// out of `0..n`.
+    pub fn from_cycles(n: usize, cycles: &[Vec<usize>]) -> Option<Permutation> {
+        let mut image = vec![n; n];
+        for c in cycles {
+            for (i, &x) in c.iter().enumerate() {
+                let y = c[(i + 1) % c.len()];
+                if x >= n || image[x] != n { return None }
+                image[x] = y;
+            }
+        }
+        for (i, x) in image.iter_mut().enumerate() {
+            if *x == n { *x = i }
+        }
+        Some(Permutation::from_vec_unchecked(image))
+    }
+
+    /// Parse the one-line notation, as printed by `Display`: the images of
+    /// `0, 1, ..., n-1`, separated by spaces or commas, optionally between
+    /// brackets, e.g., `"[2 0 1]"`.
+    pub fn from_one_line(s: &str) -> Option<Permutation> {
+        let s = s.trim();
+        let s = if s.starts_with('[') && s.ends_with(']') {
+            &s[1..s.len() - 1]
+        } else {
+            s
+        };
+        parse_points(s).and_then(Permutation::from_vec)
+    }
+
+    /// Parse the cycle notation of a permutation of `0..n`, as printed by
+    /// `cycle_notation`, e.g., `"(0 2 1)(4 5)"`. Fixed points may be
+    /// omitted.
+    pub fn from_cycle_notation(n: usize, s: &str) -> Option<Permutation> {
+        let mut cycles = Vec::new();
+        let mut s = s.trim();
+        while !s.is_empty() {
+            if !s.starts_with('(') { return None }
+            let close = s.find(')')?;
+            cycles.push(parse_points(&s[1..close])?);
+            s = s[close + 1..].trim_start();
+        }
+        Permutation::from_cycles(n, &cycles)
+    }
+
+    /// The product `s[w[0]] ∘ s[w[1]] ∘ ...` of adjacent transpositions,
+    /// where `s[i]` exchanges `i` and `i + 1`.
+    ///
+    /// Returns `None` if a letter is not less than `n - 1`.
+    pub fn from_word(n: usize, w: &[usize]) -> Option<Permutation> {
+        let mut p = Permutation::identity(n);
+        for &i in w {
+            if i + 1 >= n { return None }
+            p.swap(i, i + 1);
+        }
+        Some(p)
+    }
+
+    // The caller ensures that image is a permutation
+    fn from_vec_unchecked(image: Vec<usize>) -> Permutation {
+        Permutation { image: image }
+    }
+
+    /// The size `n` of the permuted set `0..n`.
+    pub fn len(&self) -> usize {
+        self.image.len()
+    }
+
+    /// Whether this is the permutation of the empty set.
+    pub fn is_empty(&self) -> bool {
+        self.image.is_empty()
+    }
+
+    /// The image of `i`.
+    pub fn apply_index(&self, i: usize) -> usize {
+        self.image[i]
+    }
+
+    /// The one-line notation.
+    pub fn as_slice(&self) -> &[usize] {
+        &self.image
+    }
+
+    pub fn into_vec(self) -> Vec<usize> {
+        self.image
+    }
+
+    /// Exchange the images of `i` and `j`, i.e., compose with the
+    /// transposition of `i` and `j` on the right.
+    pub fn swap(&mut self, i: usize, j: usize) {
+        self.image.swap(i, j)
+    }
+
+    /// The composition `self ∘ other`, which maps `i` to
+    /// `self[other[i]]`.
+    ///
+    /// # Panics
+    ///
+    /// If the permutations have different lengths.
+    pub fn compose(&self, other: &Permutation) -> Permutation {
+        assert_eq!(self.len(), other.len(), "Permutations of different lengths");
+        Permutation::from_vec_unchecked(
+            other.image.iter().map(|&j| { self.image[j] }).collect())
+    }
+
+    /// The inverse permutation.
+    pub fn inverse(&self) -> Permutation {
+        let mut inv = vec![0; self.len()];
+        for (i, &j) in self.image.iter().enumerate() { inv[j] = i }
+        Permutation::from_vec_unchecked(inv)
+    }
+
+    /// Rearrange a slice: the element at position `i` of the result is
+    /// `src[self[i]]`.
+    ///
+    /// This agrees with `compose`: applying `p.compose(q)` is the same as
+    /// applying `p`, then `q`.
+    ///
+    /// # Panics
+    ///
+    /// If the lengths differ.
+    pub fn apply<T: Clone>(&self, src: &[T]) -> Vec<T> {
+        assert_eq!(self.len(), src.len(), "Slice of the wrong length");
+        self.image.iter().map(|&j| { src[j].clone() }).collect()
+    }
+}
+
+impl Permutation {
+    /// The cycle decomposition, including fixed points.
+    ///
+    /// Each cycle starts with its smallest element, and the cycles are
+    /// sorted by their first elements.
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        let mut seen = vec![false; self.len()];
+        let mut cycles = Vec::new();
+        for i in 0..self.len() {
+            if seen[i] { continue }
+            let mut c = Vec::new();
+            let mut j = i;
+            while !seen[j] {
+                seen[j] = true;
+                c.push(j);
+                j = self.image[j];
+            }
+            cycles.push(c);
+        }
+        cycles
+    }
+
+    /// Whether the permutation is a product of an even or odd number of
+    /// transpositions.
+    pub fn parity(&self) -> Parity {
+        parity(&self.image)
+    }
+
+    /// The signature: `1` for even permutations, `-1` for odd ones.
+    pub fn sign(&self) -> i32 {
+        self.parity().sign()
+    }
+
+    /// The `k`-th power, computed from the cycles in linear time.
+    /// Negative powers are powers of the inverse.
+    pub fn pow(&self, k: isize) -> Permutation {
+        let mut image = vec![0; self.len()];
+        for c in self.cycles() {
+            let l = c.len() as isize;
+            let shift = ((k % l + l) % l) as usize;
+            for (i, &x) in c.iter().enumerate() {
+                image[x] = c[(i + shift) % c.len()];
+            }
+        }
+        Permutation::from_vec_unchecked(image)
+    }
+
+    /// The smallest positive `k` such that the `k`-th power is the
+    /// identity: the least common multiple of the lengths of the cycles.
+    pub fn order(&self) -> usize {
+        self.cycles().iter().fold(1, |m, c| { m / gcd(m, c.len()) * c.len() })
+    }
+
+    /// The lengths of the cycles, which determine the conjugacy class.
+    pub fn cycle_type(&self) -> Partition {
+        Partition::from_parts(self.cycles().iter().map(|c| { c.len() }).collect())
+    }
+}
+
+impl Permutation {
+    /// Rearrange `v` in place, in the same way as `apply`: the element at
+    /// position `i` is replaced by the one at position `self[i]`.
+    ///
+    /// Every cycle is rotated separately, from its smallest element. This
+    /// uses constant extra space, but finding those smallest elements takes
+    /// quadratic time in the worst case.
+    ///
+    /// # Panics
+    ///
+    /// If the lengths differ.
+    pub fn apply_to<T>(&self, v: &mut [T]) {
+        assert_eq!(self.len(), v.len(), "Slice of the wrong length");
+        for i in 0..v.len() {
+            if !self.is_cycle_leader(i) { continue }
+            let mut j = i;
+            loop {
+                let k = self.image[j];
+                if k == i { break }
+                v.swap(j, k);
+                j = k;
+            }
+        }
+    }
+
+    /// Rearrange `v` in place, in the inverse way of `apply_to`: the element
+    /// at position `i` is moved to position `self[i]`.
+    ///
+    /// # Panics
+    ///
+    /// If the lengths differ.
+    pub fn apply_inverse_to<T>(&self, v: &mut [T]) {
+        assert_eq!(self.len(), v.len(), "Slice of the wrong length");
+        for i in 0..v.len() {
+            if !self.is_cycle_leader(i) { continue }
+            let mut k = self.image[i];
+            while k != i {
+                v.swap(i, k);
+                k = self.image[k];
+            }
+        }
+    }
+
+    // Whether i is the smallest element of its cycle
+    fn is_cycle_leader(&self, i: usize) -> bool {
+        let mut j = self.image[i];
+        while j != i {
+            if j < i { return false }
+            j = self.image[j];
+        }
+        true
+    }
+}
+
+impl Permutation {
+    /// A shortest word for `from_word`, whose length is the number of
+    /// inversions.
+    pub fn reduced_word(&self) -> Vec<usize> {
+        // Bubble sort the one-line notation, then reverse the swaps
+        let mut v = self.image.clone();
+        let mut w = Vec::new();
+        for end in (0..v.len()).rev() {
+            for i in 0..end {
+                if v[i] > v[i + 1] { v.swap(i, i + 1); w.push(i) }
+            }
+        }
+        w.reverse();
+        w
+    }
+
+    /// The inversion table: the `j`-th entry is the number of elements
+    /// greater than `j` to the left of `j` in the one-line notation, so that
+    /// it is less than `n - j` (Knuth, TAOCP 5.1.1).
+    pub fn inversion_table(&self) -> Vec<usize> {
+        let inv = self.inverse();
+        (0..self.len()).map(|j| {
+            self.image[..inv[j]].iter().filter(|&&x| { x > j }).count()
+        }).collect()
+    }
+
+    /// The permutation with the given inversion table, if it is one: the
+    /// `j`-th entry must be less than `n - j`.
+    pub fn from_inversion_table(table: &[usize]) -> Option<Permutation> {
+        let n = table.len();
+        // Insert n-1, ..., 0, each after the right number of larger ones
+        let mut image = Vec::with_capacity(n);
+        for j in (0..n).rev() {
+            if table[j] >= n - j { return None }
+            image.insert(table[j], j);
+        }
+        Some(Permutation::from_vec_unchecked(image))
+    }
+
+    /// Format in cycle notation, e.g., `(0 2 1)(3)(4 5)`, including fixed
+    /// points.
+    pub fn cycle_notation(&self) -> CycleNotation<'_> {
+        CycleNotation { perm: self }
+    }
+}
+
+/// Format in one-line notation, e.g., `[2 0 1]`.
+impl fmt::Display for Permutation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, x) in self.image.iter().enumerate() {
+            if i > 0 { write!(f, " ")? }
+            write!(f, "{}", x)?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// Parse the one-line notation, see `Permutation::from_one_line`.
+impl str::FromStr for Permutation {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Permutation, Error> {
+        Permutation::from_one_line(s).ok_or_else(|| {
+            Error::Parse { kind: "permutation", input: s.to_string() }
+        })
+    }
+}
+
+/// The cycle notation of a permutation, see `Permutation::cycle_notation`.
+#[derive(Clone, Copy, Debug)]
+pub struct CycleNotation<'a> {
+    perm: &'a Permutation,
+}
+
+impl<'a> fmt::Display for CycleNotation<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.perm.cycles() {
+            write!(f, "(")?;
+            for (i, x) in c.iter().enumerate() {
+                if i > 0 { write!(f, " ")? }
+                write!(f, "{}", x)?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+// Parse numbers separated by spaces or commas
+fn parse_points(s: &str) -> Option<Vec<usize>> {
+    s.split(|c: char| { c == ',' || c.is_whitespace() })
+        .filter(|x| { !x.is_empty() })
+        .map(|x| { x.parse().ok() })
+        .collect()
+}
+
+impl ops::Index<usize> for Permutation {
+    type Output = usize;
+    fn index(&self, i: usize) -> &usize {
+        &self.image[i]
+    }
+}
+
+/// A signed permutation of `0..n`, an element of the hyperoctahedral group:
+/// a permutation together with a sign for the image of every element. It
+/// acts on vectors by permuting their coordinates and negating some of them.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SignedPermutation {
+    perm: Permutation,
+    negated: Vec<bool>,
+}
+
+impl SignedPermutation {
+    /// The identity, with positive signs.
+    pub fn identity(n: usize) -> SignedPermutation {
+        SignedPermutation { perm: Permutation::identity(n), negated: vec![false; n] }
+    }
+
+    /// The signed permutation mapping `i` to `perm[i]`, negated if
+    /// `negated[i]`, if the lengths agree.
+    pub fn new(perm: Permutation, negated: Vec<bool>) -> Option<SignedPermutation> {
+        if perm.len() == negated.len() {
+            Some(SignedPermutation { perm: perm, negated: negated })
+        } else {
+            None
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.perm.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.perm.is_empty()
+    }
+
+    /// The underlying permutation, forgetting the signs.
+    pub fn permutation(&self) -> &Permutation {
+        &self.perm
+    }
+
+    /// Whether the image of every element is negated.
+    pub fn negated(&self) -> &[bool] {
+        &self.negated
+    }
+
+    /// The image of `i`, and whether it is negated.
+    pub fn apply_index(&self, i: usize) -> (usize, bool) {
+        (self.perm[i], self.negated[i])
+    }
+
+    /// The composition `self ∘ other`, as for `Permutation::compose`, the
+    /// signs multiplying.
+    ///
+    /// # Panics
+    ///
+    /// If the lengths differ.
+    pub fn compose(&self, other: &SignedPermutation) -> SignedPermutation {
+        let perm = self.perm.compose(&other.perm);
+        let negated = (0..other.len()).map(|i| {
+            other.negated[i] != self.negated[other.perm[i]]
+        }).collect();
+        SignedPermutation { perm: perm, negated: negated }
+    }
+
+    /// The inverse signed permutation.
+    pub fn inverse(&self) -> SignedPermutation {
+        let mut negated = vec![false; self.len()];
+        for (i, &j) in self.perm.as_slice().iter().enumerate() {
+            negated[j] = self.negated[i];
+        }
+        SignedPermutation { perm: self.perm.inverse(), negated: negated }
+    }
+
+    /// Rearrange a vector as `Permutation::apply`, negating the coordinates
+    /// with negated images: the coordinate `i` of the result is
+    /// `±src[self[i]]`.
+    ///
+    /// # Panics
+    ///
+    /// If the lengths differ.
+    pub fn apply<T: Clone + ops::Neg<Output = T>>(&self, src: &[T]) -> Vec<T> {
+        self.perm.apply(src).into_iter().zip(&self.negated)
+            .map(|(x, &neg)| { if neg { -x } else { x } })
+            .collect()
+    }
+}
+
+/// Format in one-line notation, negated images with a minus sign, e.g.,
+/// `[2 -0 1]`.
+impl fmt::Display for SignedPermutation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[")?;
+        for i in 0..self.len() {
+            if i > 0 { write!(f, " ")? }
+            if self.negated[i] { write!(f, "-")? }
+            write!(f, "{}", self.perm[i])?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// An iterator over the `2^n n!` signed permutations of `0..n`: the
+/// permutations in the order of `index_permutations`, and for each of them
+/// the signs by binary counting, `negated[0]` being the lowest bit.
+#[derive(Clone, Debug)]
+pub struct SignedPermutations {
+    perms: IndexPermutations,
+    current: SignedPermutation,
+    first: bool,
+    done: bool,
+}
+
+impl SignedPermutations {
+    pub fn new(n: usize) -> SignedPermutations {
+        SignedPermutations {
+            perms: index_permutations(n),
+            current: SignedPermutation::identity(n),
+            first: true,
+            done: false,
+        }
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for SignedPermutations {
+    type Item = SignedPermutation;
+    fn next(&mut self) -> Option<&SignedPermutation> {
+        if self.done { return None }
+        if self.first {
+            self.first = false;
+            self.perms.advance();
+            return Some(&self.current)
+        }
+        // Increment the signs, then the permutation when they wrap around
+        for neg in &mut self.current.negated {
+            *neg = !*neg;
+            if *neg { return Some(&self.current) }
+        }
+        if self.perms.advance().is_none() {
+            self.done = true;
+            return None;
+        }
+        self.current.perm.clone_from(self.perms.current_permutation());
+        Some(&self.current)
+    }
+
+    fn get(&self) -> Option<&SignedPermutation> {
+        if self.first || self.done { None } else { Some(&self.current) }
+    }
+}
+
+/// The parity of a permutation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Parity {
+    Even,
+    Odd,
+}
+
+impl Parity {
+    /// `1` for `Even`, `-1` for `Odd`.
+    pub fn sign(self) -> i32 {
+        match self { Parity::Even => 1, Parity::Odd => -1 }
+    }
+}
+
+/// The parity of a permutation of `0..p.len()`, in linear time.
+///
+/// A permutation of `n` elements with `c` cycles (including fixed points) is
+/// a product of `n - c` transpositions.
+///
+/// # Panics
+///
+/// If `p` is not a permutation.
+pub fn parity(p: &[usize]) -> Parity {
+    let mut seen = vec![false; p.len()];
+    let mut transpositions = 0;
+    for i in 0..p.len() {
+        if seen[i] { continue }
+        let mut j = p[i];
+        seen[i] = true;
+        while j != i {
+            assert!(!seen[j], "Not a permutation");
+            seen[j] = true;
+            transpositions += 1;
+            j = p[j];
+        }
+    }
+    if transpositions % 2 == 0 { Parity::Even } else { Parity::Odd }
+}
+
+/// Whether `p` is a permutation of `0..p.len()`.
+pub fn is_permutation(p: &[usize]) -> bool {
+    let mut seen = vec![false; p.len()];
+    for &j in p {
+        if j >= p.len() || seen[j] { return false }
+        seen[j] = true;
+    }
+    true
+}
+
+/// Whether `p` contains the pattern `pattern`: a subsequence of `p` in the
+/// same relative order as `pattern`, whose elements must be distinct.
+pub fn contains_pattern<T: Ord>(p: &[T], pattern: &[T]) -> bool {
+    occurrences(p, pattern).next().is_some()
+}
+
+/// Iterate through the occurrences of `pattern` in `p`: the increasing
+/// sequences of indices in `p` whose elements are in the same relative order
+/// as `pattern`, in lexicographic order. The elements of `pattern` must be
+/// distinct.
+pub fn occurrences<'a, T: Ord>(p: &'a [T], pattern: &'a [T]) -> Occurrences<'a, T> {
+    Occurrences {
+        p: p,
+        pattern: pattern,
+        indices: Vec::with_capacity(pattern.len()),
+        first: true,
+        done: false,
+    }
+}
+
+/// An iterator over the occurrences of a pattern, by backtracking.
+///
+/// See `occurrences`.
+// indices is the current partial occurrence.
+#[derive(Clone, Debug)]
+pub struct Occurrences<'a, T: 'a> {
+    p: &'a [T],
+    pattern: &'a [T],
+    indices: Vec<usize>,
+    first: bool,
+    done: bool,
+}
+
+impl<'a, T: Ord> Occurrences<'a, T> {
+    // Whether p[i] can extend the partial occurrence.
+    fn fits(&self, i: usize) -> bool {
+        let k = self.indices.len();
+        self.indices.iter().enumerate().all(|(t, &j)| {
+            self.p[j].cmp(&self.p[i]) == self.pattern[t].cmp(&self.pattern[k])
+        })
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl<'a, T: Ord> StreamingIterator for Occurrences<'a, T> {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        if self.done { return None }
+        let (n, k) = (self.p.len(), self.pattern.len());
+        let mut i = if self.first {
+            self.first = false;
+            0
+        } else {
+            match self.indices.pop() {
+                None => { self.done = true; return None }
+                Some(i) => i + 1,
+            }
+        };
+        while self.indices.len() < k {
+            // Not enough room left for the rest of the pattern
+            if i + k - self.indices.len() > n {
+                match self.indices.pop() {
+                    None => { self.done = true; return None }
+                    Some(j) => i = j + 1,
+                }
+            } else {
+                if self.fits(i) { self.indices.push(i) }
+                i += 1;
+            }
+        }
+        Some(&self.indices)
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(&self.indices) }
+    }
+}
+
+/// The length of the longest (strictly) increasing subsequences of `p`, in
+/// time `O(n log n)`.
+pub fn lis_length<T: Ord>(p: &[T]) -> usize {
+    lis_lengths(p, 0..p.len(), |i, j| { p[i] < p[j] }).iter().cloned().max().unwrap_or(0)
+}
+
+/// The indices of a longest (strictly) increasing subsequence of `p`, in
+/// time `O(n log n)`.
+pub fn longest_increasing_subsequence<T: Ord>(p: &[T]) -> Vec<usize> {
+    let lengths = lis_lengths(p, 0..p.len(), |i, j| { p[i] < p[j] });
+    let mut len = lengths.iter().cloned().max().unwrap_or(0);
+    // Walk back from the last position ending a longest subsequence
+    let mut witness = Vec::with_capacity(len);
+    for i in (0..p.len()).rev() {
+        if len > 0 && lengths[i] == len && witness.last().is_none_or(|&j| { p[i] < p[j] }) {
+            witness.push(i);
+            len -= 1;
+        }
+    }
+    witness.reverse();
+    witness
+}
+
+/// Iterate through the longest (strictly) increasing subsequences of `p`,
+/// as increasing sequences of indices, in lexicographic order.
+pub fn longest_increasing_subsequences<T: Ord>(p: &[T]) -> LongestIncreasingSubsequences<'_, T> {
+    let ending = lis_lengths(p, 0..p.len(), |i, j| { p[i] < p[j] });
+    let starting = lis_lengths(p, (0..p.len()).rev(), |i, j| { p[i] > p[j] });
+    let len = ending.iter().cloned().max().unwrap_or(0);
+    // The positions in a longest subsequence, and where they are in it
+    let level = (0..p.len()).map(|i| {
+        if ending[i] + starting[i] == len + 1 { Some(ending[i] - 1) } else { None }
+    }).collect();
+    LongestIncreasingSubsequences {
+        p: p,
+        level: level,
+        len: len,
+        indices: Vec::with_capacity(len),
+        first: true,
+        done: false,
+    }
+}
+
+// The lengths of the longest subsequences ending at every index, visiting
+// the indices in the given order, increasing for less. Patience sorting:
+// tails[l] is the index ending the best subsequence of length l + 1 so far.
+fn lis_lengths<T, I, F>(p: &[T], order: I, less: F) -> Vec<usize> where
+    I: Iterator<Item = usize>,
+    F: Fn(usize, usize) -> bool,
+{
+    let mut lengths = vec![0; p.len()];
+    let mut tails: Vec<usize> = Vec::new();
+    for i in order {
+        let l = tails.partition_point(|&j| { less(j, i) });
+        lengths[i] = l + 1;
+        if l == tails.len() { tails.push(i) } else { tails[l] = i }
+    }
+    lengths
+}
+
+/// An iterator over the longest increasing subsequences of a sequence.
+///
+/// See `longest_increasing_subsequences`. The positions which may appear in
+/// them are precomputed, so that the backtracking never fails to complete a
+/// partial subsequence.
+// level[i] is the position of p[i] in the longest subsequences containing
+// it, if any. indices is the current partial subsequence.
+#[derive(Clone, Debug)]
+pub struct LongestIncreasingSubsequences<'a, T: 'a> {
+    p: &'a [T],
+    level: Vec<Option<usize>>,
+    len: usize,
+    indices: Vec<usize>,
+    first: bool,
+    done: bool,
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl<'a, T: Ord> StreamingIterator for LongestIncreasingSubsequences<'a, T> {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        if self.done { return None }
+        let n = self.p.len();
+        let mut i = if self.first {
+            self.first = false;
+            0
+        } else {
+            match self.indices.pop() {
+                None => { self.done = true; return None }
+                Some(i) => i + 1,
+            }
+        };
+        while self.indices.len() < self.len {
+            if i == n {
+                match self.indices.pop() {
+                    None => { self.done = true; return None }
+                    Some(j) => i = j + 1,
+                }
+            } else {
+                let t = self.indices.len();
+                let fits = self.level[i] == Some(t) &&
+                    self.indices.last().is_none_or(|&j| { self.p[j] < self.p[i] });
+                if fits { self.indices.push(i) }
+                i += 1;
+            }
+        }
+        Some(&self.indices)
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(&self.indices) }
+    }
+}
+
+impl_snapshot!(SignedPermutations);
+
+impl<'a, T: Clone> Snapshot for Occurrences<'a, T> {
+    type State = Occurrences<'a, T>;
+    fn snapshot(&self) -> Occurrences<'a, T> { self.clone() }
+    fn restore(&mut self, state: &Occurrences<'a, T>) { self.clone_from(state) }
+}
+
+impl<'a, T: Clone> Snapshot for LongestIncreasingSubsequences<'a, T> {
+    type State = LongestIncreasingSubsequences<'a, T>;
+    fn snapshot(&self) -> LongestIncreasingSubsequences<'a, T> { self.clone() }
+    fn restore(&mut self, state: &LongestIncreasingSubsequences<'a, T>) { self.clone_from(state) }
+}
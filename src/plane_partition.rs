@@ -0,0 +1,76 @@
+//! Plane partitions
+
+use std::*;
+use iter::{StreamingIterator, Snapshot};
+
+/// An iterator over plane partitions in an `a × b × c` box: `a × b`
+/// matrices of heights in `0..=c`, weakly decreasing along rows and
+/// columns.
+///
+/// The matrices are yielded as their rows, in lexicographic order of the
+/// heights read row by row, starting from the empty plane partition.
+/// There are `prod (i + j + k - 1) / (i + j + k - 2)` of them, for `i`,
+/// `j` and `k` ranging over `1..=a`, `1..=b` and `1..=c` (MacMahon).
+#[derive(Clone, Debug)]
+pub struct PlanePartitions {
+    rows: Vec<Vec<usize>>,
+    c: usize,
+    first: bool,
+    done: bool,
+}
+
+impl PlanePartitions {
+    pub fn new(a: usize, b: usize, c: usize) -> PlanePartitions {
+        PlanePartitions {
+            rows: vec![vec![0; b]; a],
+            c: c,
+            first: true,
+            done: false,
+        }
+    }
+
+    /// The current plane partition, as its rows.
+    pub fn current(&self) -> &[Vec<usize>] {
+        &self.rows
+    }
+
+    // The largest height allowed at (i, j) by its neighbours before it
+    fn max_height(&self, i: usize, j: usize) -> usize {
+        let left = if j > 0 { self.rows[i][j - 1] } else { self.c };
+        let above = if i > 0 { self.rows[i - 1][j] } else { self.c };
+        cmp::min(left, above)
+    }
+}
+
+/// Iterate through the plane partitions.
+///
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for PlanePartitions {
+    type Item = [Vec<usize>];
+    fn next(&mut self) -> Option<&[Vec<usize>]> {
+        if self.done { return None }
+        if self.first { self.first = false; return Some(&self.rows) }
+        // Increment the last height which can be, and flatten the cells
+        // after it
+        let b = self.rows.first().map_or(0, |r| { r.len() });
+        for l in (0..(self.rows.len() * b)).rev() {
+            let (i, j) = (l / b, l % b);
+            if self.rows[i][j] < self.max_height(i, j) {
+                self.rows[i][j] += 1;
+                for x in self.rows[i][(j + 1)..].iter_mut() { *x = 0 }
+                for r in self.rows[(i + 1)..].iter_mut() {
+                    for x in r.iter_mut() { *x = 0 }
+                }
+                return Some(&self.rows)
+            }
+        }
+        self.done = true;
+        None
+    }
+
+    fn get(&self) -> Option<&[Vec<usize>]> {
+        if self.first || self.done { None } else { Some(&self.rows) }
+    }
+}
+
+impl_snapshot!(PlanePartitions);
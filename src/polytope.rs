@@ -0,0 +1,170 @@
+//! Faces of the hypercube and of the simplex
+//!
+//! A face of the `n`-cube `[0, 1]^n` fixes some coordinates to 0 or 1 and
+//! leaves the others free, so it is a word over `{0, 1, *}`, its dimension
+//! being the number of stars. A face of the `n`-simplex is the convex hull
+//! of a nonempty subset of its `n + 1` vertices.
+
+use std::*;
+use iter::{StreamingIterator, Snapshot, IndexCombinations};
+
+/// A coordinate of a face of the cube.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Star {
+    Zero,
+    One,
+    /// Free coordinate, `*`.
+    Free,
+}
+
+/// Formats as `0`, `1` or `*`.
+impl fmt::Display for Star {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let c = match *self {
+            Star::Zero => '0',
+            Star::One => '1',
+            Star::Free => '*',
+        };
+        write!(f, "{}", c)
+    }
+}
+
+/// The dimension of a face of the cube: its number of free coordinates.
+pub fn cube_face_dimension(face: &[Star]) -> usize {
+    face.iter().filter(|&&c| { c == Star::Free }).count()
+}
+
+/// An iterator over the faces of the `n`-cube, in lexicographic order
+/// (`Zero < One < Free`), or only those of a given dimension with
+/// `of_dimension`.
+///
+/// There are `3^n` faces, and `choose(n, d) * 2^(n - d)` of dimension `d`.
+// dimension is the number of stars in face, and wanted the dimension to
+// keep, if any.
+#[derive(Clone, Debug)]
+pub struct CubeFaces {
+    face: Vec<Star>,
+    dimension: usize,
+    wanted: Option<usize>,
+    first: bool,
+    done: bool,
+}
+
+impl CubeFaces {
+    pub fn new(n: usize) -> CubeFaces {
+        CubeFaces {
+            face: vec![Star::Zero; n],
+            dimension: 0,
+            wanted: None,
+            first: true,
+            done: false,
+        }
+    }
+
+    /// The faces of dimension `d`, e.g., the vertices for `d = 0` and the
+    /// edges for `d = 1`.
+    pub fn of_dimension(n: usize, d: usize) -> CubeFaces {
+        CubeFaces { wanted: Some(d), done: d > n, ..CubeFaces::new(n) }
+    }
+
+    // Move to the next face in lexicographic order, returning false if
+    // there is none.
+    fn advance(&mut self) -> bool {
+        for c in self.face.iter_mut().rev() {
+            match *c {
+                Star::Zero => { *c = Star::One; return true }
+                Star::One => { *c = Star::Free; self.dimension += 1; return true }
+                Star::Free => { *c = Star::Zero; self.dimension -= 1 }
+            }
+        }
+        false
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for CubeFaces {
+    type Item = [Star];
+    fn next(&mut self) -> Option<&[Star]> {
+        if self.done { return None }
+        let mut advance = !self.first;
+        self.first = false;
+        loop {
+            if advance && !self.advance() {
+                self.done = true;
+                return None;
+            }
+            advance = true;
+            if self.wanted.is_none_or(|d| { d == self.dimension }) { break }
+        }
+        Some(&self.face)
+    }
+
+    fn get(&self) -> Option<&[Star]> {
+        if self.first || self.done { None } else { Some(&self.face) }
+    }
+}
+
+/// An iterator over the faces of the `n`-simplex with vertices `0..n+1`, as
+/// increasing sequences of vertices, by increasing dimension and in
+/// lexicographic order for each dimension, or only those of a given
+/// dimension with `of_dimension`.
+///
+/// There are `2^(n + 1) - 1` faces, and `choose(n + 1, d + 1)` of dimension
+/// `d`.
+// dimension is that of the current faces, iterated by combinations, and
+// last the largest dimension to reach.
+#[derive(Clone, Debug)]
+pub struct SimplexFaces {
+    n: usize,
+    dimension: usize,
+    last: usize,
+    combinations: IndexCombinations,
+    done: bool,
+}
+
+impl SimplexFaces {
+    pub fn new(n: usize) -> SimplexFaces {
+        SimplexFaces {
+            n: n,
+            dimension: 0,
+            last: n,
+            combinations: IndexCombinations::new(n + 1, 1),
+            done: false,
+        }
+    }
+
+    /// The faces of dimension `d`, i.e., with `d + 1` vertices.
+    pub fn of_dimension(n: usize, d: usize) -> SimplexFaces {
+        SimplexFaces {
+            n: n,
+            dimension: d,
+            last: d,
+            combinations: IndexCombinations::new(n + 1, cmp::min(d + 1, n + 1)),
+            // No face of dimension d > n
+            done: d > n,
+        }
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for SimplexFaces {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        if self.done { return None }
+        while self.combinations.advance().is_none() {
+            if self.dimension == self.last {
+                self.done = true;
+                return None;
+            }
+            self.dimension += 1;
+            self.combinations = IndexCombinations::new(self.n + 1, self.dimension + 1);
+        }
+        Some(self.combinations.current())
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.done { None } else { self.combinations.get() }
+    }
+}
+
+impl_snapshot!(CubeFaces, SimplexFaces);
@@ -0,0 +1,99 @@
+//! Python bindings, with the `python` feature
+//!
+//! The module `combinatorust` exposes the enumerations of indices as Python
+//! iterators yielding lists, e.g., `for c in combinatorust.combinations(5, 2)`.
+//!
+//! Build the extension module with the `extension-module` feature, which
+//! does not link libpython, as a shared library:
+//!
+//! ```sh
+//! cargo rustc --release --features extension-module --crate-type cdylib
+//! ```
+
+use std::*;
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use iter::*;
+
+// A Python iterator class wrapping an enumeration of indices
+macro_rules! py_iter {
+    ($(#[$doc: meta])* $py: ident, $name: expr, $iter: ty) => {
+        $(#[$doc])*
+        #[pyclass(name = $name)]
+        struct $py {
+            iter: $iter,
+        }
+
+        #[pymethods]
+        impl $py {
+            fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+                slf
+            }
+
+            fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<Vec<usize>> {
+                slf.iter.next().map(|x| { x.to_vec() })
+            }
+        }
+    }
+}
+
+py_iter!(PyCombinations, "Combinations", IndexCombinations);
+py_iter!(PySubsequences, "Subsequences", IndexSubsequences);
+py_iter!(PyPermutations, "Permutations", IndexPermutations);
+py_iter!(PyCatalan, "Catalan", Catalan);
+py_iter!(PyPartitions, "Partitions", Partitions);
+
+/// Combinations of `k` indices in `range(n)`, in lexicographic order.
+#[pyfunction]
+fn combinations(n: usize, k: usize) -> PyResult<PyCombinations> {
+    if k > n {
+        return Err(PyValueError::new_err(
+            format!("Cannot choose {} elements out of {}", k, n)))
+    }
+    Ok(PyCombinations { iter: IndexCombinations::new(n, k) })
+}
+
+/// Subsequences of `range(n)`.
+#[pyfunction]
+fn subsequences(n: usize) -> PySubsequences {
+    PySubsequences { iter: IndexSubsequences::new(n) }
+}
+
+/// Permutations of `range(n)`, by adjacent transpositions.
+#[pyfunction]
+fn permutations(n: usize) -> PyPermutations {
+    PyPermutations { iter: index_permutations(n) }
+}
+
+/// Binary trees with `n` leaves, in the encoding of `Catalan`.
+#[pyfunction]
+fn catalan(n: usize) -> PyResult<PyCatalan> {
+    if n == 0 {
+        return Err(PyValueError::new_err("A tree has at least one leaf"))
+    }
+    Ok(PyCatalan { iter: Catalan::new(n) })
+}
+
+/// Partitions of `n`, as nonincreasing lists of parts, in reverse
+/// lexicographic order.
+#[pyfunction]
+fn partitions(n: usize) -> PyPartitions {
+    PyPartitions { iter: Partitions::new(n) }
+}
+
+// The functions are given by their paths from this module, since the
+// macro imports them and paths are relative to the crate root in Rust 2015
+#[pymodule]
+pub fn combinatorust(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyCombinations>()?;
+    m.add_class::<PySubsequences>()?;
+    m.add_class::<PyPermutations>()?;
+    m.add_class::<PyCatalan>()?;
+    m.add_class::<PyPartitions>()?;
+    m.add_function(wrap_pyfunction!(self::combinations, m)?)?;
+    m.add_function(wrap_pyfunction!(self::subsequences, m)?)?;
+    m.add_function(wrap_pyfunction!(self::permutations, m)?)?;
+    m.add_function(wrap_pyfunction!(self::catalan, m)?)?;
+    m.add_function(wrap_pyfunction!(self::partitions, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,576 @@
+//! Uniform random generation, with the `rand` feature
+//!
+//! Each distribution is uniform over the objects enumerated by the
+//! corresponding iterator, e.g., `rng.sample(UniformPermutation(n))`.
+
+use std::*;
+use rand::Rng;
+use rand::distributions::Distribution;
+use permutation::Permutation;
+use graph::Graph;
+use partition::Partition;
+
+/// The uniform distribution over permutations of `0..n`.
+#[derive(Clone, Copy, Debug)]
+pub struct UniformPermutation(pub usize);
+
+impl Distribution<Permutation> for UniformPermutation {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Permutation {
+        let mut p = Permutation::identity(self.0);
+        // Fisher-Yates shuffle
+        for i in (1..self.0).rev() {
+            let j = rng.gen_range(0..(i + 1));
+            p.swap(i, j);
+        }
+        p
+    }
+}
+
+/// The uniform distribution over derangements of `0..n`, the permutations
+/// without fixed points enumerated by `ConstrainedPermutations::derangements`.
+///
+/// This is the algorithm of Martínez, Panholzer and Prodinger, without
+/// rejection: a random permutation is built by swaps as in Fisher-Yates,
+/// while closing cycles with the probability that a uniform derangement
+/// would, using floating point.
+#[derive(Clone, Copy, Debug)]
+pub struct UniformDerangement(pub usize);
+
+impl Distribution<Permutation> for UniformDerangement {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Permutation {
+        let n = self.0;
+        assert!(n != 1, "No derangement of 1 element");
+        // e[u] = D_u / u!, where D_u is the number of derangements of u
+        // elements, so that (u - 1) D_(u-2) / D_u = e[u-2] / (u e[u])
+        let mut e = vec![1.0; n + 1];
+        let mut inverse_factorial = 1.0;
+        for u in 1..(n + 1) {
+            inverse_factorial /= u as f64;
+            e[u] = e[u - 1] + if u % 2 == 0 { inverse_factorial } else { -inverse_factorial };
+        }
+        let mut p = Permutation::identity(n);
+        let mut marked = vec![false; n];
+        // The number of elements not yet in a closed cycle
+        let mut u = n;
+        for i in (0..n).rev() {
+            if u < 2 { break }
+            if marked[i] { continue }
+            let mut j = rng.gen_range(0..i);
+            while marked[j] { j = rng.gen_range(0..i) }
+            p.swap(i, j);
+            if rng.gen::<f64>() < e[u - 2] / (u as f64 * e[u]) {
+                marked[j] = true;
+                u -= 1;
+            }
+            u -= 1;
+        }
+        p
+    }
+}
+
+/// The uniform distribution over combinations of `k` indices in `0..n`,
+/// in increasing order.
+#[derive(Clone, Copy, Debug)]
+pub struct UniformCombination {
+    pub n: usize,
+    pub k: usize,
+}
+
+impl Distribution<Vec<usize>> for UniformCombination {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<usize> {
+        let UniformCombination { n, k } = *self;
+        assert!(k <= n, "Cannot choose {} elements out of {}", k, n);
+        // Floyd's algorithm
+        let mut c = Vec::with_capacity(k);
+        for j in (n - k)..n {
+            let x = rng.gen_range(0..(j + 1));
+            if c.contains(&x) { c.push(j) } else { c.push(x) }
+        }
+        c.sort();
+        c
+    }
+}
+
+/// The distribution over combinations of `k` indices of `weights`, in
+/// increasing order, drawn one by one without replacement, each with
+/// probability proportional to its weight among the remaining ones.
+///
+/// This is the method of Efraimidis and Spirakis: every index `i` gets the
+/// key `u^(1/w_i)` for a uniform `u` in `(0, 1)`, and the `k` largest keys
+/// win.
+#[derive(Clone, Debug)]
+pub struct WeightedCombination {
+    pub weights: Vec<f64>,
+    pub k: usize,
+}
+
+impl WeightedCombination {
+    /// # Panics
+    ///
+    /// If a weight is negative or not finite, or fewer than `k` weights are
+    /// positive.
+    pub fn new(weights: Vec<f64>, k: usize) -> WeightedCombination {
+        assert!(weights.iter().all(|&w| { w >= 0.0 && w.is_finite() }), "Invalid weight");
+        let positive = weights.iter().filter(|&&w| { w > 0.0 }).count();
+        assert!(k <= positive, "Cannot choose {} elements out of {}", k, positive);
+        WeightedCombination { weights: weights, k: k }
+    }
+}
+
+impl Distribution<Vec<usize>> for WeightedCombination {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<usize> {
+        // Compare the logarithms of the keys, ln(u) / w, to avoid underflow
+        let mut keys = self.weights.iter().enumerate()
+            .filter(|&(_, &w)| { w > 0.0 })
+            .map(|(i, &w)| { ((1.0 - rng.gen::<f64>()).ln() / w, i) })
+            .collect::<Vec<(f64, usize)>>();
+        let k = self.k;
+        if k == 0 { return Vec::new() }
+        keys.select_nth_unstable_by(k - 1, |x, y| { y.0.partial_cmp(&x.0).unwrap() });
+        let mut c = keys[..k].iter().map(|&(_, i)| { i }).collect::<Vec<usize>>();
+        c.sort();
+        c
+    }
+}
+
+/// The uniform distribution over subsequences of `0..n`.
+#[derive(Clone, Copy, Debug)]
+pub struct UniformSubsequence(pub usize);
+
+impl Distribution<Vec<usize>> for UniformSubsequence {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<usize> {
+        (0..self.0).filter(|_| { rng.gen() }).collect()
+    }
+}
+
+/// The uniform distribution over binary trees with `n` leaves, `n > 0`, in
+/// the encoding of `Catalan`.
+///
+/// This is Rémy's algorithm: trees are grown from a single leaf, by
+/// replacing a uniform node with a new internal node, whose children are
+/// that node and a new leaf, on a uniform side.
+#[derive(Clone, Copy, Debug)]
+pub struct UniformBinaryTree(pub usize);
+
+impl Distribution<Vec<usize>> for UniformBinaryTree {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<usize> {
+        let n = self.0;
+        assert!(n > 0, "No binary tree without leaves");
+        // Children of the internal nodes, and parents, with NONE for leaves
+        // and the root respectively
+        const NONE: usize = usize::MAX;
+        let mut children = vec![(NONE, NONE); 2 * n - 1];
+        let mut parent = vec![NONE; 2 * n - 1];
+        let mut root = 0;
+        for k in 1..n {
+            let u = rng.gen_range(0..(2 * k - 1));
+            let (v, leaf) = (2 * k - 1, 2 * k);
+            children[v] = if rng.gen() { (u, leaf) } else { (leaf, u) };
+            parent[v] = parent[u];
+            parent[u] = v;
+            parent[leaf] = v;
+            match parent[v] {
+                NONE => root = v,
+                p => if children[p].0 == u { children[p].0 = v } else { children[p].1 = v },
+            }
+        }
+        // Label the internal nodes in preorder with their leftmost leaves
+        let mut tree = Vec::with_capacity(n - 1);
+        let mut leaves = 0;
+        let mut stack = vec![root];
+        while let Some(u) = stack.pop() {
+            match children[u] {
+                (NONE, _) => leaves += 1,
+                (l, r) => {
+                    tree.push(leaves);
+                    stack.push(r);
+                    stack.push(l);
+                }
+            }
+        }
+        tree
+    }
+}
+
+/// The uniform distribution over partitions of the set `0..n`, as
+/// restricted growth strings: the `i`-th element is the index of the block
+/// containing `i`, the blocks being numbered in the order of their smallest
+/// elements.
+///
+/// This is Stam's algorithm: a number of urns `k` is drawn with probability
+/// `k^n / (e B_n k!)`, where `B_n` is the Bell number, and the elements are
+/// thrown into uniform urns; the nonempty urns are the blocks. The law of
+/// `k` is computed in floating point, hence "uniform" up to rounding.
+#[derive(Clone, Copy, Debug)]
+pub struct UniformSetPartition(pub usize);
+
+impl Distribution<Vec<usize>> for UniformSetPartition {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<usize> {
+        let n = self.0;
+        if n == 0 { return Vec::new() }
+        // The logarithms of the weights k^n / k!, until they are negligible
+        // past k = n, where they start decreasing
+        let mut weights: Vec<f64> = Vec::new();
+        let (mut log_factorial, mut max) = (0.0, f64::NEG_INFINITY);
+        for k in 1.. {
+            log_factorial += (k as f64).ln();
+            let w = n as f64 * (k as f64).ln() - log_factorial;
+            if k > n && w < max - 50.0 { break }
+            if w > max { max = w }
+            weights.push(w);
+        }
+        let total = weights.iter().map(|&w| { (w - max).exp() }).sum::<f64>();
+        let mut u = rng.gen::<f64>() * total;
+        let mut urns = weights.len();
+        for (k, &w) in weights.iter().enumerate() {
+            u -= (w - max).exp();
+            if u < 0.0 { urns = k + 1; break }
+        }
+        // Throw the elements, and number the urns by first use
+        let mut block = vec![usize::MAX; urns];
+        let mut blocks = 0;
+        (0..n).map(|_| {
+            let b = &mut block[rng.gen_range(0..urns)];
+            if *b == usize::MAX { *b = blocks; blocks += 1 }
+            *b
+        }).collect()
+    }
+}
+
+/// The Markov chain of Jacobson and Matthews on Latin squares of order `n`,
+/// whose limit distribution is uniform.
+///
+/// A Latin square is seen as a cube of `0`s and `1`s, where the cell
+/// `(r, c, s)` is `1` when the symbol `s` is at row `r` and column `c`, so
+/// that every line of the cube sums to `1`. A move adds `1` to a cell and
+/// fixes the lines through it, which may leave one cell at `-1`: the square
+/// is then improper, until a later move fixes it.
+// cube[(r * n + c) * n + s] is the cell (r, c, s). improper is the cell at
+// -1, if any.
+#[derive(Clone, Debug)]
+pub struct JacobsonMatthews {
+    n: usize,
+    cube: Vec<i8>,
+    improper: Option<(usize, usize, usize)>,
+}
+
+impl JacobsonMatthews {
+    /// Start from the cyclic square, whose entry at `(r, c)` is
+    /// `(r + c) % n`.
+    pub fn new(n: usize) -> JacobsonMatthews {
+        let mut cube = vec![0; n * n * n];
+        for r in 0..n {
+            for c in 0..n {
+                cube[(r * n + c) * n + (r + c) % n] = 1;
+            }
+        }
+        JacobsonMatthews { n: n, cube: cube, improper: None }
+    }
+
+    /// Whether the current state is a Latin square.
+    pub fn is_proper(&self) -> bool {
+        self.improper.is_none()
+    }
+
+    /// The current square, as its rows, if it is proper.
+    pub fn square(&self) -> Option<Vec<Vec<usize>>> {
+        if !self.is_proper() { return None }
+        let n = self.n;
+        Some((0..n).map(|r| {
+            (0..n).map(|c| {
+                (0..n).find(|&s| { self.cube[(r * n + c) * n + s] == 1 }).unwrap()
+            }).collect()
+        }).collect())
+    }
+
+    /// Make one move.
+    pub fn step<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let n = self.n;
+        if n < 2 { return }
+        let at = |r: usize, c: usize, s: usize| { (r * n + c) * n + s };
+        let (r, c, s) = match self.improper {
+            Some(cell) => cell,
+            None => {
+                // A uniform cell at 0
+                let (r, c) = (rng.gen_range(0..n), rng.gen_range(0..n));
+                let s = (0..n).filter(|&s| { self.cube[at(r, c, s)] == 0 })
+                    .nth(rng.gen_range(0..(n - 1))).unwrap();
+                (r, c, s)
+            }
+        };
+        // The cells at 1 on the lines through (r, c, s): one on each if the
+        // square is proper, two otherwise
+        let cube = &self.cube;
+        let mut pick = |cells: Vec<usize>| { cells[rng.gen_range(0..cells.len())] };
+        let r1 = pick((0..n).filter(|&x| { cube[at(x, c, s)] == 1 }).collect());
+        let c1 = pick((0..n).filter(|&x| { cube[at(r, x, s)] == 1 }).collect());
+        let s1 = pick((0..n).filter(|&x| { cube[at(r, c, x)] == 1 }).collect());
+        let cube = &mut self.cube;
+        cube[at(r, c, s)] += 1;
+        cube[at(r1, c, s)] -= 1;
+        cube[at(r, c1, s)] -= 1;
+        cube[at(r, c, s1)] -= 1;
+        cube[at(r, c1, s1)] += 1;
+        cube[at(r1, c, s1)] += 1;
+        cube[at(r1, c1, s)] += 1;
+        cube[at(r1, c1, s1)] -= 1;
+        self.improper = if cube[at(r1, c1, s1)] < 0 { Some((r1, c1, s1)) } else { None };
+    }
+}
+
+/// The approximately uniform distribution over Latin squares of order `n`,
+/// as their rows, by `n^3` moves of `JacobsonMatthews` from the cyclic
+/// square, and as many more as needed to reach a proper square.
+#[derive(Clone, Copy, Debug)]
+pub struct UniformLatinSquare(pub usize);
+
+impl Distribution<Vec<Vec<usize>>> for UniformLatinSquare {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<Vec<usize>> {
+        let mut chain = JacobsonMatthews::new(self.0);
+        for _ in 0..self.0.pow(3) { chain.step(rng) }
+        while !chain.is_proper() { chain.step(rng) }
+        chain.square().unwrap()
+    }
+}
+
+/// The uniform distribution over spanning trees of a connected graph, as
+/// their edges `(a, b)` with `a < b`, in lexicographic order.
+///
+/// This is Wilson's algorithm: from every vertex not yet in the tree, a
+/// random walk runs until it hits the tree, and its loop erasure joins it.
+#[derive(Clone, Copy, Debug)]
+pub struct UniformSpanningTree<'a>(pub &'a Graph);
+
+impl<'a> Distribution<Vec<(usize, usize)>> for UniformSpanningTree<'a> {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<(usize, usize)> {
+        let g = self.0;
+        let n = g.order();
+        if n == 0 { return Vec::new() }
+        // The walks would never end otherwise
+        let mut reached = 1u64;
+        let mut todo = vec![0];
+        while let Some(v) = todo.pop() {
+            let mut new = g.neighbors(v) & !reached;
+            reached |= new;
+            while new != 0 {
+                todo.push(new.trailing_zeros() as usize);
+                new &= new - 1;
+            }
+        }
+        assert!(reached.count_ones() as usize == n, "Disconnected graph");
+        // next[v] is the last exit of the walk from v
+        let mut in_tree = vec![false; n];
+        let mut next = vec![0; n];
+        in_tree[0] = true;
+        for start in 1..n {
+            let mut v = start;
+            while !in_tree[v] {
+                let neighbors = g.neighbors(v);
+                let mut r = rng.gen_range(0..neighbors.count_ones());
+                let mut mask = neighbors;
+                while r > 0 { mask &= mask - 1; r -= 1 }
+                next[v] = mask.trailing_zeros() as usize;
+                v = next[v];
+            }
+            let mut v = start;
+            while !in_tree[v] {
+                in_tree[v] = true;
+                v = next[v];
+            }
+        }
+        let mut edges = (1..n).map(|v| { (cmp::min(v, next[v]), cmp::max(v, next[v])) })
+            .collect::<Vec<(usize, usize)>>();
+        edges.sort();
+        edges
+    }
+}
+
+/// The uniform distribution over partitions of `n` into exactly `parts`
+/// parts.
+///
+/// The smallest part of such a partition is either 1, the rest being a
+/// partition of `n - 1` into `parts - 1` parts, or greater, and then
+/// subtracting 1 from every part gives a partition of `n - parts` into
+/// `parts` parts. The two cases are drawn in proportion to their numbers of
+/// partitions, precomputed.
+#[derive(Clone, Debug)]
+pub struct UniformPartition {
+    n: usize,
+    parts: usize,
+    // counts[i][j] is the number of partitions of i into j parts
+    counts: Vec<Vec<u128>>,
+}
+
+impl UniformPartition {
+    /// # Panics
+    ///
+    /// If there is no such partition, or on overflow.
+    pub fn new(n: usize, parts: usize) -> UniformPartition {
+        let mut counts = vec![vec![0u128; parts + 1]; n + 1];
+        counts[0][0] = 1;
+        for i in 1..(n + 1) {
+            for j in 1..(cmp::min(i, parts) + 1) {
+                counts[i][j] = counts[i - 1][j - 1].checked_add(counts[i - j][j])
+                    .expect("Overflow");
+            }
+        }
+        assert!(counts[n][parts] > 0, "No partition of {} into {} parts", n, parts);
+        UniformPartition { n: n, parts: parts, counts: counts }
+    }
+
+    /// The number of partitions of `n` into `parts` parts.
+    pub fn count(&self) -> u128 {
+        self.counts[self.n][self.parts]
+    }
+}
+
+impl Distribution<Partition> for UniformPartition {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Partition {
+        let (mut i, mut j) = (self.n, self.parts);
+        let mut parts = Vec::with_capacity(j);
+        // The number of times 1 was subtracted from every part
+        let mut subtracted = 0;
+        while j > 0 {
+            if rng.gen_range(0..self.counts[i][j]) < self.counts[i - 1][j - 1] {
+                parts.push(1 + subtracted);
+                i -= 1;
+                j -= 1;
+            } else {
+                subtracted += 1;
+                i -= j;
+            }
+        }
+        Partition::from_parts(parts)
+    }
+}
+
+/// Draw `k` independent uniform objects from every stratum `s` in `strata`,
+/// where `dist(s)` is the uniform distribution over the stratum, stratum by
+/// stratum.
+///
+/// Unlike uniform sampling over the union of the strata, which concentrates
+/// on the largest ones, this covers every stratum equally.
+pub fn stratified<R, T, D, F>(rng: &mut R, strata: ops::Range<usize>, k: usize, dist: F) -> Vec<T> where
+    R: Rng + ?Sized,
+    D: Distribution<T>,
+    F: Fn(usize) -> D,
+{
+    let mut samples = Vec::with_capacity(strata.len() * k);
+    for s in strata {
+        let d = dist(s);
+        for _ in 0..k { samples.push(d.sample(rng)) }
+    }
+    samples
+}
+
+/// `k` uniform combinations of every size from `0` to `n`, in `0..n`, by
+/// increasing size.
+pub fn stratified_combinations<R: Rng + ?Sized>(rng: &mut R, n: usize, k: usize) -> Vec<Vec<usize>> {
+    stratified(rng, 0..(n + 1), k, |size| { UniformCombination { n: n, k: size } })
+}
+
+/// `k` uniform partitions of `n` with every number of parts, by increasing
+/// number of parts (from 1, or 0 if `n` is 0).
+pub fn stratified_partitions<R: Rng + ?Sized>(rng: &mut R, n: usize, k: usize) -> Vec<Partition> {
+    let first = if n == 0 { 0 } else { 1 };
+    stratified(rng, first..(n + 1), k, |parts| { UniformPartition::new(n, parts) })
+}
+
+/// An endless stream of independent uniform permutations of `0..n`.
+#[derive(Clone, Debug)]
+pub struct RandomPermutations<R> {
+    n: usize,
+    rng: R,
+}
+
+impl<R: Rng> RandomPermutations<R> {
+    pub fn new(n: usize, rng: R) -> RandomPermutations<R> {
+        RandomPermutations { n: n, rng: rng }
+    }
+}
+
+impl<R: Rng> Iterator for RandomPermutations<R> {
+    type Item = Permutation;
+    fn next(&mut self) -> Option<Permutation> {
+        Some(self.rng.sample(UniformPermutation(self.n)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+/// An endless stream of independent uniform combinations of `k` indices in
+/// `0..n`.
+#[derive(Clone, Debug)]
+pub struct RandomCombinations<R> {
+    dist: UniformCombination,
+    rng: R,
+}
+
+impl<R: Rng> RandomCombinations<R> {
+    pub fn new(n: usize, k: usize, rng: R) -> RandomCombinations<R> {
+        assert!(k <= n, "Cannot choose {} elements out of {}", k, n);
+        RandomCombinations { dist: UniformCombination { n: n, k: k }, rng: rng }
+    }
+}
+
+impl<R: Rng> Iterator for RandomCombinations<R> {
+    type Item = Vec<usize>;
+    fn next(&mut self) -> Option<Vec<usize>> {
+        Some(self.rng.sample(self.dist))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+/// An endless stream of independent weighted combinations, see
+/// `WeightedCombination`.
+#[derive(Clone, Debug)]
+pub struct RandomWeightedCombinations<R> {
+    dist: WeightedCombination,
+    rng: R,
+}
+
+impl<R: Rng> RandomWeightedCombinations<R> {
+    pub fn new(weights: Vec<f64>, k: usize, rng: R) -> RandomWeightedCombinations<R> {
+        RandomWeightedCombinations { dist: WeightedCombination::new(weights, k), rng: rng }
+    }
+}
+
+impl<R: Rng> Iterator for RandomWeightedCombinations<R> {
+    type Item = Vec<usize>;
+    fn next(&mut self) -> Option<Vec<usize>> {
+        Some(self.rng.sample(&self.dist))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+/// An endless stream of independent uniform subsequences of `0..n`.
+#[derive(Clone, Debug)]
+pub struct RandomSubsequences<R> {
+    n: usize,
+    rng: R,
+}
+
+impl<R: Rng> RandomSubsequences<R> {
+    pub fn new(n: usize, rng: R) -> RandomSubsequences<R> {
+        RandomSubsequences { n: n, rng: rng }
+    }
+}
+
+impl<R: Rng> Iterator for RandomSubsequences<R> {
+    type Item = Vec<usize>;
+    fn next(&mut self) -> Option<Vec<usize>> {
+        Some(self.rng.sample(UniformSubsequence(self.n)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
@@ -0,0 +1,694 @@
+//! Ranking and unranking of combinatorial objects
+//!
+//! The rank of an object is its position in the order in which the
+//! corresponding iterator of `iter` enumerates it, starting from 0.
+//!
+//! Ranks and cardinalities quickly overflow machine integers, e.g.,
+//! `choose(100, 50)` and `factorial(25)` do not fit in a `u64`. The functions
+//! suffixed with `_as` compute them in any type implementing `Rank`: `u64`,
+//! `u128`, `usize`, and `num_bigint::BigUint` with the `num-bigint` feature.
+
+use std::*;
+use std::io::Write;
+use iter::{StreamingIterator, Snapshot};
+use error::{Error, Result};
+#[cfg(feature = "num-bigint")]
+use num_bigint::BigUint;
+
+/// Integer types for ranks and cardinalities.
+///
+/// All operations panic on overflow, and on negative results.
+pub trait Rank: Clone + Ord + fmt::Debug + fmt::Display {
+    /// # Panics
+    ///
+    /// If `x` does not fit.
+    fn from_usize(x: usize) -> Self;
+
+    /// The value, if it fits in a `usize`.
+    fn to_usize(&self) -> Option<usize>;
+
+    fn plus(&self, other: &Self) -> Self;
+
+    fn minus(&self, other: &Self) -> Self;
+
+    fn times(&self, x: usize) -> Self;
+
+    /// The quotient, rounded down.
+    fn divided_by(&self, x: usize) -> Self;
+
+    /// The quotient, rounded down, and the remainder.
+    fn div_rem(&self, other: &Self) -> (Self, Self);
+
+    /// `2^e`.
+    fn power_of_two(e: usize) -> Self;
+}
+
+macro_rules! impl_rank {
+    ($($t:ty),*) => { $(
+        impl Rank for $t {
+            fn from_usize(x: usize) -> $t {
+                <$t as convert::TryFrom<usize>>::try_from(x).expect("Overflow")
+            }
+
+            fn to_usize(&self) -> Option<usize> {
+                <usize as convert::TryFrom<$t>>::try_from(*self).ok()
+            }
+
+            fn plus(&self, other: &$t) -> $t {
+                self.checked_add(*other).expect("Overflow")
+            }
+
+            fn minus(&self, other: &$t) -> $t {
+                self.checked_sub(*other).expect("Negative rank")
+            }
+
+            fn times(&self, x: usize) -> $t {
+                self.checked_mul(<$t as Rank>::from_usize(x)).expect("Overflow")
+            }
+
+            fn divided_by(&self, x: usize) -> $t {
+                *self / <$t as Rank>::from_usize(x)
+            }
+
+            fn div_rem(&self, other: &$t) -> ($t, $t) {
+                (*self / *other, *self % *other)
+            }
+
+            fn power_of_two(e: usize) -> $t {
+                <u32 as convert::TryFrom<usize>>::try_from(e).ok().and_then(|e| { (1 as $t).checked_shl(e) })
+                    .expect("Overflow")
+            }
+        }
+    )* }
+}
+
+impl_rank!(usize, u64, u128);
+
+#[cfg(feature = "num-bigint")]
+impl Rank for BigUint {
+    fn from_usize(x: usize) -> BigUint {
+        BigUint::from(x)
+    }
+
+    fn to_usize(&self) -> Option<usize> {
+        <usize as convert::TryFrom<&BigUint>>::try_from(self).ok()
+    }
+
+    fn plus(&self, other: &BigUint) -> BigUint {
+        self + other
+    }
+
+    fn minus(&self, other: &BigUint) -> BigUint {
+        assert!(self >= other, "Negative rank");
+        self - other
+    }
+
+    fn times(&self, x: usize) -> BigUint {
+        self * BigUint::from(x)
+    }
+
+    fn divided_by(&self, x: usize) -> BigUint {
+        self / BigUint::from(x)
+    }
+
+    fn div_rem(&self, other: &BigUint) -> (BigUint, BigUint) {
+        (self / other, self % other)
+    }
+
+    fn power_of_two(e: usize) -> BigUint {
+        BigUint::from(1u32) << e
+    }
+}
+
+/// Binomial coefficient: the number of combinations of `k` elements out of `n`.
+pub fn choose(n: usize, k: usize) -> usize {
+    choose_as(n, k)
+}
+
+/// Same as `choose`, in any `Rank` type.
+pub fn choose_as<R: Rank>(n: usize, k: usize) -> R {
+    if k > n { return R::from_usize(0) }
+    let k = cmp::min(k, n - k);
+    // Each intermediate product is divisible by i + 1
+    (0..k).fold(R::from_usize(1), |c, i| { c.times(n - i).divided_by(i + 1) })
+}
+
+/// Same as `choose`, or `Error::Overflow` if the result does not fit in a
+/// `usize`.
+pub fn try_choose(n: usize, k: usize) -> Result<usize> {
+    if k > n { return Ok(0) }
+    let k = cmp::min(k, n - k);
+    // The intermediate products are at most the result times n
+    (0..k).try_fold(1usize, |c, i| {
+        let c = c as u128 * (n - i) as u128 / (i + 1) as u128;
+        <usize as convert::TryFrom<u128>>::try_from(c).map_err(|_| { Error::Overflow })
+    })
+}
+
+/// The number of permutations of `n` elements.
+pub fn factorial(n: usize) -> usize {
+    factorial_as(n)
+}
+
+/// Same as `factorial`, or `Error::Overflow` if the result does not fit in
+/// a `usize`.
+pub fn try_factorial(n: usize) -> Result<usize> {
+    (2..(n + 1)).try_fold(1usize, |f, i| { f.checked_mul(i).ok_or(Error::Overflow) })
+}
+
+/// Same as `factorial`, in any `Rank` type.
+pub fn factorial_as<R: Rank>(n: usize) -> R {
+    (2..(n + 1)).fold(R::from_usize(1), |f, i| { f.times(i) })
+}
+
+/// Rank of a combination of indices in `0..n`, in lexicographic order.
+pub fn combination_rank(n: usize, c: &[usize]) -> usize {
+    combination_rank_with(choose, n, c)
+}
+
+/// Same as `combination_rank`, in any `Rank` type.
+pub fn combination_rank_as<R: Rank>(n: usize, c: &[usize]) -> R {
+    combination_rank_with(choose_as, n, c)
+}
+
+/// Write into `dest` the combination of `k` indices in `0..n` with rank `r`
+/// in lexicographic order.
+///
+/// # Panics
+///
+/// If `r >= choose(n, k)`.
+pub fn combination_unrank(n: usize, k: usize, r: usize, dest: &mut Vec<usize>) {
+    combination_unrank_with(choose, n, k, r, dest)
+}
+
+/// Same as `combination_unrank`, but return an error instead of panicking.
+pub fn try_combination_unrank(n: usize, k: usize, r: usize, dest: &mut Vec<usize>) -> Result<()> {
+    if k > n { return Err(Error::InvalidK { n: n, k: k }) }
+    if r >= try_choose(n, k)? { return Err(Error::RankOutOfRange) }
+    combination_unrank(n, k, r, dest);
+    Ok(())
+}
+
+/// Same as `combination_unrank`, in any `Rank` type.
+pub fn combination_unrank_as<R: Rank>(n: usize, k: usize, r: &R, dest: &mut Vec<usize>) {
+    combination_unrank_with(choose_as, n, k, r.clone(), dest)
+}
+
+/// Pascal's triangle, precomputed up to a given size.
+///
+/// It takes `O(n k)` memory, to rank and unrank many combinations with
+/// constant-time binomial coefficients, whereas `rank::combination_rank`
+/// and `rank::combination_unrank` compute them in constant memory.
+///
+/// Entries which do not fit in a `usize` saturate at `usize::MAX`.
+#[derive(Clone, Debug)]
+pub struct BinomialTable {
+    n: usize,
+    k: usize,
+    // choose(i, j) is at i * (k + 1) + j
+    table: Vec<usize>,
+}
+
+impl BinomialTable {
+    /// The table of `choose(i, j)` for `i <= n` and `j <= k`.
+    pub fn new(n: usize, k: usize) -> BinomialTable {
+        let w = k + 1;
+        let mut table = vec![0usize; (n + 1) * w];
+        for i in 0..(n + 1) {
+            table[i * w] = 1;
+            for j in 1..(cmp::min(i, k) + 1) {
+                table[i * w + j] =
+                    table[(i - 1) * w + j - 1].saturating_add(table[(i - 1) * w + j]);
+            }
+        }
+        BinomialTable { n: n, k: k, table: table }
+    }
+
+    /// Binomial coefficient, in constant time.
+    ///
+    /// # Panics
+    ///
+    /// If `n` or `k` is out of the bounds of the table.
+    pub fn choose(&self, n: usize, k: usize) -> usize {
+        assert!(n <= self.n && k <= self.k, "Out of the bounds of the table");
+        self.table[n * (self.k + 1) + k]
+    }
+
+    /// Same as `rank::combination_rank`, for `n` and `c.len()` within the
+    /// bounds of the table.
+    pub fn combination_rank(&self, n: usize, c: &[usize]) -> usize {
+        combination_rank_with(|n, k| { self.choose(n, k) }, n, c)
+    }
+
+    /// Same as `rank::combination_unrank`, for `n` and `k` within the bounds
+    /// of the table.
+    pub fn combination_unrank(&self, n: usize, k: usize, r: usize,
+                              dest: &mut Vec<usize>) {
+        combination_unrank_with(|n, k| { self.choose(n, k) }, n, k, r, dest)
+    }
+
+    /// Write the table in a binary format, to be read back with `read_from`
+    /// or memory-mapped with `MappedBinomialTable`: a header of
+    /// `TABLE_MAGIC`, `n` and `k`, followed by the entries, all as 64-bit
+    /// little-endian integers.
+    pub fn write_to<W: io::Write>(&self, out: W) -> io::Result<()> {
+        let mut out = io::BufWriter::new(out);
+        out.write_all(TABLE_MAGIC)?;
+        for &x in [self.n, self.k].iter().chain(&self.table) {
+            out.write_all(&(x as u64).to_le_bytes())?;
+        }
+        out.flush()
+    }
+
+    /// Read a table written by `write_to`.
+    pub fn read_from<R: io::Read>(mut input: R) -> io::Result<BinomialTable> {
+        let mut bytes = Vec::new();
+        input.read_to_end(&mut bytes)?;
+        let (n, k) = table_header(&bytes)?;
+        let table = bytes[TABLE_HEADER..].chunks(8).map(|b| { read_entry(b) }).collect();
+        Ok(BinomialTable { n: n, k: k, table: table })
+    }
+}
+
+fn combination_rank_with<R: Rank, F: Fn(usize, usize) -> R>(choose: F, n: usize, c: &[usize]) -> R {
+    let k = c.len();
+    let mut r = R::from_usize(0);
+    let mut j = 0;
+    for (i, &x) in c.iter().enumerate() {
+        for y in j..x { r = r.plus(&choose(n - y - 1, k - i - 1)) }
+        j = x + 1;
+    }
+    r
+}
+
+fn combination_unrank_with<R: Rank, F>(choose: F, n: usize, k: usize, mut r: R,
+                                       dest: &mut Vec<usize>) where
+    F: Fn(usize, usize) -> R
+{
+    assert!(r < choose(n, k), "Rank out of range");
+    dest.clear();
+    let mut x = 0;
+    for i in 0..k {
+        loop {
+            let c = choose(n - x - 1, k - i - 1);
+            if r < c { break }
+            r = r.minus(&c);
+            x += 1;
+        }
+        dest.push(x);
+        x += 1;
+    }
+}
+
+/// The first bytes of the files of binomial tables.
+pub const TABLE_MAGIC: &[u8; 8] = b"CRBINOM1";
+
+// The magic, n and k
+const TABLE_HEADER: usize = 24;
+
+// Check the header and the length of a table, and return its n and k
+fn table_header(bytes: &[u8]) -> io::Result<(usize, usize)> {
+    let invalid = |msg| { io::Error::new(io::ErrorKind::InvalidData, msg) };
+    if bytes.len() < TABLE_HEADER || &bytes[..8] != TABLE_MAGIC {
+        return Err(invalid("Not a binomial table"));
+    }
+    let (n, k) = (read_entry(&bytes[8..16]), read_entry(&bytes[16..24]));
+    let len = n.checked_add(1).and_then(|n1| { n1.checked_mul(k.checked_add(1)?) })
+        .and_then(|e| { e.checked_mul(8)?.checked_add(TABLE_HEADER) });
+    if len != Some(bytes.len()) { return Err(invalid("Truncated binomial table")) }
+    Ok((n, k))
+}
+
+fn read_entry(bytes: &[u8]) -> usize {
+    let mut b = [0; 8];
+    b.copy_from_slice(bytes);
+    u64::from_le_bytes(b) as usize
+}
+
+/// A `BinomialTable` memory-mapped from a file written by
+/// `BinomialTable::write_to`, with the `memmap2` feature.
+///
+/// Large tables are then built once, and shared by the processes which map
+/// them, without being read or recomputed at start-up.
+#[cfg(feature = "memmap2")]
+#[derive(Debug)]
+pub struct MappedBinomialTable {
+    n: usize,
+    k: usize,
+    map: ::memmap2::Mmap,
+}
+
+#[cfg(feature = "memmap2")]
+impl MappedBinomialTable {
+    /// Map the table stored in the file at `path`.
+    ///
+    /// The file must not be modified while it is mapped.
+    pub fn open<P: AsRef<path::Path>>(path: P) -> io::Result<MappedBinomialTable> {
+        let file = fs::File::open(path)?;
+        // Safety: the mapping is read-only, and the file must not be
+        // modified, as documented
+        let map = unsafe { ::memmap2::Mmap::map(&file)? };
+        let (n, k) = table_header(&map)?;
+        Ok(MappedBinomialTable { n: n, k: k, map: map })
+    }
+
+    /// Map the table stored at `path`, after building it and writing it
+    /// there if there is no such file yet.
+    ///
+    /// The existing table is used even if it is larger than needed, but
+    /// not if it is smaller: then it is rebuilt.
+    pub fn open_or_build<P: AsRef<path::Path>>(path: P, n: usize, k: usize)
+        -> io::Result<MappedBinomialTable>
+    {
+        if let Ok(t) = MappedBinomialTable::open(&path) {
+            if t.n >= n && t.k >= k { return Ok(t) }
+        }
+        // Write to a temporary file, renamed at the end, so that concurrent
+        // workers never map a partial table
+        let mut tmp = path.as_ref().to_path_buf().into_os_string();
+        tmp.push(format!(".{}.tmp", process::id()));
+        BinomialTable::new(n, k).write_to(fs::File::create(&tmp)?)?;
+        fs::rename(&tmp, &path)?;
+        MappedBinomialTable::open(path)
+    }
+
+    /// Same as `BinomialTable::choose`.
+    ///
+    /// # Panics
+    ///
+    /// If `n` or `k` is out of the bounds of the table.
+    pub fn choose(&self, n: usize, k: usize) -> usize {
+        assert!(n <= self.n && k <= self.k, "Out of the bounds of the table");
+        let i = TABLE_HEADER + 8 * (n * (self.k + 1) + k);
+        read_entry(&self.map[i..(i + 8)])
+    }
+
+    /// Same as `rank::combination_rank`, for `n` and `c.len()` within the
+    /// bounds of the table.
+    pub fn combination_rank(&self, n: usize, c: &[usize]) -> usize {
+        combination_rank_with(|n, k| { self.choose(n, k) }, n, c)
+    }
+
+    /// Same as `rank::combination_unrank`, for `n` and `k` within the bounds
+    /// of the table.
+    pub fn combination_unrank(&self, n: usize, k: usize, r: usize,
+                              dest: &mut Vec<usize>) {
+        combination_unrank_with(|n, k| { self.choose(n, k) }, n, k, r, dest)
+    }
+}
+
+/// Rank of a subsequence of `0..n`, in the order of `IndexSubsequences`.
+pub fn subsequence_rank(n: usize, s: &[usize]) -> usize {
+    subsequence_rank_as(n, s)
+}
+
+/// Same as `subsequence_rank`, in any `Rank` type.
+pub fn subsequence_rank_as<R: Rank>(n: usize, s: &[usize]) -> R {
+    let mut r = R::from_usize(0);
+    let mut j = 0;
+    for &x in s {
+        // Skip the parent node, and the subtrees rooted at j, ..., x-1, one
+        // by one: their sizes are at most the rank, so they cannot
+        // overflow unless it does
+        r = r.plus(&R::from_usize(1));
+        for y in j..x { r = r.plus(&R::power_of_two(n - y - 1)) }
+        j = x + 1;
+    }
+    r
+}
+
+/// Write into `dest` the subsequence of `0..n` with rank `r` in the order of
+/// `IndexSubsequences`.
+///
+/// # Panics
+///
+/// If `r >= 2^n`.
+pub fn subsequence_unrank(n: usize, r: usize, dest: &mut Vec<usize>) {
+    subsequence_unrank_as(n, &r, dest)
+}
+
+/// Same as `subsequence_unrank`, but return an error instead of panicking.
+/// All ranks are valid when `2^n` does not fit in a `usize`.
+pub fn try_subsequence_unrank(n: usize, r: usize, dest: &mut Vec<usize>) -> Result<()> {
+    if !is_below_power_of_two(&r, n) { return Err(Error::RankOutOfRange) }
+    subsequence_unrank(n, r, dest);
+    Ok(())
+}
+
+/// Same as `subsequence_unrank`, in any `Rank` type.
+pub fn subsequence_unrank_as<R: Rank>(n: usize, r: &R, dest: &mut Vec<usize>) {
+    let zero = R::from_usize(0);
+    let one = R::from_usize(1);
+    assert!(is_below_power_of_two(r, n), "Rank out of range");
+    let mut r = r.clone();
+    dest.clear();
+    let mut x = 0;
+    while r > zero {
+        r = r.minus(&one);
+        loop {
+            if is_below_power_of_two(&r, n - 1 - x) { break }
+            r = r.minus(&R::power_of_two(n - 1 - x));
+            x += 1;
+        }
+        dest.push(x);
+        x += 1;
+    }
+}
+
+// r < 2^e, without computing 2^e, which may overflow
+fn is_below_power_of_two<R: Rank>(r: &R, e: usize) -> bool {
+    let zero = R::from_usize(0);
+    let mut r = r.clone();
+    for _ in 0..e {
+        if r == zero { return true }
+        r = r.divided_by(2);
+    }
+    r == zero
+}
+
+/// Rank of a permutation of `0..n`, in lexicographic order.
+pub fn permutation_rank(p: &[usize]) -> usize {
+    permutation_rank_as(p)
+}
+
+/// Same as `permutation_rank`, in any `Rank` type.
+pub fn permutation_rank_as<R: Rank>(p: &[usize]) -> R {
+    let n = p.len();
+    // Horner's scheme in the factorial number system
+    let mut r = R::from_usize(0);
+    for (i, &x) in p.iter().enumerate() {
+        let smaller = p[i + 1..].iter().filter(|&&y| { y < x }).count();
+        r = r.times(n - i).plus(&R::from_usize(smaller));
+    }
+    r
+}
+
+/// Write into `dest` the permutation of `0..n` with rank `r` in
+/// lexicographic order.
+///
+/// # Panics
+///
+/// If `r >= n!`.
+pub fn permutation_unrank(n: usize, r: usize, dest: &mut Vec<usize>) {
+    permutation_unrank_as(n, &r, dest)
+}
+
+/// Same as `permutation_unrank`, but return an error instead of panicking.
+/// All ranks are valid when `n!` does not fit in a `usize`, up to `n = 34`.
+pub fn try_permutation_unrank(n: usize, r: usize, dest: &mut Vec<usize>) -> Result<()> {
+    let f = (2..(n + 1)).try_fold(1u128, |f, i| { f.checked_mul(i as u128) })
+        .ok_or(Error::Overflow)?;
+    if r as u128 >= f { return Err(Error::RankOutOfRange) }
+    permutation_unrank_as(n, &(r as u128), dest);
+    Ok(())
+}
+
+/// Same as `permutation_unrank`, in any `Rank` type.
+pub fn permutation_unrank_as<R: Rank>(n: usize, r: &R, dest: &mut Vec<usize>) {
+    assert!(*r < factorial_as(n), "Rank out of range");
+    let mut remaining = (0..n).collect::<Vec<usize>>();
+    let mut r = r.clone();
+    dest.clear();
+    for i in 0..n {
+        let (q, m) = r.div_rem(&factorial_as(n - 1 - i));
+        dest.push(remaining.remove(q.to_usize().unwrap()));
+        r = m;
+    }
+}
+
+/// An iterator over the ranks `0..len`, each exactly once, in a
+/// pseudo-random order determined by `seed`.
+///
+/// The order is a bijection of the ranks, a Feistel network on the smallest
+/// domain of an even number of bits containing them, restricted to the
+/// ranks by cycle walking: the images out of range are mapped again.
+#[derive(Clone, Debug)]
+pub struct ShuffledRanks {
+    len: usize,
+    index: usize,
+    half_bits: u32,
+    keys: [u64; 4],
+}
+
+impl ShuffledRanks {
+    pub fn new(len: usize, seed: u64) -> ShuffledRanks {
+        let bits = 64 - (len.saturating_sub(1) as u64).leading_zeros();
+        let mut state = seed;
+        let mut keys = [0; 4];
+        for k in keys.iter_mut() { *k = splitmix64(&mut state) }
+        ShuffledRanks {
+            len: len,
+            index: 0,
+            half_bits: cmp::max(1, bits.div_ceil(2)),
+            keys: keys,
+        }
+    }
+
+    // The image of i by the bijection of the domain
+    fn permute(&self, i: u64) -> u64 {
+        let mask = (1u64 << self.half_bits) - 1;
+        let (mut l, mut r) = (i >> self.half_bits, i & mask);
+        for &key in &self.keys {
+            let mut state = r ^ key;
+            let f = splitmix64(&mut state) & mask;
+            let next = l ^ f;
+            l = r;
+            r = next;
+        }
+        l << self.half_bits | r
+    }
+}
+
+impl Iterator for ShuffledRanks {
+    type Item = usize;
+    fn next(&mut self) -> Option<usize> {
+        if self.index >= self.len { return None }
+        let mut x = self.permute(self.index as u64);
+        while x >= self.len as u64 { x = self.permute(x) }
+        self.index += 1;
+        Some(x as usize)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.len - self.index;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for ShuffledRanks {}
+
+// The SplitMix64 generator
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// An iterator over combinations, subsequences or permutations, each
+/// exactly once, in a pseudo-random order determined by a seed, by
+/// unranking `ShuffledRanks`.
+///
+/// The same seed gives the same order, so that randomized tests over large
+/// spaces remain reproducible.
+#[derive(Clone, Debug)]
+pub struct Shuffled {
+    ranks: ShuffledRanks,
+    space: Space,
+    rank: usize,
+    current: Vec<usize>,
+    first: bool,
+    done: bool,
+}
+
+#[derive(Clone, Debug)]
+enum Space {
+    Combinations(usize, usize, BinomialTable),
+    Subsequences(usize),
+    Permutations(usize),
+}
+
+impl Shuffled {
+    /// The combinations of `k` indices in `0..n`.
+    ///
+    /// # Panics
+    ///
+    /// If there are more than `usize::MAX` combinations.
+    pub fn combinations(n: usize, k: usize, seed: u64) -> Shuffled {
+        let len = try_choose(n, k).unwrap_or_else(|_| {
+            panic!("Cannot shuffle the combinations of {} elements out of {} by rank", k, n)
+        });
+        Shuffled::new(len, Space::Combinations(n, k, BinomialTable::new(n, k)), seed)
+    }
+
+    /// The subsequences of `0..n`.
+    ///
+    /// # Panics
+    ///
+    /// If there are more than `usize::MAX` subsequences, i.e., if `n` is at
+    /// least the number of bits of a `usize`.
+    pub fn subsequences(n: usize, seed: u64) -> Shuffled {
+        assert!(n < usize::BITS as usize,
+                "Cannot shuffle the subsequences of {} elements by rank", n);
+        Shuffled::new(1 << n, Space::Subsequences(n), seed)
+    }
+
+    /// The permutations of `0..n`.
+    ///
+    /// # Panics
+    ///
+    /// If there are more than `usize::MAX` permutations.
+    pub fn permutations(n: usize, seed: u64) -> Shuffled {
+        let len = try_factorial(n).unwrap_or_else(|_| {
+            panic!("Cannot shuffle the permutations of {} elements by rank", n)
+        });
+        Shuffled::new(len, Space::Permutations(n), seed)
+    }
+
+    fn new(len: usize, space: Space, seed: u64) -> Shuffled {
+        Shuffled {
+            ranks: ShuffledRanks::new(len, seed),
+            space: space,
+            rank: 0,
+            current: Vec::new(),
+            first: true,
+            done: false,
+        }
+    }
+
+    /// The rank of the current object, in the order of the corresponding
+    /// iterator of `iter`.
+    pub fn rank(&self) -> usize {
+        self.rank
+    }
+}
+
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for Shuffled {
+    type Item = [usize];
+    fn next(&mut self) -> Option<&[usize]> {
+        if self.done { return None }
+        self.first = false;
+        match self.ranks.next() {
+            None => { self.done = true; None }
+            Some(r) => {
+                self.rank = r;
+                match self.space {
+                    Space::Combinations(n, k, ref table) => {
+                        table.combination_unrank(n, k, r, &mut self.current)
+                    }
+                    Space::Subsequences(n) => subsequence_unrank(n, r, &mut self.current),
+                    Space::Permutations(n) => permutation_unrank(n, r, &mut self.current),
+                }
+                Some(&self.current)
+            }
+        }
+    }
+
+    fn get(&self) -> Option<&[usize]> {
+        if self.first || self.done { None } else { Some(&self.current) }
+    }
+}
+
+impl_snapshot!(Shuffled);
@@ -0,0 +1,67 @@
+//! Generic backtracking search
+//!
+//! A search problem is given by its partial solutions (states), the ways to
+//! extend each of them (children), and which of them are solutions. The
+//! tree of states is explored depth-first, and the solutions are output in
+//! preorder.
+
+use std::*;
+
+/// A backtracking search problem.
+pub trait Problem {
+    type State;
+
+    /// Push the extensions of `state` to `out`, in the order they should be
+    /// explored.
+    fn children(&self, state: &Self::State, out: &mut Vec<Self::State>);
+
+    /// Whether `state` is a solution. Solutions are still extended.
+    fn is_solution(&self, state: &Self::State) -> bool;
+
+    /// Whether `state` can be skipped, along with all its extensions,
+    /// because none of them is a solution. Checking this in `children`
+    /// instead is equivalent; this is only a convenience.
+    fn prune(&self, _state: &Self::State) -> bool {
+        false
+    }
+}
+
+/// An iterator over the solutions of a `Problem`, depth-first from a root
+/// state.
+#[derive(Clone, Debug)]
+pub struct Solutions<P: Problem> {
+    problem: P,
+    // States left to explore, the next one on top
+    stack: Vec<P::State>,
+}
+
+impl<P: Problem> Solutions<P> {
+    pub fn new(problem: P, root: P::State) -> Solutions<P> {
+        Solutions { problem: problem, stack: vec![root] }
+    }
+
+    /// The problem being solved.
+    pub fn problem(&self) -> &P {
+        &self.problem
+    }
+
+    /// The number of states waiting to be explored.
+    pub fn pending(&self) -> usize {
+        self.stack.len()
+    }
+}
+
+impl<P: Problem> Iterator for Solutions<P> {
+    type Item = P::State;
+    fn next(&mut self) -> Option<P::State> {
+        while let Some(state) = self.stack.pop() {
+            if self.problem.prune(&state) { continue }
+            let top = self.stack.len();
+            self.problem.children(&state, &mut self.stack);
+            // Pop the first child first
+            self.stack[top..].reverse();
+            if self.problem.is_solution(&state) { return Some(state) }
+        }
+        None
+    }
+}
@@ -0,0 +1,43 @@
+//! Serialization with serde, with the `serde` feature
+//!
+//! Objects are serialized as sequences of integers: a permutation in
+//! one-line notation, a partition as its parts in nonincreasing order.
+//! Deserialization rejects sequences which are not valid objects.
+
+use std::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error;
+use permutation::Permutation;
+use partition::Partition;
+
+impl Serialize for Permutation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_slice().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Permutation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Permutation, D::Error> {
+        let image = Vec::<usize>::deserialize(deserializer)?;
+        Permutation::from_vec(image).ok_or_else(|| { D::Error::custom("not a permutation") })
+    }
+}
+
+impl Serialize for Partition {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.parts().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Partition {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Partition, D::Error> {
+        let parts = Vec::<usize>::deserialize(deserializer)?;
+        let valid = parts.iter().all(|&x| { x > 0 }) &&
+            parts.windows(2).all(|w| { w[0] >= w[1] });
+        if valid {
+            Ok(Partition::from_parts(parts))
+        } else {
+            Err(D::Error::custom("not a nonincreasing sequence of positive parts"))
+        }
+    }
+}
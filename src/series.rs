@@ -0,0 +1,132 @@
+//! Formal power series truncated to a given degree
+//!
+//! The coefficients of products such as `Π (1 + x^i)` or `1/Π (1 - x^i)`
+//! count restricted partitions and compositions, which serves to check and
+//! precompute the lengths of the corresponding enumerations.
+
+use std::*;
+
+/// A power series with `u128` coefficients, up to degree `n` included.
+///
+/// All operations panic on overflow.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Series {
+    coefficients: Vec<u128>,
+}
+
+impl Series {
+    /// The series `1`, up to degree `n`.
+    pub fn one(n: usize) -> Series {
+        let mut coefficients = vec![0; n + 1];
+        coefficients[0] = 1;
+        Series { coefficients: coefficients }
+    }
+
+    /// The series with the given coefficients, of degrees `0` to
+    /// `coefficients.len() - 1`.
+    ///
+    /// # Panics
+    ///
+    /// If `coefficients` is empty.
+    pub fn from_coefficients(coefficients: Vec<u128>) -> Series {
+        assert!(!coefficients.is_empty(), "No coefficients");
+        Series { coefficients: coefficients }
+    }
+
+    /// `Π (1 + x^i)` for `i` in `1..n+1`, up to degree `n`: the numbers of
+    /// partitions into distinct parts.
+    pub fn distinct_partitions(n: usize) -> Series {
+        (1..(n + 1)).fold(Series::one(n), |s, i| { s.times_one_plus(i) })
+    }
+
+    /// `1/Π (1 - x^i)` for `i` in `1..n+1`, up to degree `n`: the numbers of
+    /// partitions.
+    pub fn partitions(n: usize) -> Series {
+        Series::partitions_with_parts(n, &(1..(n + 1)).collect::<Vec<usize>>())
+    }
+
+    /// `1/Π (1 - x^i)` for `i` in `parts`, up to degree `n`: the numbers of
+    /// partitions with parts in `parts`.
+    ///
+    /// # Panics
+    ///
+    /// If a part is `0`.
+    pub fn partitions_with_parts(n: usize, parts: &[usize]) -> Series {
+        parts.iter().fold(Series::one(n), |s, &i| { s.over_one_minus(i) })
+    }
+
+    /// `1/(1 - Σ x^i)` for `i` in `parts`, up to degree `n`: the numbers of
+    /// compositions with parts in `parts` (see `Compositions::with_parts`).
+    ///
+    /// # Panics
+    ///
+    /// If a part is `0`.
+    pub fn compositions_with_parts(n: usize, parts: &[usize]) -> Series {
+        assert!(parts.iter().all(|&i| { i > 0 }), "Part 0");
+        let mut s = Series::one(n);
+        for m in 1..(n + 1) {
+            s.coefficients[m] = parts.iter().filter(|&&i| { i <= m })
+                .fold(0u128, |c, &i| { add(c, s.coefficients[m - i]) });
+        }
+        s
+    }
+
+    /// The degree `n` up to which the series is known.
+    pub fn degree(&self) -> usize {
+        self.coefficients.len() - 1
+    }
+
+    /// The coefficient of `x^d`.
+    ///
+    /// # Panics
+    ///
+    /// If `d` is greater than the degree.
+    pub fn coefficient(&self, d: usize) -> u128 {
+        self.coefficients[d]
+    }
+
+    pub fn coefficients(&self) -> &[u128] {
+        &self.coefficients
+    }
+
+    /// The product, up to the smaller of the two degrees.
+    pub fn times(&self, other: &Series) -> Series {
+        let n = cmp::min(self.degree(), other.degree());
+        let coefficients = (0..(n + 1)).map(|d| {
+            (0..(d + 1)).fold(0u128, |c, i| {
+                let x = self.coefficients[i].checked_mul(other.coefficients[d - i])
+                    .expect("Overflow");
+                add(c, x)
+            })
+        }).collect();
+        Series { coefficients: coefficients }
+    }
+
+    /// Multiply by `1 + x^i`.
+    pub fn times_one_plus(mut self, i: usize) -> Series {
+        let n = self.degree();
+        // From the top, so that each coefficient is shifted only once
+        for d in (i..(n + 1)).rev() {
+            self.coefficients[d] = add(self.coefficients[d], self.coefficients[d - i]);
+        }
+        self
+    }
+
+    /// Divide by `1 - x^i`, i.e., multiply by `1 + x^i + x^2i + ...`.
+    ///
+    /// # Panics
+    ///
+    /// If `i` is `0`.
+    pub fn over_one_minus(mut self, i: usize) -> Series {
+        assert!(i > 0, "Division by 1 - x^0");
+        let n = self.degree();
+        for d in i..(n + 1) {
+            self.coefficients[d] = add(self.coefficients[d], self.coefficients[d - i]);
+        }
+        self
+    }
+}
+
+fn add(a: u128, b: u128) -> u128 {
+    a.checked_add(b).expect("Overflow")
+}
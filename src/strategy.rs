@@ -0,0 +1,37 @@
+//! Strategies for proptest, with the `proptest` feature
+//!
+//! The strategies are built from proptest's own combinators, so that they
+//! shrink within their domain: permutations toward the identity, subsets
+//! toward the empty set, partitions toward fewer parts.
+
+use std::*;
+use proptest::prelude::*;
+use proptest::{bits, collection};
+use bits::full_mask;
+use permutation::Permutation;
+use partition::Partition;
+
+/// Permutations of `0..n`.
+pub fn any_permutation(n: usize) -> impl Strategy<Value = Permutation> {
+    Just((0..n).collect::<Vec<usize>>()).prop_shuffle()
+        .prop_map(|image| { Permutation::from_vec(image).unwrap() })
+}
+
+/// Partitions of `n`.
+pub fn any_partition_of(n: usize) -> impl Strategy<Value = Partition> {
+    // Cut 1, 2, ..., n between the consecutive true positions
+    collection::vec(any::<bool>(), n.saturating_sub(1)).prop_map(move |cuts| {
+        let mut parts = Vec::new();
+        let mut part = 1;
+        for cut in cuts {
+            if cut { parts.push(part); part = 1 } else { part += 1 }
+        }
+        if n > 0 { parts.push(part) }
+        Partition::from_parts(parts)
+    })
+}
+
+/// Subsets of `0..n`, as masks (see `bits`).
+pub fn any_subset(n: usize) -> impl Strategy<Value = u64> {
+    bits::u64::masked(full_mask(n))
+}
@@ -0,0 +1,218 @@
+//! Young tableaux
+
+use std::*;
+use iter::{StreamingIterator, Snapshot};
+use partition::Partition;
+use permutation::Permutation;
+
+/// A semistandard Young tableau: rows of nonincreasing lengths, weakly
+/// increasing along the rows and strictly increasing down the columns.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Tableau {
+    rows: Vec<Vec<usize>>,
+}
+
+impl Tableau {
+    /// The tableau with the given rows, if it is semistandard. Empty rows
+    /// are not allowed.
+    pub fn from_rows(rows: Vec<Vec<usize>>) -> Option<Tableau> {
+        let shaped = rows.iter().all(|r| { !r.is_empty() }) &&
+            rows.windows(2).all(|w| { w[0].len() >= w[1].len() });
+        let increasing = rows.iter().all(|r| { r.windows(2).all(|w| { w[0] <= w[1] }) }) &&
+            rows.windows(2).all(|w| { w[1].iter().zip(&w[0]).all(|(x, y)| { x > y }) });
+        if shaped && increasing { Some(Tableau { rows: rows }) } else { None }
+    }
+
+    /// The rows.
+    pub fn rows(&self) -> &[Vec<usize>] {
+        &self.rows
+    }
+
+    /// The number of cells.
+    pub fn len(&self) -> usize {
+        self.rows.iter().map(|r| { r.len() }).sum()
+    }
+
+    /// Whether the tableau has no cells.
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// The shape: the partition of the lengths of the rows.
+    pub fn shape(&self) -> Partition {
+        Partition::from_parts(self.rows.iter().map(|r| { r.len() }).collect())
+    }
+
+    /// Whether the entries are `0..n`, each once, where `n` is the number
+    /// of cells.
+    pub fn is_standard(&self) -> bool {
+        let n = self.len();
+        let mut seen = vec![false; n];
+        self.rows.iter().flat_map(|r| { r.iter() }).all(|&x| {
+            x < n && !mem::replace(&mut seen[x], true)
+        })
+    }
+}
+
+/// The Robinson-Schensted correspondence: a bijection between permutations
+/// of `0..n` and pairs `(P, Q)` of standard tableaux of the same shape with
+/// `n` cells.
+///
+/// The images `p[0], p[1], ...` are inserted in turn in `P` by row
+/// bumping, and `Q` records the order in which the cells of `P` were
+/// created. The length of the first row is that of the longest increasing
+/// subsequences of `p`.
+pub fn rsk(p: &Permutation) -> (Tableau, Tableau) {
+    let mut ins: Vec<Vec<usize>> = Vec::new();
+    let mut rec: Vec<Vec<usize>> = Vec::new();
+    for (i, &x) in p.as_slice().iter().enumerate() {
+        let mut x = x;
+        let mut r = 0;
+        loop {
+            if r == ins.len() {
+                ins.push(vec![x]);
+                rec.push(vec![i]);
+                break;
+            }
+            // Bump the first entry greater than x
+            let c = ins[r].iter().position(|&y| { y > x });
+            match c {
+                None => {
+                    ins[r].push(x);
+                    rec[r].push(i);
+                    break;
+                }
+                Some(c) => {
+                    x = mem::replace(&mut ins[r][c], x);
+                    r += 1;
+                }
+            }
+        }
+    }
+    (Tableau { rows: ins }, Tableau { rows: rec })
+}
+
+/// The inverse of `rsk`: the permutation corresponding to the pair of
+/// tableaux, if they are standard and of the same shape.
+pub fn rsk_inverse(p: &Tableau, q: &Tableau) -> Option<Permutation> {
+    if !p.is_standard() || !q.is_standard() || p.shape() != q.shape() {
+        return None;
+    }
+    let mut ins = p.rows.clone();
+    let mut rec = q.rows.clone();
+    let mut image = vec![0; p.len()];
+    for i in (0..image.len()).rev() {
+        // The cell created last is a corner, with the largest entry of Q
+        let r = rec.iter().position(|row| { row.last() == Some(&i) }).unwrap();
+        rec[r].pop();
+        let mut x = ins[r].pop().unwrap();
+        if rec[r].is_empty() { rec.pop(); ins.pop(); }
+        // Bump back the last entry less than x in every row above
+        for row in ins[..r].iter_mut().rev() {
+            let c = row.iter().rposition(|&y| { y < x }).unwrap();
+            x = mem::replace(&mut row[c], x);
+        }
+        image[i] = x;
+    }
+    Permutation::from_vec(image)
+}
+
+/// An iterator over semistandard Young tableaux of a given shape with
+/// entries in `1..=m`: rows are weakly increasing, columns strictly
+/// increasing.
+///
+/// The tableaux are yielded as their rows, in lexicographic order of the
+/// entries read row by row. Every step takes time linear in the number of
+/// cells: each entry is bounded by what the cells after it need, so the
+/// search never reaches a dead end.
+#[derive(Clone, Debug)]
+pub struct SemistandardTableaux {
+    rows: Vec<Vec<usize>>,
+    // cols[j] is the length of the column j
+    cols: Vec<usize>,
+    m: usize,
+    first: bool,
+    done: bool,
+}
+
+impl SemistandardTableaux {
+    pub fn new(shape: &Partition, m: usize) -> SemistandardTableaux {
+        let parts = shape.parts();
+        let width = parts.first().map_or(0, |&w| { w });
+        SemistandardTableaux {
+            rows: parts.iter().map(|&l| { vec![0; l] }).collect(),
+            cols: (0..width).map(|j| { parts.iter().filter(|&&l| { l > j }).count() }).collect(),
+            m: m,
+            first: true,
+            done: false,
+        }
+    }
+
+    /// The current tableau, as its rows.
+    pub fn current(&self) -> &[Vec<usize>] {
+        &self.rows
+    }
+
+    // The smallest entry allowed at (i, j) by the cells before it
+    fn min_entry(&self, i: usize, j: usize) -> usize {
+        let left = if j > 0 { self.rows[i][j - 1] } else { 1 };
+        let above = if i > 0 { self.rows[i - 1][j] + 1 } else { 1 };
+        cmp::max(left, above)
+    }
+
+    // The largest entry at (i, j) leaving room for the cells below it
+    fn max_entry(&self, i: usize, j: usize) -> usize {
+        self.m + 1 + i - self.cols[j]
+    }
+
+    // Fill the cells after (i, j), in reading order, with their smallest
+    // entries
+    fn fill_after(&mut self, i: usize, j: usize) {
+        for j1 in (j + 1)..self.rows[i].len() {
+            self.rows[i][j1] = self.min_entry(i, j1);
+        }
+        for i1 in (i + 1)..self.rows.len() {
+            for j1 in 0..self.rows[i1].len() {
+                self.rows[i1][j1] = self.min_entry(i1, j1);
+            }
+        }
+    }
+}
+
+/// Iterate through the tableaux.
+///
+/// Calls to `.next()` after `None` has been output keep returning `None`.
+impl StreamingIterator for SemistandardTableaux {
+    type Item = [Vec<usize>];
+    fn next(&mut self) -> Option<&[Vec<usize>]> {
+        if self.done { return None }
+        if self.first {
+            self.first = false;
+            if self.rows.len() > self.m { self.done = true; return None }
+            for i in 0..self.rows.len() {
+                for j in 0..self.rows[i].len() {
+                    self.rows[i][j] = self.min_entry(i, j);
+                }
+            }
+            return Some(&self.rows)
+        }
+        // Increment the last entry which can be
+        for i in (0..self.rows.len()).rev() {
+            for j in (0..self.rows[i].len()).rev() {
+                if self.rows[i][j] < self.max_entry(i, j) {
+                    self.rows[i][j] += 1;
+                    self.fill_after(i, j);
+                    return Some(&self.rows)
+                }
+            }
+        }
+        self.done = true;
+        None
+    }
+
+    fn get(&self) -> Option<&[Vec<usize>]> {
+        if self.first || self.done { None } else { Some(&self.rows) }
+    }
+}
+
+impl_snapshot!(SemistandardTableaux);
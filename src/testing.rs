@@ -0,0 +1,151 @@
+//! Assertions for testing enumerations
+//!
+//! Every helper consumes an iterator, panics with a descriptive message if
+//! the property fails, and returns the number of items checked.
+//!
+//! `exhaustive_test!` defines a test checking a property of every item.
+
+use std::*;
+use std::hash::Hash;
+use iter::{StreamingIterator, Order};
+
+/// Assert that the items are strictly increasing in the given order (or
+/// strictly decreasing in lexicographic order, for `Order::RevLex`).
+///
+/// # Panics
+///
+/// If two consecutive items are out of order, or for
+/// `Order::GrayMinimalChange`, which does not compare items.
+pub fn assert_ordered<T, I>(mut iter: I, order: Order) -> usize where
+    T: Ord + Clone + fmt::Debug,
+    I: StreamingIterator<Item = [T]>
+{
+    let mut prev: Option<Vec<T>> = None;
+    let mut count = 0;
+    while let Some(x) = iter.next() {
+        if let Some(ref p) = prev {
+            let ok = match order {
+                Order::Lex => &p[..] < x,
+                Order::Colex => p.iter().rev().lt(x.iter().rev()),
+                Order::RevLex => &p[..] > x,
+                Order::GrayMinimalChange => panic!("Unsupported order {:?}", order),
+            };
+            assert!(ok, "Items {} and {} out of {:?} order: {:?}, {:?}",
+                    count - 1, count, order, p, x);
+        }
+        prev = Some(x.to_vec());
+        count += 1;
+    }
+    count
+}
+
+/// Assert that there are `expected` items.
+pub fn assert_count<I: StreamingIterator>(iter: I, expected: usize) -> usize {
+    let count = iter.count();
+    assert_eq!(count, expected, "Wrong number of items");
+    count
+}
+
+/// Assert that no item is repeated.
+///
+/// This keeps a copy of every item.
+pub fn assert_distinct<I>(mut iter: I) -> usize where
+    I: StreamingIterator,
+    I::Item: ToOwned,
+    <I::Item as ToOwned>::Owned: Hash + Eq + fmt::Debug
+{
+    let mut seen = collections::HashSet::new();
+    while let Some(x) = iter.next() {
+        let x = x.to_owned();
+        let count = seen.len();
+        assert!(!seen.contains(&x), "Item {} is a duplicate: {:?}", count, x);
+        seen.insert(x);
+    }
+    seen.len()
+}
+
+/// The result of a property checked by `assert_all`: a `bool`, `()` for
+/// properties which make their own assertions, or a `Result`.
+pub trait Outcome {
+    /// A description of the failure, if any.
+    fn failure(self) -> Option<String>;
+}
+
+impl Outcome for bool {
+    fn failure(self) -> Option<String> {
+        if self { None } else { Some("false".to_string()) }
+    }
+}
+
+impl Outcome for () {
+    fn failure(self) -> Option<String> {
+        None
+    }
+}
+
+impl<E: fmt::Debug> Outcome for Result<(), E> {
+    fn failure(self) -> Option<String> {
+        self.err().map(|e| { format!("{:?}", e) })
+    }
+}
+
+/// Assert that `property` holds for every item.
+///
+/// The first counterexample is reported with its rank, also when the
+/// property panics by itself, e.g., with `assert!`.
+pub fn assert_all<I, F, R>(iter: I, mut property: F) -> usize where
+    I: StreamingIterator,
+    I::Item: fmt::Debug,
+    F: FnMut(&I::Item) -> R,
+    R: Outcome
+{
+    let mut iter = iter.with_rank();
+    let mut count = 0;
+    while let Some((rank, x)) = iter.next() {
+        let report = Report { rank: rank, item: x };
+        let outcome = property(x);
+        mem::forget(report);
+        if let Some(failure) = outcome.failure() {
+            panic!("Counterexample at rank {}: {:?} ({})", rank, x, failure);
+        }
+        count += 1;
+    }
+    count
+}
+
+// Print the counterexample if the property panics
+struct Report<'a, T: ?Sized + fmt::Debug + 'a> {
+    rank: u128,
+    item: &'a T,
+}
+
+impl<'a, T: ?Sized + fmt::Debug> Drop for Report<'a, T> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            eprintln!("Counterexample at rank {}: {:?}", self.rank, self.item);
+        }
+    }
+}
+
+/// Define a test which checks a property of every item of a
+/// `StreamingIterator`, with `assert_all`, and optionally their number.
+///
+/// For example, `exhaustive_test!(trees, Catalan::new(6), |t| t[0] == 0);`
+/// or, with assertions in a block and the expected count,
+/// `exhaustive_test!(trees, Catalan::new(6), |t| { assert!(t.len() == 5) }, count: 42);`.
+#[macro_export]
+macro_rules! exhaustive_test {
+    ($name: ident, $iter: expr, |$x: pat| $property: expr) => {
+        #[test]
+        fn $name() {
+            $crate::testing::assert_all($iter, |$x| $property);
+        }
+    };
+    ($name: ident, $iter: expr, |$x: pat| $property: expr, count: $count: expr) => {
+        #[test]
+        fn $name() {
+            let count = $crate::testing::assert_all($iter, |$x| $property);
+            assert_eq!(count, $count, "Wrong number of items");
+        }
+    };
+}
@@ -0,0 +1,234 @@
+//! Typed views of the objects yielded by the iterators
+//!
+//! The iterators yield bare slices and masks, which are cheap to produce.
+//! These thin wrappers borrow them to give access to the operations which
+//! depend on what they represent, while still dereferencing to the slice (or
+//! mask), e.g., `view::PermutationRef::new(it.next().unwrap()).inversions()`.
+
+use std::*;
+use bits;
+use permutation::Permutation;
+
+/// A combination of elements of a source sequence of length `n`: the chosen
+/// elements, and their positions in the source, in increasing order.
+///
+/// Dereferences to the slice of the chosen elements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Combination<'a, T: 'a> {
+    items: &'a [T],
+    indices: &'a [usize],
+    n: usize,
+}
+
+impl<'a, T> Combination<'a, T> {
+    /// # Panics
+    ///
+    /// If `items` and `indices` have different lengths, or the indices are
+    /// not increasing and less than `n`.
+    pub fn new(items: &'a [T], indices: &'a [usize], n: usize) -> Combination<'a, T> {
+        assert_eq!(items.len(), indices.len(), "Items and indices of different lengths");
+        assert!(indices.windows(2).all(|w| { w[0] < w[1] }), "Indices not increasing");
+        assert!(indices.last().is_none_or(|&i| { i < n }), "Index out of range");
+        Combination { items: items, indices: indices, n: n }
+    }
+
+    /// The positions of the chosen elements in the source.
+    pub fn as_indices(&self) -> &'a [usize] {
+        self.indices
+    }
+
+    /// The length `n` of the source.
+    pub fn source_len(&self) -> usize {
+        self.n
+    }
+
+    /// Whether the element at position `i` of the source is chosen.
+    pub fn contains_index(&self, i: usize) -> bool {
+        self.indices.binary_search(&i).is_ok()
+    }
+
+    /// The positions of the elements which are not chosen, in increasing
+    /// order.
+    pub fn complement(&self) -> Vec<usize> {
+        let mut chosen = self.indices.iter().peekable();
+        (0..self.n).filter(|&i| {
+            if chosen.peek() == Some(&&i) { chosen.next(); false } else { true }
+        }).collect()
+    }
+
+    /// The elements of `src` which are not chosen.
+    ///
+    /// # Panics
+    ///
+    /// If `src` is not of length `n`.
+    pub fn complement_items<U: Clone>(&self, src: &[U]) -> Vec<U> {
+        assert_eq!(src.len(), self.n, "Slice of the wrong length");
+        self.complement().into_iter().map(|i| { src[i].clone() }).collect()
+    }
+
+    /// The positions as a mask.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is greater than 64.
+    pub fn to_mask(&self) -> SubsetMask {
+        SubsetMask::new(bits::indices_mask(self.indices), self.n)
+    }
+}
+
+impl<'a, T> ops::Deref for Combination<'a, T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        self.items
+    }
+}
+
+/// A subset of `0..n` for `n <= 64`, as the bits of a mask (see `bits`).
+///
+/// Dereferences to the mask.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SubsetMask {
+    mask: u64,
+    n: usize,
+}
+
+impl SubsetMask {
+    /// # Panics
+    ///
+    /// If `n` is greater than 64, or `mask` has bits set beyond the `n`
+    /// lowest ones.
+    pub fn new(mask: u64, n: usize) -> SubsetMask {
+        assert!(mask & !bits::full_mask(n) == 0, "Mask out of range");
+        SubsetMask { mask: mask, n: n }
+    }
+
+    /// # Panics
+    ///
+    /// If `n` is greater than 64, or an index is not less than `n`.
+    pub fn from_indices(indices: &[usize], n: usize) -> SubsetMask {
+        SubsetMask::new(bits::indices_mask(indices), n)
+    }
+
+    pub fn mask(&self) -> u64 {
+        self.mask
+    }
+
+    /// The size `n` of the set.
+    pub fn universe(&self) -> usize {
+        self.n
+    }
+
+    /// The number of elements.
+    pub fn len(&self) -> usize {
+        self.mask.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mask == 0
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        i < self.n && self.mask >> i & 1 == 1
+    }
+
+    /// The subset of the other elements of `0..n`.
+    pub fn complement(&self) -> SubsetMask {
+        SubsetMask { mask: !self.mask & bits::full_mask(self.n), n: self.n }
+    }
+
+    /// The elements, in increasing order.
+    pub fn as_indices(&self) -> bits::MaskIndices {
+        bits::mask_indices(self.mask)
+    }
+
+    /// The elements of `src` at the positions in the subset.
+    ///
+    /// # Panics
+    ///
+    /// If `src` is not of length `n`.
+    pub fn apply<T: Clone>(&self, src: &[T]) -> Vec<T> {
+        assert_eq!(src.len(), self.n, "Slice of the wrong length");
+        let mut dest = Vec::with_capacity(self.len());
+        bits::select_into(self.mask, src, &mut dest);
+        dest
+    }
+}
+
+impl ops::Deref for SubsetMask {
+    type Target = u64;
+    fn deref(&self) -> &u64 {
+        &self.mask
+    }
+}
+
+/// Format as a string of `n` bits, see `bits::bitstring`.
+impl fmt::Display for SubsetMask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", bits::bitstring(self.mask, self.n))
+    }
+}
+
+/// An arrangement of elements, e.g., yielded by `Permutations`, or a
+/// permutation of `0..n` in one-line notation, as yielded by
+/// `IndexPermutations`.
+///
+/// Dereferences to the slice of the elements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PermutationRef<'a, T: 'a> {
+    items: &'a [T],
+}
+
+impl<'a, T> PermutationRef<'a, T> {
+    pub fn new(items: &'a [T]) -> PermutationRef<'a, T> {
+        PermutationRef { items: items }
+    }
+}
+
+impl<'a, T: Ord> PermutationRef<'a, T> {
+    /// The permutation of `0..n` in the same relative order, equal elements
+    /// being ordered by position.
+    pub fn pattern(&self) -> Permutation {
+        let mut order: Vec<usize> = (0..self.items.len()).collect();
+        order.sort_by(|&i, &j| { self.items[i].cmp(&self.items[j]) });
+        Permutation::from_vec(order).unwrap().inverse()
+    }
+
+    /// The number of pairs of elements out of order.
+    pub fn inversions(&self) -> usize {
+        let items = self.items;
+        (0..items.len()).map(|j| {
+            items[..j].iter().filter(|&x| { *x > items[j] }).count()
+        }).sum()
+    }
+}
+
+impl<'a> PermutationRef<'a, usize> {
+    /// The permutation, if this is a permutation of `0..n`.
+    pub fn to_permutation(&self) -> Option<Permutation> {
+        Permutation::from_vec(self.items.to_vec())
+    }
+
+    /// Rearrange a slice as `Permutation::apply`: the element at position
+    /// `i` of the result is `src[self[i]]`.
+    ///
+    /// # Panics
+    ///
+    /// If the lengths differ.
+    pub fn apply<U: Clone>(&self, src: &[U]) -> Vec<U> {
+        assert_eq!(self.items.len(), src.len(), "Slice of the wrong length");
+        self.items.iter().map(|&j| { src[j].clone() }).collect()
+    }
+}
+
+impl<'a, T> ops::Deref for PermutationRef<'a, T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        self.items
+    }
+}
+
+impl<'a, T> From<&'a [T]> for PermutationRef<'a, T> {
+    fn from(items: &'a [T]) -> PermutationRef<'a, T> {
+        PermutationRef::new(items)
+    }
+}